@@ -19,6 +19,8 @@ This tool:
 import sys
 import os
 import subprocess
+import re
+import struct
 import json
 import requests
 import tarfile
@@ -236,6 +238,212 @@ def get_dependency_graph(package_ref, package_id, cache_path, profile):
     return None
 
 
+def find_package_node(dependency_graph, pkg_name):
+    """
+    Find the node of a package in a conan graph info/install JSON graph.
+
+    Returns:
+        Node dict, or None if the package isn't in the graph
+    """
+    if not dependency_graph:
+        return None
+    nodes = dependency_graph.get('graph', {}).get('nodes', {})
+    for node_id, node in nodes.items():
+        ref = node.get('ref') or ''
+        if ref.split('/', 1)[0] == pkg_name:
+            return node
+    return None
+
+
+def get_package_cpp_info(package_ref, profile):
+    """
+    Get the cpp_info of a package as computed by its package_info().
+
+    conan graph info doesn't run package_info(), so this installs the
+    package (already in the cache, nothing is built) to get it.
+
+    Returns:
+        Serialized cpp_info dict ({"root": {...}, "<component>": {...}}), or None
+    """
+    cmd = ['conan', 'install', f'--requires={package_ref}', '--format=json', '-pr', profile]
+    output = run_conan_command(cmd)
+    if not output:
+        return None
+
+    try:
+        json_start = output.find('{')
+        if json_start == -1:
+            return None
+        graph = json.loads(output[json_start:])
+    except Exception as e:
+        print(f"Warning: Could not parse conan install JSON: {e}")
+        return None
+
+    node = find_package_node(graph, package_ref.split('/', 1)[0])
+    return node.get('cpp_info') if node else None
+
+
+def order_components(components):
+    """
+    Order cpp_info components for linking: each component before the
+    components it requires, as static linking needs.
+
+    Args:
+        components: Dict of component name -> serialized cpp_info
+
+    Returns:
+        List of component names
+    """
+    ordered = []
+    visiting = set()
+
+    def visit(name):
+        if name in ordered or name in visiting:
+            return
+        visiting.add(name)
+        for required in components[name].get('requires') or []:
+            # "other::component" requirements belong to other packages
+            if '::' not in required and required in components:
+                visit(required)
+        ordered.append(name)
+
+    for name in components:
+        visit(name)
+
+    # Post-order lists requirements first; linking needs them last
+    return list(reversed(ordered))
+
+
+def extract_link_info(cpp_info):
+    """
+    Extract link information from a serialized cpp_info.
+
+    Args:
+        cpp_info: {"root": {...}, "<component>": {...}} from conan's JSON output
+
+    Returns:
+        Dict with 'libs', 'system_libs', 'frameworks' and 'defines' (each in
+        link order, without duplicates), or None if cpp_info declares nothing
+        to link
+    """
+    if not cpp_info:
+        return None
+
+    components = {name: info for name, info in cpp_info.items() if name != 'root' and isinstance(info, dict)}
+    sections = [cpp_info.get('root') or {}] + [components[name] for name in order_components(components)]
+
+    link_info = {'libs': [], 'system_libs': [], 'frameworks': [], 'defines': []}
+    for section in sections:
+        for key in link_info:
+            for value in section.get(key) or []:
+                if value not in link_info[key]:
+                    link_info[key].append(value)
+
+    if not link_info['libs'] and not link_info['system_libs'] and not link_info['frameworks']:
+        return None
+    return link_info
+
+
+def is_header_only_node(node):
+    """
+    Check if a graph node is a header-only or interface package (nothing to link).
+
+    Uses the recipe's package_type, or a cpp_info that declares no libraries,
+    system libraries or frameworks. Nodes without either aren't header-only.
+    """
+    if node.get('package_type') == 'header-library':
+        return True
+    return node.get('cpp_info') is not None and extract_link_info(node['cpp_info']) is None
+
+
+def link_manifest(libraries, link_info=None):
+    """
+    Build the lines of libs.txt (cargo rustc-link-lib values, in link order).
+
+    With cpp_info, the libraries are linked in the order package_info()
+    declares them, followed by system libraries and frameworks. Without it,
+    every library found in lib/ is linked in directory order.
+
+    Args:
+        libraries: List of (lib_name, lib_file, kind) from find_libraries()
+        link_info: Dict from extract_link_info(), or None
+
+    Returns:
+        List of lines like "static=foo", "dylib=pthread", "framework=Security"
+    """
+    if not link_info:
+        return [f"{kind}={lib_name}" for lib_name, _, kind in libraries]
+
+    kinds = {lib_name: kind for lib_name, _, kind in libraries}
+    lines = []
+    for lib_name in link_info['libs']:
+        # Libraries outside lib/ are left for the linker to resolve
+        kind = kinds.get(lib_name)
+        lines.append(f"{kind}={lib_name}" if kind else lib_name)
+    for lib_name in link_info['system_libs']:
+        lines.append(f"dylib={lib_name}")
+    for framework in link_info['frameworks']:
+        lines.append(f"framework={framework}")
+    return lines
+
+
+def cxx_runtime_libs(settings):
+    """
+    Get the C++ standard library a binary was built against, as libs.txt lines.
+
+    Static libraries don't record their dependencies, so a crate bundling
+    C++ static libraries has to link the C++ runtime itself. It's derived from
+    the binary's compiler.libcxx (gcc, clang, apple-clang, Android NDK) or,
+    for MSVC, compiler.runtime and compiler.runtime_type. C libraries whose
+    recipe removes compiler.libcxx get nothing.
+
+    Args:
+        settings: Dict from read_conaninfo_settings()
+
+    Returns:
+        List of lines like "stdc++", "c++" or "msvcprt"
+    """
+    libcxx = settings.get('compiler.libcxx')
+    if libcxx in ('libstdc++', 'libstdc++11'):
+        return ['stdc++']
+    if libcxx == 'libc++':
+        return ['c++']
+    if libcxx == 'c++_shared':
+        return ['c++_shared']
+    if libcxx == 'c++_static':
+        return ['c++_static', 'c++abi']
+
+    # MSVC: runtime static/dynamic (msvc) or MT/MTd/MD/MDd (Visual Studio, Conan 1)
+    runtime = settings.get('compiler.runtime')
+    if settings.get('compiler') not in ('msvc', 'Visual Studio') or not runtime:
+        return []
+    debug = settings.get('compiler.runtime_type') == 'Debug' or runtime in ('MTd', 'MDd')
+    static = runtime in ('static', 'MT', 'MTd')
+    return [f"{'libcpmt' if static else 'msvcprt'}{'d' if debug else ''}"]
+
+
+def links_env_var(links, key):
+    """
+    Get the environment variable through which cargo passes a build script
+    metadata key of a dependency with links = "<links>" (e.g., DEP_ZLIB_INCLUDE).
+    """
+    return f"DEP_{links.upper().replace('-', '_')}_{key.upper()}"
+
+
+def sys_env_prefix(pkg_name):
+    """
+    Get the prefix of the environment variables read by a generated build.rs
+    (e.g., "libjpeg-turbo" -> "LIBJPEG_TURBO_SYS" for LIBJPEG_TURBO_SYS_LIB_DIR).
+    """
+    return re.sub(r'[^A-Z0-9]', '_', pkg_name.upper()) + '_SYS'
+
+
+def pkg_config_name(cpp_info, pkg_name):
+    """Get the pkg-config module of a package (its pkg_config_name property, or its name)."""
+    root = (cpp_info or {}).get('root') or {}
+    return (root.get('properties') or {}).get('pkg_config_name') or pkg_name
+
+
 def create_binary_tarball(package_ref, package_id, output_path):
     """
     Create a .tgz using conan cache save (proper Conan format).
@@ -850,214 +1058,854 @@ def parse_conan_profile(profile_name):
         return None
 
 
-def cmd_download_rust_crates(args):
-    """Download Rust crates with dependencies"""
-    package_ref = args.package_ref
-    server_url = args.server or "http://localhost:8000"
-
-    if '/' not in package_ref:
-        print(f"Error: Invalid package reference. Use format: package_name/version")
-        return 1
-
-    package_name, version = package_ref.split('/', 1)
+def find_binary_for_profile(server_url, package_name, version, profile):
+    """
+    Find the binary of a package version matching a Conan profile.
 
-    # Use profile to query the API for the matching binary
-    print(f"Using profile '{args.profile}' to find matching binary...")
-    profile_settings = parse_conan_profile(args.profile)
+    Returns:
+        package_id, or None (after printing why) if there's no match
+    """
+    print(f"Using profile '{profile}' to find matching binary...")
+    profile_settings = parse_conan_profile(profile)
     if not profile_settings:
-        print(f"Error: Could not parse profile '{args.profile}'")
-        return 1
+        print(f"Error: Could not parse profile '{profile}'")
+        return None
 
     # Query the API with profile settings
     query_url = f"{server_url}/api/packages/{package_name}/{version}/rust-crate"
     try:
         response = requests.get(query_url, params=profile_settings)
         if response.status_code == 404:
-            print(f"Error: No binary found matching profile '{args.profile}'")
+            print(f"Error: No binary found matching profile '{profile}'")
             print(f"\nProfile settings:")
             for key, value in profile_settings.items():
                 print(f"  {key}: {value}")
-            return 1
+            return None
         response.raise_for_status()
-        data = response.json()
-        package_id = data['package']['package_id']
+        package_id = response.json()['package']['package_id']
         print(f"  Found matching binary: {package_id[:8]}...")
+        return package_id
     except Exception as e:
         print(f"Error querying server with profile: {e}")
-        return 1
+        return None
 
-    output_dir = args.output or './rust_crates'
 
+def download_crate_graph(server_url, package_name, version, package_id, output_dir):
+    """
+    Download the Rust crate of a binary and the crates of its dependencies.
+
+    Returns:
+        List of (crate_name, crate_version, crate_file), the requested crate
+        first, or None if the requested crate couldn't be downloaded.
+        Unavailable dependency crates are skipped with a warning.
+    """
     import os
     os.makedirs(output_dir, exist_ok=True)
 
-    print("\nConanCrates Rust Crate Download")
-    print("=" * 60)
-    print(f"Package: {package_name}/{version}")
-    print(f"Package ID: {package_id}")
-    print(f"Server: {server_url}")
-    print(f"Output: {output_dir}")
-    print("=" * 60)
-    print()
-
     # Get package info
     print("1. Querying package information...")
     info_url = f"{server_url}/api/packages/{package_name}/{version}/binaries/{package_id}/info"
-    
+
     try:
         response = requests.get(info_url)
         response.raise_for_status()
         package_info = response.json()
     except Exception as e:
         print(f"  Error: {e}")
-        return 1
-    
+        return None
+
     dependencies = package_info.get('dependencies', [])
     print(f"  Found {len(dependencies)} dependencies")
     print()
-    
+
     # Download main crate
     print("2. Downloading requested crate...")
     crate_name = f"{package_name.replace('_', '-')}-sys"
+    crate_version = package_info.get('rust_crate', {}).get('cargo_version') or cargo_version(version)
     crate_url = f"{server_url}/packages/{package_name}/{version}/binaries/{package_id}/rust-crate/"
-    
+
     try:
         response = requests.get(crate_url)
         if response.status_code == 404:
             print(f"  Error: Rust crate not available")
-            return 1
+            return None
         response.raise_for_status()
-        
-        crate_file = os.path.join(output_dir, f"{crate_name}-{version}.crate")
+
+        crate_file = os.path.join(output_dir, f"{crate_name}-{crate_version}.crate")
         with open(crate_file, 'wb') as f:
             f.write(response.content)
-        
+
         size_kb = len(response.content) / 1024
-        print(f"  Downloaded {crate_name}-{version}.crate ({size_kb:.1f} KB)")
-        downloaded = [crate_file]
+        print(f"  Downloaded {crate_name}-{crate_version}.crate ({size_kb:.1f} KB)")
+        downloaded = [(crate_name, crate_version, crate_file)]
     except Exception as e:
         print(f"  Error: {e}")
-        return 1
-    
+        return None
+
     # Download dependencies
     if dependencies:
         print(f"\n3. Downloading {len(dependencies)} dependencies...")
         for dep in dependencies:
             dep_name = dep['name']
             dep_version = dep['version']
+            dep_crate_version = dep.get('cargo_version') or cargo_version(dep_version)
             dep_package_id = dep['package_id']
             dep_crate_name = f"{dep_name.replace('_', '-')}-sys"
             dep_url = f"{server_url}/packages/{dep_name}/{dep_version}/binaries/{dep_package_id}/rust-crate/"
-            
+
             try:
                 response = requests.get(dep_url)
                 if response.status_code == 404:
                     print(f"  Warning: {dep_crate_name} not available")
                     continue
                 response.raise_for_status()
-                
-                dep_file = os.path.join(output_dir, f"{dep_crate_name}-{dep_version}.crate")
+
+                dep_file = os.path.join(output_dir, f"{dep_crate_name}-{dep_crate_version}.crate")
                 with open(dep_file, 'wb') as f:
                     f.write(response.content)
-                
+
                 size_kb = len(response.content) / 1024
-                print(f"  Downloaded {dep_crate_name}-{dep_version}.crate ({size_kb:.1f} KB)")
-                downloaded.append(dep_file)
+                print(f"  Downloaded {dep_crate_name}-{dep_crate_version}.crate ({size_kb:.1f} KB)")
+                downloaded.append((dep_crate_name, dep_crate_version, dep_file))
             except Exception as e:
                 print(f"  Warning: Failed to download {dep_crate_name}: {e}")
 
+    return downloaded
+
+
+def cmd_download_rust_crates(args):
+    """Download Rust crates with dependencies"""
+    package_ref = args.package_ref
+    server_url = args.server or "http://localhost:8000"
+
+    if '/' not in package_ref:
+        print(f"Error: Invalid package reference. Use format: package_name/version")
+        return 1
+
+    package_name, version = package_ref.split('/', 1)
+
+    # Use profile to query the API for the matching binary
+    package_id = find_binary_for_profile(server_url, package_name, version, args.profile)
+    if not package_id:
+        return 1
+
+    output_dir = args.output or './rust_crates'
+
+    import os
+
+    print("\nConanCrates Rust Crate Download")
+    print("=" * 60)
+    print(f"Package: {package_name}/{version}")
+    print(f"Package ID: {package_id}")
+    print(f"Server: {server_url}")
+    print(f"Output: {output_dir}")
+    print("=" * 60)
+    print()
+
+    downloaded = download_crate_graph(server_url, package_name, version, package_id, output_dir)
+    if downloaded is None:
+        return 1
+    crate_name, crate_version, _ = downloaded[0]
+
     print("\n" + "=" * 60)
     print(f"Downloaded {len(downloaded)} crate(s) to {output_dir}")
     print("=" * 60)
     print()
     print("Usage:")
     print(f"  1. Extract crates in {output_dir}")
-    for crate in downloaded:
-        print(f"     tar -xzf {os.path.basename(crate)}")
+    for _, _, crate_file in downloaded:
+        print(f"     tar -xzf {os.path.basename(crate_file)}")
     print()
     print(f"  2. Add to your Cargo.toml:")
-    print(f"     [{crate_name}]")
-    print(f'     version = "{version}"')
-    print(f'     path = "{output_dir}/{crate_name}"')
+    print(f"     [dependencies]")
+    print(f'     {crate_name} = {{ version = "{version_requirement(crate_version)}", path = "{output_dir}/{crate_name}-{crate_version}" }}')
+    if len(downloaded) > 1:
+        print()
+        print("  3. Patch in the dependency crates:")
+        print("     [patch.crates-io]")
+        for dep_crate_name, dep_crate_version, _ in downloaded[1:]:
+            print(f'     {dep_crate_name} = {{ path = "{output_dir}/{dep_crate_name}-{dep_crate_version}" }}')
     print()
-    
+    print(f"Or let `conancrates cargo-setup {package_ref} -pr {args.profile}` set up the project.")
+    print()
+
     return 0
 
 
-def cmd_download(args):
+# Name of the directory source with the vendored crates (`cargo-setup --mode source`)
+VENDOR_SOURCE_NAME = 'conancrates-vendor'
+
+# Name of the ConanCrates registry in .cargo/config.toml (`cargo-setup --mode registry`)
+REGISTRY_NAME = 'conancrates'
+
+
+def unpack_crate(crate_file, vendor_dir):
     """
-    Download package and dependencies from ConanCrates and restore to local Conan cache.
-    Acts like 'conan install' - checks cache and only downloads missing packages.
+    Unpack a .crate archive into vendor_dir/<crate>-<version>/ the way cargo vendor does.
+
+    Writes the .cargo-checksum.json cargo requires in directory sources
+    (SHA256 of every file and of the .crate). An existing directory is replaced.
+
+    Returns:
+        Path of the crate directory
     """
-    # Handle Rust crate download
-    if args.crates:
-        return cmd_download_rust_crates(args)
-    
-    import zipfile
-    import tarfile
-    import platform
+    import hashlib
+    import io
+    import shutil
+    vendor_dir = Path(vendor_dir)
+    crate_bytes = Path(crate_file).read_bytes()
+
+    with tarfile.open(fileobj=io.BytesIO(crate_bytes), mode='r:gz') as tar:
+        roots = {member.name.split('/', 1)[0] for member in tar.getmembers()}
+        if len(roots) != 1:
+            raise ValueError(f"{crate_file} doesn't have a single <crate>-<version>/ directory")
+        crate_dir = vendor_dir / roots.pop()
+        if crate_dir.exists():
+            shutil.rmtree(crate_dir)
+        vendor_dir.mkdir(parents=True, exist_ok=True)
+        tar.extractall(vendor_dir, filter='data')
+
+    files = {
+        path.relative_to(crate_dir).as_posix(): hashlib.sha256(path.read_bytes()).hexdigest()
+        for path in sorted(crate_dir.rglob('*')) if path.is_file()
+    }
+    checksum = {'files': files, 'package': hashlib.sha256(crate_bytes).hexdigest()}
+    (crate_dir / '.cargo-checksum.json').write_text(json.dumps(checksum, indent=2) + '\n')
+    return crate_dir
+
+
+def cargo_config_entries(mode, crate_dirs, vendor_dir, server_url):
+    """
+    Build the .cargo/config.toml entries for vendored crates or the registry.
 
-    package_ref = args.package_ref
-    server_url = args.server or "http://localhost:8000"
+    Args:
+        mode: "patch" ([patch.crates-io] path entries), "source" (crates-io
+              replaced by the vendor directory) or "registry" (registry definition)
+        crate_dirs: {crate_name: crate directory}, relative to the project
+        vendor_dir: Vendor directory, relative to the project
+        server_url: ConanCrates server URL
 
-    print(f"ConanCrates Download")
-    print(f"{'='*60}")
-    print(f"Package: {package_ref}")
-    print(f"Server: {server_url}")
-    print(f"{'='*60}")
+    Returns:
+        Dict of tables to merge into the config
+    """
+    if mode == 'patch':
+        return {'patch': {'crates-io': {
+            crate_name: {'path': Path(crate_dir).as_posix()} for crate_name, crate_dir in crate_dirs.items()
+        }}}
+    if mode == 'source':
+        return {'source': {
+            'crates-io': {'replace-with': VENDOR_SOURCE_NAME},
+            VENDOR_SOURCE_NAME: {'directory': Path(vendor_dir).as_posix()},
+        }}
+    return {'registries': {REGISTRY_NAME: {'index': f"sparse+{server_url.rstrip('/')}/cargo/index/"}}}
+
+
+# Rust target_os -> rpath of the executable's own directory
+RPATH_ORIGINS = {
+    'linux': '$ORIGIN',
+    'freebsd': '$ORIGIN',
+    'macos': '@loader_path',
+}
+
+
+def rust_target_os(rust_target):
+    """Get the target_os of a Rust target triple (e.g. "linux"), or None."""
+    if not rust_target:
+        return None
+    if rust_target.endswith('-apple-darwin'):
+        return 'macos'
+    return next((target_os for target_os in RPATH_ORIGINS if f"-{target_os}" in rust_target), None)
 
-    # Parse package reference
-    if '/' not in package_ref:
-        print(f"Error: Invalid package reference. Expected format: package/version")
-        return 1
 
-    package_name, version = package_ref.split('/', 1)
+def rpath_config_entries(config_path, rust_target):
+    """
+    Build the .cargo/config.toml entries giving executables an rpath to their
+    own directory, where the -sys crates' build.rs copies shared libraries.
 
-    # Get platform settings from profile or auto-detect
-    profile_name = getattr(args, 'profile', None)
+    The flags are added to the rustflags of the [target.'cfg(target_os = ...)']
+    table already in the config, if any.
 
-    if profile_name:
-        # Use settings from Conan profile
-        print(f"\nUsing Conan profile: {profile_name}")
-        settings = parse_conan_profile(profile_name)
-        if not settings:
-            print(f"Error: Could not parse profile '{profile_name}'")
-            return 1
+    Args:
+        config_path: Path of .cargo/config.toml
+        rust_target: Target triple of the binaries (e.g. "x86_64-unknown-linux-gnu")
 
-        os_name = settings['os']
-        arch = settings['arch']
-        compiler = settings['compiler']
-        compiler_version = settings['compiler_version']
-        build_type = settings['build_type']
-    else:
-        # Auto-detect platform settings (use defaults that match most common builds)
-        print(f"\nNo profile specified, auto-detecting platform...")
-        system = platform.system()
-        if system == "Windows":
-            os_name = "Windows"
-            # Default to gcc for now (MinGW/MSYS2 is common for Conan on Windows)
-            compiler = "gcc"
-            compiler_version = "11"
-        elif system == "Linux":
-            os_name = "Linux"
-            compiler = "gcc"
-            compiler_version = "11"
-        elif system == "Darwin":
-            os_name = "Macos"
-            compiler = "apple-clang"
-            compiler_version = "14"
+    Returns:
+        Dict of tables to merge into the config (empty for targets without rpaths)
+    """
+    import tomllib
+    target_os = rust_target_os(rust_target)
+    if target_os is None:
+        return {}
+    cfg = f'cfg(target_os = "{target_os}")'
+    config_path = Path(config_path)
+    config = tomllib.loads(config_path.read_text(encoding='utf-8')) if config_path.exists() else {}
+    rustflags = list(config.get('target', {}).get(cfg, {}).get('rustflags', []))
+    flag = f"link-arg=-Wl,-rpath,{RPATH_ORIGINS[target_os]}"
+    if flag not in rustflags:
+        rustflags += ['-C', flag]
+    return {'target': {cfg: {'rustflags': rustflags}}}
+
+
+def merge_tables(config, entries):
+    """Merge entries into a parsed TOML document (nested tables are merged, values replaced)."""
+    for key, value in entries.items():
+        if isinstance(value, dict) and isinstance(config.get(key), dict):
+            merge_tables(config[key], value)
         else:
-            os_name = "Linux"
-            compiler = "gcc"
-            compiler_version = "11"
+            config[key] = value
+    return config
 
-        arch = platform.machine().lower()
-        if arch in ("amd64", "x86_64"):
-            arch = "x86_64"
-        elif arch in ("arm64", "aarch64"):
-            arch = "armv8"
 
-        build_type = "Release"
+def update_cargo_config(config_path, entries):
+    """
+    Merge entries into .cargo/config.toml, creating it if needed.
+
+    Existing settings are kept, but the file is rewritten, so comments are lost.
+    """
+    import tomllib
+    config_path = Path(config_path)
+    config = tomllib.loads(config_path.read_text(encoding='utf-8')) if config_path.exists() else {}
+    merge_tables(config, entries)
+
+    lines = []
+    for key, value in config.items():
+        if isinstance(value, dict):
+            toml_table([key], value, lines)
+        elif is_table_array(value):
+            toml_table_array([key], value, lines)
+        else:
+            lines.insert(0, f"{toml_key(key)} = {toml_value(value)}")
+    config_path.parent.mkdir(parents=True, exist_ok=True)
+    config_path.write_text('\n'.join(lines).rstrip('\n') + '\n', encoding='utf-8')
+
+
+def add_cargo_dependency(cargo_toml_path, crate_name, spec):
+    """
+    Add a dependency to the [dependencies] table of a Cargo.toml.
+
+    The line is inserted textually so the rest of the manifest is untouched.
+
+    Returns:
+        True if it was added, False if the manifest already has the crate
+    """
+    import tomllib
+    cargo_toml_path = Path(cargo_toml_path)
+    content = cargo_toml_path.read_text(encoding='utf-8')
+    if crate_name in tomllib.loads(content).get('dependencies', {}):
+        return False
+
+    line = f"{crate_name} = {toml_value(spec)}\n"
+    match = re.search(r'^\[dependencies\][ \t]*(#.*)?\n', content, flags=re.MULTILINE)
+    if match:
+        content = content[:match.end()] + line + content[match.end():]
+    else:
+        content = content.rstrip('\n') + '\n\n[dependencies]\n' + line
+    cargo_toml_path.write_text(content, encoding='utf-8')
+    return True
+
+
+def cmd_cargo_setup(args):
+    """
+    Set up a Rust project to use a package's crate: download the crate graph
+    of the binary matching the profile, vendor it, and write .cargo/config.toml.
+    """
+    import os
+    package_ref = args.package_ref
+    server_url = args.server or "http://localhost:8000"
+    mode = args.mode
+
+    if '/' not in package_ref:
+        print(f"Error: Invalid package reference. Use format: package_name/version")
+        return 1
+
+    package_name, version = package_ref.split('/', 1)
+    project_dir = Path(args.project_dir)
+    vendor_dir = Path(args.vendor_dir)
+
+    print("ConanCrates Cargo Setup")
+    print("=" * 60)
+    print(f"Package: {package_ref}")
+    print(f"Project: {project_dir}")
+    print(f"Mode: {mode}")
+    print("=" * 60)
+    print()
+
+    package_id = find_binary_for_profile(server_url, package_name, version, args.profile)
+    if not package_id:
+        return 1
+    print()
+    try:
+        info = fetch_binary_info(server_url, package_name, version, package_id) or {}
+    except requests.RequestException as e:
+        print(f"Error querying package information: {e}")
+        return 1
+    rust_target = info.get('rust_crate', {}).get('rust_target')
+
+    crate_name = f"{package_name.replace('_', '-')}-sys"
+    if mode == 'registry':
+        # Cargo resolves and downloads the crates from the sparse index itself,
+        # which serves one crate per version: it has to be built for the profile
+        try:
+            problems = registry_coverage_problems(server_url, info, rust_target)
+        except requests.RequestException as e:
+            print(f"Error querying package information: {e}")
+            return 1
+        if problems:
+            print(f"✗ Error: The registry's crates don't cover this profile's target ({rust_target or 'unknown'}):")
+            for problem in problems:
+                print(f"  {problem}")
+            print(f"\nMerge the versions' crates (conancrates merge-rust-crates), or use --mode patch")
+            print(f"to vendor the crates of this profile's binaries")
+            return 1
+        crate_version = cargo_version(version)
+        dependency = {'version': version_requirement(crate_version), 'registry': REGISTRY_NAME}
+        crate_dirs = {}
+    else:
+        with tempfile.TemporaryDirectory() as download_dir:
+            downloaded = download_crate_graph(server_url, package_name, version, package_id, download_dir)
+            if downloaded is None:
+                return 1
+
+            print(f"\nUnpacking into {project_dir / vendor_dir}...")
+            crate_dirs = {}
+            for dep_crate_name, dep_crate_version, crate_file in downloaded:
+                try:
+                    crate_dir = unpack_crate(crate_file, project_dir / vendor_dir)
+                except (tarfile.TarError, ValueError) as e:
+                    print(f"  Error: Could not unpack {os.path.basename(crate_file)}: {e}")
+                    return 1
+                crate_dirs[dep_crate_name] = crate_dir.relative_to(project_dir)
+                print(f"  {crate_dirs[dep_crate_name].as_posix()}")
+        crate_version = downloaded[0][1]
+        dependency = version_requirement(crate_version)
+
+    configure_cargo_project(project_dir, vendor_dir, mode, server_url, crate_dirs, {crate_name: dependency}, rust_target)
+    return 0
+
+
+def registry_coverage_problems(server_url, info, rust_target):
+    """
+    Check that the crates the registry serves for a binary and its dependencies
+    are built for its target: the index serves the crate of one binary per
+    version, or the merged multi-target crate (the info API's registry_targets).
+
+    Args:
+        server_url: ConanCrates server URL
+        info: Info API response of the binary
+        rust_target: Target triple of the binary
+
+    Returns:
+        List of problems, one line per crate not covering rust_target
+    """
+    crates = [(info.get('rust_crate', {}), info.get('package', {}).get('name'), info.get('package', {}).get('version'))]
+    for dep in info.get('dependencies', []):
+        dep_info = fetch_binary_info(server_url, dep['name'], dep['version'], dep['package_id']) or {}
+        crates.append((dep_info.get('rust_crate', {}), dep['name'], dep['version']))
+
+    problems = []
+    for rust_crate, package_name, version in crates:
+        targets = rust_crate.get('registry_targets') or []
+        if not rust_target or rust_target not in targets:
+            served = ', '.join(targets) if targets else 'no crate'
+            problems.append(f"{rust_crate.get('crate_name') or package_name} {version}: the index serves {served}")
+    return problems
+
+
+def configure_cargo_project(project_dir, vendor_dir, mode, server_url, crate_dirs, dependencies, rust_target=None):
+    """
+    Write .cargo/config.toml for vendored crates (or the registry) and add
+    dependencies ({crate_name: spec}) to the project's Cargo.toml.
+
+    For Linux, FreeBSD and macOS targets, executables also get an rpath to
+    their own directory (rpath_config_entries()), so they find the shared
+    libraries of the -sys crates outside cargo too.
+    """
+    config_path = project_dir / '.cargo' / 'config.toml'
+    entries = cargo_config_entries(mode, crate_dirs, vendor_dir, server_url)
+    rpath_entries = rpath_config_entries(config_path, rust_target)
+    merge_tables(entries, rpath_entries)
+    update_cargo_config(config_path, entries)
+    print(f"\n✓ Wrote {config_path}")
+    if rpath_entries:
+        print(f"  Executables get an rpath to their own directory ({RPATH_ORIGINS[rust_target_os(rust_target)]}),")
+        print(f"  where build.rs copies shared libraries: ship those next to the executables")
+
+    cargo_toml = project_dir / 'Cargo.toml'
+    for crate_name, dependency in dependencies.items():
+        spec_line = f"{crate_name} = {toml_value(dependency)}"
+        if not cargo_toml.exists():
+            print(f"\nNo Cargo.toml in {project_dir}: add to [dependencies] once the project exists:")
+            print(f"  {spec_line}")
+        elif add_cargo_dependency(cargo_toml, crate_name, dependency):
+            print(f"✓ Added {spec_line} to {cargo_toml}")
+        else:
+            print(f"  {cargo_toml} already depends on {crate_name}, not changed")
+
+    if mode == 'source':
+        print(f"\nNote: crates.io is replaced by {vendor_dir.as_posix()}, so other crates.io")
+        print(f"dependencies have to be vendored there too (cargo vendor).")
+    print(f"\nNext: cargo build")
+
+
+# Lockfile written by `conancrates lock` and read by `conancrates install`
+LOCKFILE_NAME = 'conancrates.lock'
+LOCKFILE_VERSION = 1
+
+
+class LockfileError(Exception):
+    """Exception raised when a lockfile can't be read or doesn't match the server"""
+    pass
+
+
+def fetch_binary_info(server_url, package_name, version, package_id):
+    """
+    Query the server's info API for a binary.
+
+    Returns:
+        The info JSON, or None if the server doesn't have the binary
+    """
+    url = f"{server_url}/api/packages/{package_name}/{version}/binaries/{package_id}/info"
+    response = requests.get(url)
+    if response.status_code == 404:
+        return None
+    response.raise_for_status()
+    return response.json()
+
+
+def download_crate_bytes(server_url, package_name, version, package_id):
+    """Download the .crate of a binary (None if it has none)."""
+    url = f"{server_url}/packages/{package_name}/{version}/binaries/{package_id}/rust-crate/"
+    response = requests.get(url)
+    if response.status_code == 404:
+        return None
+    response.raise_for_status()
+    return response.content
+
+
+def lock_entry(server_url, package_name, version, package_id):
+    """
+    Lock the crate of a binary.
+
+    The sha256 comes from the server; crates uploaded before it recorded
+    checksums are downloaded and hashed.
+
+    Returns:
+        Dict with the crate's name, version, Conan package reference,
+        package_id, recipe_revision, sha256 and target (if the binary maps
+        to a Rust target triple)
+
+    Raises:
+        LockfileError: If the server has no crate for the binary
+    """
+    import hashlib
+    info = fetch_binary_info(server_url, package_name, version, package_id)
+    if info is None or not info['rust_crate'].get('available'):
+        raise LockfileError(f"{package_name}/{version}:{package_id} has no Rust crate on the server")
+
+    sha256 = info['rust_crate'].get('sha256')
+    if not sha256:
+        crate_bytes = download_crate_bytes(server_url, package_name, version, package_id)
+        if crate_bytes is None:
+            raise LockfileError(f"{package_name}/{version}:{package_id} has no Rust crate on the server")
+        sha256 = hashlib.sha256(crate_bytes).hexdigest()
+
+    entry = {
+        'name': info['rust_crate']['crate_name'],
+        'version': info['rust_crate']['cargo_version'],
+        'package': f"{package_name}/{version}",
+        'package_id': package_id,
+        'recipe_revision': info['package'].get('recipe_revision') or '',
+        'sha256': sha256,
+    }
+    if info['rust_crate'].get('rust_target'):
+        entry['target'] = info['rust_crate']['rust_target']
+    return entry
+
+
+def resolve_lock(server_url, package_refs, profile):
+    """
+    Resolve packages to the binaries matching a profile and lock the crates
+    of those binaries and of all their dependencies.
+
+    Returns:
+        Lock entries sorted by crate name
+
+    Raises:
+        LockfileError: If a package has no matching binary, or two packages
+                       need different binaries of the same dependency
+    """
+    entries = {}
+    for package_ref in package_refs:
+        package_name, version = package_ref.split('/', 1)
+        package_id = find_binary_for_profile(server_url, package_name, version, profile)
+        if not package_id:
+            raise LockfileError(f"No binary of {package_ref} matches profile '{profile}'")
+
+        info = fetch_binary_info(server_url, package_name, version, package_id) or {}
+        binaries = [(package_name, version, package_id)]
+        binaries.extend((dep['name'], dep['version'], dep['package_id']) for dep in info.get('dependencies', []))
+        for binary in binaries:
+            entry = lock_entry(server_url, *binary)
+            locked = entries.get(entry['name'])
+            if locked and (locked['package'], locked['package_id']) != (entry['package'], entry['package_id']):
+                raise LockfileError(
+                    f"{entry['name']} is needed as {locked['package']}:{locked['package_id']} "
+                    f"and as {entry['package']}:{entry['package_id']}"
+                )
+            entries[entry['name']] = entry
+    return [entries[name] for name in sorted(entries)]
+
+
+def write_lockfile(lockfile_path, requires, crates):
+    """Write a lockfile: the requested package references and the locked crates."""
+    lines = [
+        "# This file is generated by `conancrates lock`. Do not edit it by hand.",
+        f"version = {LOCKFILE_VERSION}",
+        f"requires = {toml_value(sorted(requires))}",
+    ]
+    for crate in crates:
+        lines.extend(['', '[[crate]]'])
+        lines.extend(f"{toml_key(key)} = {toml_value(value)}" for key, value in crate.items())
+    Path(lockfile_path).write_text('\n'.join(lines) + '\n', encoding='utf-8')
+
+
+def read_lockfile(lockfile_path):
+    """
+    Read a lockfile.
+
+    Returns:
+        Tuple of (requested package references, locked crates)
+
+    Raises:
+        LockfileError: If the file isn't a lockfile this CLI understands
+    """
+    import tomllib
+    try:
+        lock = tomllib.loads(Path(lockfile_path).read_text(encoding='utf-8'))
+    except (OSError, tomllib.TOMLDecodeError) as e:
+        raise LockfileError(f"Could not read {lockfile_path}: {e}")
+    if lock.get('version') != LOCKFILE_VERSION:
+        raise LockfileError(f"{lockfile_path} has unsupported version {lock.get('version')!r}")
+
+    required = ('name', 'version', 'package', 'package_id', 'sha256')
+    for crate in lock.get('crate', []):
+        missing = [key for key in required if not crate.get(key)]
+        if missing:
+            raise LockfileError(f"{lockfile_path}: crate {crate.get('name', '?')} has no {', '.join(missing)}")
+    return lock.get('requires', []), lock.get('crate', [])
+
+
+def lock_drift(locked, current):
+    """
+    Compare a locked crate with the server's current lock entry for the same binary.
+
+    Returns:
+        List of differences (empty if the crate still matches the lock)
+    """
+    differences = []
+    for key in ('name', 'version', 'recipe_revision', 'sha256', 'target'):
+        if locked.get(key, '') != current.get(key, ''):
+            differences.append(f"{key}: locked {locked.get(key, '')!r}, server has {current.get(key, '')!r}")
+    return differences
+
+
+def fetch_locked_crate(server_url, crate):
+    """
+    Download a locked crate and verify it against the lock.
+
+    Returns:
+        The .crate bytes
+
+    Raises:
+        LockfileError: If the binary is gone, its recipe revision, target or
+                       crate changed, or the download doesn't match the sha256
+    """
+    import hashlib
+    package_name, version = crate['package'].split('/', 1)
+    label = f"{crate['name']} {crate['version']} ({crate['package']}:{crate['package_id']})"
+
+    try:
+        current = lock_entry(server_url, package_name, version, crate['package_id'])
+    except LockfileError:
+        raise LockfileError(f"{label}: the binary is no longer on the server")
+    differences = lock_drift(crate, current)
+    if differences:
+        raise LockfileError(f"{label} changed on the server:\n    " + '\n    '.join(differences))
+
+    crate_bytes = download_crate_bytes(server_url, package_name, version, crate['package_id'])
+    if crate_bytes is None:
+        raise LockfileError(f"{label}: the crate is no longer on the server")
+    sha256 = hashlib.sha256(crate_bytes).hexdigest()
+    if sha256 != crate['sha256']:
+        raise LockfileError(f"{label}: checksum mismatch (locked {crate['sha256']}, downloaded {sha256})")
+    return crate_bytes
+
+
+def cmd_lock(args):
+    """Resolve packages for a profile and write conancrates.lock."""
+    server_url = args.server or "http://localhost:8000"
+    package_refs = args.package_refs
+    invalid = [package_ref for package_ref in package_refs if '/' not in package_ref]
+    if invalid:
+        print(f"Error: Invalid package reference {invalid[0]}. Use format: package_name/version")
+        return 1
+    lockfile_path = Path(args.project_dir) / LOCKFILE_NAME
+
+    try:
+        crates = resolve_lock(server_url, package_refs, args.profile)
+    except (LockfileError, requests.RequestException) as e:
+        print(f"✗ Error: {e}")
+        return 1
+
+    write_lockfile(lockfile_path, package_refs, crates)
+    print(f"\n✓ Locked {len(crates)} crate(s) in {lockfile_path}")
+    for crate in crates:
+        print(f"  {crate['name']} {crate['version']}  {crate['package']}:{crate['package_id']}  {crate.get('target', '')}")
+    return 0
+
+
+def cmd_install(args):
+    """
+    Install the crates of conancrates.lock into a Rust project: verify each
+    crate against the lock, vendor it, and write .cargo/config.toml.
+
+    Without --locked, the lockfile is (re)created when it's missing or the
+    requested packages changed. With --locked, it must exist and match.
+    """
+    server_url = args.server or "http://localhost:8000"
+    project_dir = Path(args.project_dir)
+    vendor_dir = Path(args.vendor_dir)
+    lockfile_path = project_dir / LOCKFILE_NAME
+
+    try:
+        requires, crates = read_lockfile(lockfile_path) if lockfile_path.exists() else (None, [])
+    except LockfileError as e:
+        print(f"✗ Error: {e}")
+        return 1
+
+    package_refs = args.package_refs or requires or []
+    if requires is None or sorted(package_refs) != sorted(requires):
+        if args.locked:
+            reason = "doesn't exist" if requires is None else f"locks {', '.join(requires) or 'nothing'}"
+            print(f"✗ Error: {lockfile_path} {reason}; run `conancrates lock` (--locked never updates it)")
+            return 1
+        if not package_refs or not args.profile:
+            print(f"Error: {lockfile_path} needs updating: pass the packages and -pr to lock them")
+            return 1
+        print(f"Locking {', '.join(package_refs)}...")
+        lock_args = argparse.Namespace(server=server_url, package_refs=package_refs,
+                                       profile=args.profile, project_dir=args.project_dir)
+        if cmd_lock(lock_args) != 0:
+            return 1
+        requires, crates = read_lockfile(lockfile_path)
+        print()
+
+    # Everything is verified before anything is unpacked
+    print(f"Verifying {len(crates)} locked crate(s)...")
+    with tempfile.TemporaryDirectory() as download_dir:
+        crate_files = []
+        try:
+            for crate in crates:
+                crate_file = Path(download_dir) / f"{crate['name']}-{crate['version']}.crate"
+                crate_file.write_bytes(fetch_locked_crate(server_url, crate))
+                crate_files.append((crate, crate_file))
+                print(f"  ✓ {crate['name']} {crate['version']}  sha256 {crate['sha256'][:12]}")
+        except (LockfileError, requests.RequestException) as e:
+            print(f"✗ Error: {e}")
+            return 1
+
+        print(f"\nUnpacking into {project_dir / vendor_dir}...")
+        crate_dirs = {}
+        for crate, crate_file in crate_files:
+            try:
+                crate_dir = unpack_crate(crate_file, project_dir / vendor_dir)
+            except (tarfile.TarError, ValueError) as e:
+                print(f"  Error: Could not unpack {crate_file.name}: {e}")
+                return 1
+            crate_dirs[crate['name']] = crate_dir.relative_to(project_dir)
+            print(f"  {crate_dirs[crate['name']].as_posix()}")
+
+    # The requested packages are pinned to their exact locked versions
+    dependencies = {crate['name']: f"={crate['version']}" for crate in crates if crate['package'] in requires}
+    rust_target = next((crate.get('target') for crate in crates if crate['package'] in requires), None)
+    configure_cargo_project(project_dir, vendor_dir, args.mode, server_url, crate_dirs, dependencies, rust_target)
+    return 0
+
+
+def cmd_download(args):
+    """
+    Download package and dependencies from ConanCrates and restore to local Conan cache.
+    Acts like 'conan install' - checks cache and only downloads missing packages.
+    """
+    # Handle Rust crate download
+    if args.crates:
+        return cmd_download_rust_crates(args)
+    
+    import zipfile
+    import tarfile
+    import platform
+
+    package_ref = args.package_ref
+    server_url = args.server or "http://localhost:8000"
+
+    print(f"ConanCrates Download")
+    print(f"{'='*60}")
+    print(f"Package: {package_ref}")
+    print(f"Server: {server_url}")
+    print(f"{'='*60}")
+
+    # Parse package reference
+    if '/' not in package_ref:
+        print(f"Error: Invalid package reference. Expected format: package/version")
+        return 1
+
+    package_name, version = package_ref.split('/', 1)
+
+    # Get platform settings from profile or auto-detect
+    profile_name = getattr(args, 'profile', None)
+
+    if profile_name:
+        # Use settings from Conan profile
+        print(f"\nUsing Conan profile: {profile_name}")
+        settings = parse_conan_profile(profile_name)
+        if not settings:
+            print(f"Error: Could not parse profile '{profile_name}'")
+            return 1
+
+        os_name = settings['os']
+        arch = settings['arch']
+        compiler = settings['compiler']
+        compiler_version = settings['compiler_version']
+        build_type = settings['build_type']
+    else:
+        # Auto-detect platform settings (use defaults that match most common builds)
+        print(f"\nNo profile specified, auto-detecting platform...")
+        system = platform.system()
+        if system == "Windows":
+            os_name = "Windows"
+            # Default to gcc for now (MinGW/MSYS2 is common for Conan on Windows)
+            compiler = "gcc"
+            compiler_version = "11"
+        elif system == "Linux":
+            os_name = "Linux"
+            compiler = "gcc"
+            compiler_version = "11"
+        elif system == "Darwin":
+            os_name = "Macos"
+            compiler = "apple-clang"
+            compiler_version = "14"
+        else:
+            os_name = "Linux"
+            compiler = "gcc"
+            compiler_version = "11"
+
+        arch = platform.machine().lower()
+        if arch in ("amd64", "x86_64"):
+            arch = "x86_64"
+        elif arch in ("arm64", "aarch64"):
+            arch = "armv8"
+
+        build_type = "Release"
 
     print(f"Target platform: {os_name}/{arch}/{compiler} {compiler_version}/{build_type}")
 
@@ -1275,11 +2123,1329 @@ def cmd_download(args):
                 print(f"\n✓ All packages restored successfully!")
                 return 0
 
-    except Exception as e:
-        print(f"Error: {e}")
-        import traceback
-        traceback.print_exc()
-        return 1
+    except Exception as e:
+        print(f"Error: {e}")
+        import traceback
+        traceback.print_exc()
+        return 1
+
+
+# (Conan os, Conan arch) -> Rust target triple
+RUST_TARGETS = {
+    ('Linux', 'x86_64'): 'x86_64-unknown-linux-gnu',
+    ('Linux', 'x86'): 'i686-unknown-linux-gnu',
+    ('Linux', 'armv8'): 'aarch64-unknown-linux-gnu',
+    ('Linux', 'armv7'): 'armv7-unknown-linux-gnueabi',
+    ('Linux', 'armv7hf'): 'armv7-unknown-linux-gnueabihf',
+    ('Linux', 'armv6'): 'arm-unknown-linux-gnueabi',
+    ('Linux', 'ppc64le'): 'powerpc64le-unknown-linux-gnu',
+    ('Linux', 'ppc64'): 'powerpc64-unknown-linux-gnu',
+    ('Linux', 's390x'): 's390x-unknown-linux-gnu',
+    ('Linux', 'riscv64'): 'riscv64gc-unknown-linux-gnu',
+    ('Macos', 'x86_64'): 'x86_64-apple-darwin',
+    ('Macos', 'armv8'): 'aarch64-apple-darwin',
+    ('iOS', 'armv8'): 'aarch64-apple-ios',
+    ('iOS', 'x86_64'): 'x86_64-apple-ios',
+    ('Android', 'armv8'): 'aarch64-linux-android',
+    ('Android', 'armv7'): 'armv7-linux-androideabi',
+    ('Android', 'x86'): 'i686-linux-android',
+    ('Android', 'x86_64'): 'x86_64-linux-android',
+    ('FreeBSD', 'x86_64'): 'x86_64-unknown-freebsd',
+    ('FreeBSD', 'armv8'): 'aarch64-unknown-freebsd',
+    ('Emscripten', 'wasm'): 'wasm32-unknown-emscripten',
+}
+
+# (Conan arch, Windows ABI) -> Rust target triple
+RUST_WINDOWS_TARGETS = {
+    ('x86_64', 'msvc'): 'x86_64-pc-windows-msvc',
+    ('x86', 'msvc'): 'i686-pc-windows-msvc',
+    ('armv8', 'msvc'): 'aarch64-pc-windows-msvc',
+    ('x86_64', 'gnu'): 'x86_64-pc-windows-gnu',
+    ('x86', 'gnu'): 'i686-pc-windows-gnu',
+}
+
+
+def conan_settings_to_rust_target(settings):
+    """
+    Map Conan settings to a Rust target triple.
+
+    Must match packages/rust_targets.py on the server, which records the
+    triple of every uploaded binary and names the native/<target-triple>/
+    directories of merged crates the same way.
+
+    Args:
+        settings: Dict with 'os', 'arch' and optionally 'compiler'
+
+    Returns:
+        Target triple (e.g., "x86_64-unknown-linux-gnu") or None if unknown
+    """
+    os_name = settings.get('os')
+    arch = settings.get('arch')
+
+    if os_name == 'Windows':
+        # MinGW gcc builds use the -gnu target, MSVC-compatible compilers the -msvc target
+        abi = 'gnu' if settings.get('compiler') == 'gcc' else 'msvc'
+        return RUST_WINDOWS_TARGETS.get((arch, abi))
+
+    return RUST_TARGETS.get((os_name, arch))
+
+
+def read_conaninfo_settings(binary_path):
+    """
+    Read the [settings] section of a binary package's conaninfo.txt.
+
+    Returns:
+        Dict like {'os': 'Linux', 'arch': 'x86_64', 'compiler': 'gcc', 'compiler.version': '11', ...}
+    """
+    settings = {}
+    conaninfo_path = Path(binary_path) / 'conaninfo.txt'
+    if not conaninfo_path.exists():
+        return settings
+
+    in_settings_section = False
+    for line in conaninfo_path.read_text(encoding='utf-8', errors='replace').split('\n'):
+        line = line.strip()
+        if line.startswith('['):
+            in_settings_section = line == '[settings]'
+            continue
+        if in_settings_section and '=' in line:
+            key, value = line.split('=', 1)
+            settings[key.strip()] = value.strip()
+
+    return settings
+
+
+def is_import_library(lib_file):
+    """
+    Check if a Windows .lib is a DLL import library rather than a static library.
+
+    Both are ar archives; import libraries contain short import objects
+    (header starting with Sig1=0x0000, Sig2=0xFFFF) instead of COFF objects.
+    """
+    try:
+        with open(lib_file, 'rb') as f:
+            if f.read(8) != b'!<arch>\n':
+                return False
+            while True:
+                header = f.read(60)
+                if len(header) < 60:
+                    return False
+                name = header[0:16].decode('ascii', errors='replace').strip()
+                size = int(header[48:58].decode('ascii', errors='replace').strip())
+                member_start = f.tell()
+                # Skip the symbol tables ("/") and long name table ("//")
+                if name not in ('/', '//'):
+                    if f.read(4) == b'\x00\x00\xff\xff':
+                        return True
+                f.seek(member_start + size + (size % 2))
+    except (OSError, ValueError):
+        return False
+
+
+def find_libraries(binary_path):
+    """
+    Find the libraries of a binary package and whether they're static or shared.
+
+    Shared libraries are .so/.dylib files and .lib files that are DLL import
+    libraries. Versioned shared objects (libfoo.so.1), versioned dylibs and
+    the DLLs in bin/ aren't linked directly but are needed at runtime.
+
+    Returns:
+        Tuple of (libraries, runtime_files):
+        - libraries: list of (lib_name, lib_file, kind) with kind "static" or "dylib"
+        - runtime_files: list of files to bundle alongside the libraries
+    """
+    binary_path = Path(binary_path)
+    lib_dir = binary_path / 'lib'
+    bin_dir = binary_path / 'bin'
+
+    dll_files = sorted(bin_dir.glob('*.dll')) if bin_dir.exists() else []
+    dll_stems = {dll.stem.lower() for dll in dll_files}
+
+    libraries = []
+    runtime_files = list(dll_files)
+    if lib_dir.exists():
+        for lib_file in sorted(lib_dir.iterdir()):
+            if not lib_file.is_file():
+                continue
+
+            if '.so.' in lib_file.name or (lib_file.suffix == '.dylib' and '.' in lib_file.stem):
+                runtime_files.append(lib_file)
+                continue
+
+            if lib_file.suffix not in ['.a', '.lib', '.so', '.dylib']:
+                continue
+
+            # Extract library name (remove lib prefix and extension)
+            lib_name = lib_file.stem
+            # Only strip "lib" prefix from Unix-style libraries (.a, .so, .dylib)
+            # Windows .lib files don't follow the "lib" prefix convention:
+            #   - Static libs on Windows: libfoo.lib (keep "lib")
+            #   - Import libs on Windows: foo.lib (no "lib" to strip)
+            if lib_name.startswith('lib') and lib_file.suffix in ['.a', '.so', '.dylib']:
+                lib_name = lib_name[3:]
+
+            if lib_file.suffix in ['.so', '.dylib']:
+                kind = 'dylib'
+            elif lib_file.suffix == '.lib' and (lib_file.stem.lower() in dll_stems or is_import_library(lib_file)):
+                kind = 'dylib'
+            else:
+                kind = 'static'
+
+            libraries.append((lib_name, lib_file, kind))
+
+    # A library shipped both ways (libfoo.a and libfoo.so) is linked statically
+    static_names = {lib_name for lib_name, _, kind in libraries if kind == 'static'}
+    libraries = [lib for lib in libraries if lib[2] == 'static' or lib[0] not in static_names]
+
+    return libraries, runtime_files
+
+
+# C and C++ header extensions
+HEADER_EXTENSIONS = ('.h', '.hh', '.hpp', '.hxx', '.h++', '.inl', '.ipp', '.tpp', '.tcc')
+
+
+def find_headers(include_dir):
+    """
+    Find the headers of a binary package.
+
+    Covers the usual C and C++ header extensions and extensionless headers
+    like Eigen/Dense or the C++ standard library's.
+
+    Returns:
+        Sorted list of header files (empty if there's no include/)
+    """
+    include_dir = Path(include_dir)
+    if not include_dir.exists():
+        return []
+    return sorted(
+        path for path in include_dir.rglob('*')
+        if path.is_file() and (path.suffix.lower() in HEADER_EXTENSIONS or not path.suffix)
+    )
+
+
+def archive_symbols(data, functions_only=True):
+    """
+    Read the symbol index of an ar archive (static library or import library).
+
+    Supports the GNU/MSVC index ("/" or "/SYM64/", big-endian offsets) and the
+    BSD one ("__.SYMDEF", written by Apple's ranlib). Archives without an index
+    are read member by member (functions_only applies to their ELF members).
+
+    Returns:
+        Set of the global symbols defined by the archive's members
+    """
+    symbols = set()
+    members = []
+    offset = 8
+    while offset + 60 <= len(data):
+        header = data[offset:offset + 60]
+        name = header[0:16].decode('ascii', errors='replace').strip()
+        size = int(header[48:58].decode('ascii', errors='replace').strip())
+        member = data[offset + 60:offset + 60 + size]
+        offset += 60 + size + (size % 2)
+
+        # BSD long names ("#1/<length>") are stored at the start of the member
+        if name.startswith('#1/'):
+            name_length = int(name[3:])
+            name = member[:name_length].rstrip(b'\0').decode('ascii', errors='replace')
+            member = member[name_length:]
+
+        if name in ('/', '/SYM64/'):
+            # Only the first "/" member: MSVC's second linker member has another layout
+            if symbols:
+                continue
+            width = 8 if name == '/SYM64/' else 4
+            count = int.from_bytes(member[:width], 'big')
+            names = member[width + count * width:].split(b'\0')
+            symbols.update(symbol.decode('ascii', errors='replace') for symbol in names[:count] if symbol)
+        elif name.startswith('__.SYMDEF'):
+            width = 8 if '64' in name else 4
+            ranlib_size = int.from_bytes(member[:width], 'little')
+            strings_start = width + ranlib_size + width
+            for entry in range(0, ranlib_size, 2 * width):
+                string_offset = int.from_bytes(member[width + entry:width + entry + width], 'little')
+                end = member.find(b'\0', strings_start + string_offset)
+                symbols.add(member[strings_start + string_offset:end].decode('ascii', errors='replace'))
+        elif name != '//':
+            members.append(member)
+
+    if not symbols:
+        for member in members:
+            symbols.update(elf_symbols(member, functions_only) or macho_symbols(member))
+    return symbols
+
+
+def elf_symbols(data, functions_only=True):
+    """
+    Read the global functions defined by an ELF shared library or object file.
+
+    Uses the dynamic symbol table (what a shared library exports), or the
+    symbol table of object files. With functions_only=False, data objects
+    (global variables) and TLS variables are read too.
+    """
+    if data[:4] != b'\x7fELF':
+        return set()
+    is_64 = data[4] == 2
+    endian = '<' if data[5] == 1 else '>'
+
+    if is_64:
+        shoff, = struct.unpack_from(endian + 'Q', data, 0x28)
+        shentsize, shnum = struct.unpack_from(endian + 'HH', data, 0x3A)
+        section_format, symbol_size = endian + 'IIQQQQIIQQ', 24
+    else:
+        shoff, = struct.unpack_from(endian + 'I', data, 0x20)
+        shentsize, shnum = struct.unpack_from(endian + 'HH', data, 0x2E)
+        section_format, symbol_size = endian + 'IIIIIIIIII', 16
+
+    sections = [struct.unpack_from(section_format, data, shoff + index * shentsize) for index in range(shnum)]
+
+    # Section type -> (offset, size, linked string table): SHT_DYNSYM = 11, SHT_SYMTAB = 2
+    tables = {section[1]: (section[4], section[5], section[6]) for section in sections}
+    table = tables.get(11) or tables.get(2)
+    if not table:
+        return set()
+    table_offset, table_size, string_section = table
+    strings_offset = sections[string_section][4]
+
+    symbols = set()
+    for offset in range(table_offset, table_offset + table_size, symbol_size):
+        if is_64:
+            name, info, other, shndx = struct.unpack_from(endian + 'IBBH', data, offset)
+        else:
+            name, _, _, info, other, shndx = struct.unpack_from(endian + 'IIIBBH', data, offset)
+        # Defined, global or weak, function or ifunc (or object, TLS), default or protected visibility
+        kinds = (2, 10) if functions_only else (1, 2, 6, 10)
+        if shndx == 0 or info >> 4 not in (1, 2) or info & 0xf not in kinds or other & 0x3 not in (0, 3):
+            continue
+        end = data.find(b'\0', strings_offset + name)
+        symbols.add(data[strings_offset + name:end].decode('ascii', errors='replace'))
+    return symbols
+
+
+def macho_symbols(data):
+    """
+    Read the external symbols defined by a Mach-O dylib or object file.
+
+    Universal (fat) binaries are read per architecture. Names keep the leading
+    underscore Mach-O adds to C symbols.
+    """
+    if data[:4] == b'\xca\xfe\xba\xbe':
+        symbols = set()
+        arch_count, = struct.unpack_from('>I', data, 4)
+        for index in range(arch_count):
+            _, _, offset, size, _ = struct.unpack_from('>iiIII', data, 8 + index * 20)
+            symbols |= macho_symbols(data[offset:offset + size])
+        return symbols
+
+    if data[:4] not in (b'\xce\xfa\xed\xfe', b'\xcf\xfa\xed\xfe'):
+        return set()
+    is_64 = data[:4] == b'\xcf\xfa\xed\xfe'
+    command_count, = struct.unpack_from('<I', data, 16)
+
+    symbols = set()
+    offset = 32 if is_64 else 28
+    for _ in range(command_count):
+        command, command_size = struct.unpack_from('<II', data, offset)
+        if command == 0x2:  # LC_SYMTAB
+            symoff, nsyms, stroff, _ = struct.unpack_from('<IIII', data, offset + 8)
+            entry_size = 16 if is_64 else 12
+            for index in range(nsyms):
+                name, n_type = struct.unpack_from('<IB', data, symoff + index * entry_size)
+                # External (N_EXT) and defined in a section (N_SECT), not a debug entry
+                if n_type & 0xe0 or not n_type & 0x01 or n_type & 0x0e != 0x0e:
+                    continue
+                end = data.find(b'\0', stroff + name)
+                symbols.add(data[stroff + name:end].decode('ascii', errors='replace'))
+        offset += command_size
+    return symbols
+
+
+def library_symbols(lib_file, functions_only=True):
+    """
+    Read the symbols a library exports.
+
+    Static libraries and Windows import libraries are ar archives, read
+    through their symbol index. Shared libraries are ELF or Mach-O files.
+    DLLs are linked through their import library, so PE files aren't read.
+    Archive indexes and Mach-O files list variables too; ELF files only
+    with functions_only=False.
+
+    Returns:
+        Set of symbol names (empty if the file can't be read)
+    """
+    try:
+        data = Path(lib_file).read_bytes()
+        if data.startswith(b'!<arch>\n'):
+            return archive_symbols(data, functions_only)
+        return elf_symbols(data, functions_only) or macho_symbols(data)
+    except (OSError, struct.error, ValueError, IndexError):
+        return set()
+
+
+# Rust keywords, which can't name the smoke test's extern functions
+RUST_KEYWORDS = {
+    'as', 'async', 'await', 'box', 'break', 'const', 'continue', 'crate', 'dyn', 'else', 'enum',
+    'extern', 'false', 'fn', 'for', 'gen', 'if', 'impl', 'in', 'let', 'loop', 'match', 'mod',
+    'move', 'mut', 'pub', 'ref', 'return', 'self', 'Self', 'static', 'struct', 'super', 'trait',
+    'true', 'try', 'type', 'unsafe', 'use', 'where', 'while', 'yield',
+}
+
+# Maximum number of functions the link smoke test takes the address of
+SMOKE_TEST_FUNCTIONS = 16
+
+
+def smoke_test_functions(libraries, headers, limit=SMOKE_TEST_FUNCTIONS):
+    """
+    Pick C functions for the link smoke test.
+
+    A function qualifies if a library exports it and a header uses its name
+    like a function ("name("), which leaves out C++ (mangled) symbols and
+    internal symbols that happen to be global. Mach-O and 32-bit Windows
+    prefix C symbols with an underscore, so names are also matched without it.
+
+    Args:
+        libraries: List of (lib_name, lib_file, kind) from find_libraries()
+        headers: List of header files
+        limit: Maximum number of functions, spread over the sorted names
+
+    Returns:
+        Sorted list of function names as declared in C
+    """
+    called = set()
+    for header in headers:
+        try:
+            text = Path(header).read_text(errors='replace')
+        except OSError:
+            continue
+        called.update(re.findall(r'\b([A-Za-z_]\w*)\s*\(', text))
+
+    functions = set()
+    for _, lib_file, _ in libraries:
+        for symbol in library_symbols(lib_file):
+            if symbol.startswith('__imp_'):
+                continue
+            for name in (symbol, symbol[1:] if symbol.startswith('_') else None):
+                if name and name in called and name not in RUST_KEYWORDS:
+                    functions.add(name)
+                    break
+
+    functions = sorted(functions)
+    if len(functions) > limit:
+        functions = [functions[index * len(functions) // limit] for index in range(limit)]
+    return functions
+
+
+def generate_link_smoke_test(crate_name, functions, dependency_crates=()):
+    """
+    Generate tests/link_smoke.rs, which takes the address of exported functions.
+
+    The functions are declared without parameters and never called: taking
+    their address is enough for the linker to resolve them against the
+    crate's libraries, so `cargo test` fails if the crate doesn't link.
+
+    Args:
+        crate_name: Name of the -sys crate
+        functions: Function names from smoke_test_functions()
+        dependency_crates: -sys crates of the dependencies, linked explicitly
+                           so their libraries resolve the package's symbols
+    """
+    extern_crates = ''.join(
+        f"extern crate {name.replace('-', '_')};\n" for name in [crate_name] + list(dependency_crates)
+    )
+    declarations = ''.join(f"    fn {name}();\n" for name in functions)
+    addresses = ''.join(f'        ("{name}", {name} as *const ()),\n' for name in functions)
+
+    return f'''//! Link smoke test, generated by conancrates
+//!
+//! Takes the address of functions exported by the package's libraries and
+//! declared in its headers. The functions aren't called (their signatures
+//! aren't known here), but the test only links if they resolve.
+
+{extern_crates}
+extern "C" {{
+{declarations}}}
+
+#[test]
+fn exported_functions_link() {{
+    let functions: [(&str, *const ()); {len(functions)}] = [
+{addresses}    ];
+    for (name, address) in functions {{
+        assert!(!std::hint::black_box(address).is_null(), "{{}} has no address", name);
+    }}
+}}
+'''
+
+
+def load_rust_config(recipe_path):
+    """
+    Read the [rust] section of conancrates.ini next to a recipe.
+
+    Recipes opt into crate generation settings by exporting the file
+    (exports = "conancrates.ini"):
+
+        [rust]
+        bindgen = true
+        bindgen_header = mylib/mylib.h
+        bindgen_allowlist = mylib_.* MYLIB_.*
+        cxx_manifest = cxx.toml
+
+    Args:
+        recipe_path: Path to conanfile.py in the Conan cache
+
+    Returns:
+        Dict of the section's keys (empty if there's no file or section)
+    """
+    import configparser
+
+    if not recipe_path:
+        return {}
+
+    config_path = Path(recipe_path).parent / 'conancrates.ini'
+    if not config_path.exists():
+        return {}
+
+    config = configparser.ConfigParser()
+    try:
+        config.read(config_path, encoding='utf-8')
+    except configparser.Error as e:
+        print(f"⚠ Warning: Could not parse {config_path}: {e}")
+        return {}
+
+    if not config.has_section('rust'):
+        return {}
+    return dict(config.items('rust'))
+
+
+def find_bindgen_header(include_dir, pkg_name, headers):
+    """
+    Pick the header bindgen should start from when none is configured.
+
+    Tries include/<name>.h and include/<name>/<name>.h, then a lone header.
+
+    Returns:
+        Path relative to include_dir, or None if there's no obvious entry header
+    """
+    include_dir = Path(include_dir)
+    for candidate in [f"{pkg_name}.h", f"{pkg_name}/{pkg_name}.h"]:
+        if (include_dir / candidate).exists():
+            return candidate
+
+    if len(headers) == 1:
+        return Path(headers[0]).relative_to(include_dir).as_posix()
+
+    return None
+
+
+def bindgen_command(header_path, include_dir, output_path, allowlist=None, defines=None):
+    """
+    Build the bindgen command line for a header.
+
+    Each allowlist pattern is applied to functions, types and variables.
+    Defines (from cpp_info) are passed to clang. C++ headers (any extension
+    but .h, or none) are parsed as C++.
+    """
+    command = ['bindgen', str(header_path), '-o', str(output_path)]
+    for pattern in allowlist or []:
+        command += ['--allowlist-function', pattern, '--allowlist-type', pattern, '--allowlist-var', pattern]
+
+    command += ['--', f'-I{include_dir}']
+    command += [f'-D{define}' for define in defines or []]
+    if Path(header_path).suffix.lower() in HEADER_EXTENSIONS[1:] or not Path(header_path).suffix:
+        command += ['-x', 'c++']
+    return command
+
+
+def run_bindgen(header_path, include_dir, output_path, allowlist=None, defines=None):
+    """
+    Generate Rust bindings for a header with the bindgen CLI.
+
+    Install bindgen with: cargo install bindgen-cli
+
+    Returns:
+        True if bindings were written to output_path, False otherwise
+    """
+    command = bindgen_command(header_path, include_dir, output_path, allowlist, defines)
+    try:
+        result = subprocess.run(command, capture_output=True, text=True)
+    except FileNotFoundError:
+        print(f"⚠ Warning: bindgen not found (install with: cargo install bindgen-cli)")
+        return False
+
+    if result.returncode != 0:
+        print(f"⚠ Warning: bindgen failed:")
+        for line in result.stderr.strip().split('\n')[:10]:
+            print(f"    {line}")
+        return False
+
+    return Path(output_path).exists()
+
+
+# Manifest types usable in cxx bridge signatures: type -> (Rust type, C++ shim type)
+CXX_PRIMITIVES = {
+    'bool': 'bool',
+    'i8': 'std::int8_t',
+    'i16': 'std::int16_t',
+    'i32': 'std::int32_t',
+    'i64': 'std::int64_t',
+    'u8': 'std::uint8_t',
+    'u16': 'std::uint16_t',
+    'u32': 'std::uint32_t',
+    'u64': 'std::uint64_t',
+    'usize': 'std::size_t',
+    'isize': 'rust::isize',
+    'f32': 'float',
+    'f64': 'double',
+}
+
+CXX_IDENTIFIER_RE = re.compile(r'^[A-Za-z_][A-Za-z0-9_]*$')
+
+
+class CxxManifestError(Exception):
+    """Exception raised when a cxx bridge manifest is invalid"""
+    pass
+
+
+def load_cxx_manifest(manifest_path):
+    """
+    Read a cxx bridge manifest (TOML) listing the C++ API to expose.
+
+        namespace = "mylib"              # C++ namespace of the API
+        headers = ["mylib/widget.hpp"]   # relative to include/
+        std = "c++17"                    # optional
+
+        [[classes]]
+        name = "Widget"
+        constructors = [{ name = "new", args = ["name: str"] }]
+        methods = [
+            { name = "size", returns = "usize", const = true },
+            { name = "set_name", args = ["name: str"] },
+        ]
+
+        [[functions]]
+        name = "version"
+        returns = "String"
+
+    Types are Rust primitives (i32, u64, usize, f64, bool, ...), str and
+    String (std::string in C++), a class name (const reference argument,
+    std::unique_ptr return value) or "mut <class>" (mutable reference
+    argument). Functions and methods may set cpp_name when the C++ name
+    differs.
+
+    Raises:
+        CxxManifestError: If the file can't be read or doesn't describe an API
+    """
+    import tomllib
+
+    try:
+        with open(manifest_path, 'rb') as f:
+            manifest = tomllib.load(f)
+    except (OSError, tomllib.TOMLDecodeError) as e:
+        raise CxxManifestError(f"Could not read {manifest_path}: {e}")
+
+    if not manifest.get('headers'):
+        raise CxxManifestError(f"{manifest_path} lists no headers")
+    if not manifest.get('classes') and not manifest.get('functions'):
+        raise CxxManifestError(f"{manifest_path} lists no classes or functions")
+
+    namespace = manifest.get('namespace', '')
+    if namespace and not all(CXX_IDENTIFIER_RE.match(part) for part in namespace.split('::')):
+        raise CxxManifestError(f"Invalid namespace: {namespace!r}")
+
+    class_names = set()
+    for cls in manifest.get('classes', []):
+        check_cxx_identifier(cls.get('name'), 'class')
+        class_names.add(cls['name'])
+
+    for cls in manifest.get('classes', []):
+        for item in cls.get('constructors', []) + cls.get('methods', []):
+            check_cxx_item(item, class_names, f"{cls['name']}.")
+    for item in manifest.get('functions', []):
+        check_cxx_item(item, class_names)
+
+    return manifest
+
+
+def check_cxx_identifier(name, kind):
+    """Check a name from the manifest is a valid C++ and Rust identifier."""
+    if not isinstance(name, str) or not CXX_IDENTIFIER_RE.match(name):
+        raise CxxManifestError(f"Invalid {kind} name: {name!r}")
+
+
+def check_cxx_item(item, class_names, prefix=''):
+    """Check the name, arguments and return type of a function, method or constructor."""
+    check_cxx_identifier(item.get('name'), 'function')
+    if item.get('cpp_name') is not None:
+        check_cxx_identifier(item['cpp_name'], 'C++ function')
+    for arg in item.get('args', []):
+        arg_name, _, arg_type = arg.partition(':')
+        check_cxx_identifier(arg_name.strip(), 'argument')
+        if cxx_arg_types(arg_type.strip(), class_names) is None:
+            raise CxxManifestError(f"{prefix}{item['name']}: unsupported argument type {arg_type.strip()!r}")
+    if item.get('returns') and cxx_return_types(item['returns'], class_names) is None:
+        raise CxxManifestError(f"{prefix}{item['name']}: unsupported return type {item['returns']!r}")
+
+
+def cxx_arg_types(type_name, class_names, rust_prefix=''):
+    """
+    Get the types of a manifest argument type.
+
+    Returns:
+        Tuple of (Rust type, C++ shim type, C++ expression format for the call),
+        or None if the type isn't supported
+    """
+    if type_name in CXX_PRIMITIVES:
+        return type_name, CXX_PRIMITIVES[type_name], '{}'
+    if type_name in ('str', 'String'):
+        return '&str', 'rust::Str', 'std::string({})'
+    if type_name in class_names:
+        return f'&{rust_prefix}{type_name}', f'const {type_name}&', '{}'
+    if type_name.startswith('mut ') and type_name[4:].strip() in class_names:
+        class_name = type_name[4:].strip()
+        return f'Pin<&mut {rust_prefix}{class_name}>', f'{class_name}&', '{}'
+    return None
+
+
+def cxx_return_types(type_name, class_names, rust_prefix=''):
+    """
+    Get the types of a manifest return type.
+
+    Returns:
+        Tuple of (Rust type, C++ shim type, C++ statement format for the result),
+        or None if the type isn't supported
+    """
+    if type_name in CXX_PRIMITIVES:
+        return type_name, CXX_PRIMITIVES[type_name], 'return {};'
+    if type_name in ('str', 'String'):
+        return 'String', 'rust::String', 'return rust::String({});'
+    if type_name in class_names:
+        return (f'UniquePtr<{rust_prefix}{type_name}>', f'std::unique_ptr<{type_name}>',
+                f'return std::make_unique<{type_name}>({{}});')
+    return None
+
+
+def snake_case(name):
+    """Convert a C++ class name to snake case (e.g., "HttpClient" -> "http_client")."""
+    return re.sub(r'(?<=[a-z0-9])(?=[A-Z])|(?<=[A-Z])(?=[A-Z][a-z])', '_', name).lower()
+
+
+def cxx_shim_functions(manifest):
+    """
+    List the shim functions for a manifest: one per function, constructor and method.
+
+    Returns:
+        List of dicts with rust_name, args [(name, type)], returns, self
+        (None, "const" or "mut"), class_name, target (C++ callee) and
+        kind ("function", "constructor" or "method")
+    """
+    namespace = manifest.get('namespace', '')
+    qualified = f"::{namespace}::" if namespace else "::"
+
+    def parse_args(item):
+        args = []
+        for arg in item.get('args', []):
+            arg_name, _, arg_type = arg.partition(':')
+            args.append((arg_name.strip(), arg_type.strip()))
+        return args
+
+    shims = []
+    for cls in manifest.get('classes', []):
+        class_name = cls['name']
+        prefix = snake_case(class_name)
+        for item in cls.get('constructors', []):
+            shims.append({
+                'kind': 'constructor', 'class_name': class_name, 'name': item['name'],
+                'rust_name': f"{prefix}_{item['name']}", 'args': parse_args(item),
+                'returns': class_name, 'self': None, 'target': class_name,
+            })
+        for item in cls.get('methods', []):
+            shims.append({
+                'kind': 'method', 'class_name': class_name, 'name': item['name'],
+                'rust_name': f"{prefix}_{item['name']}", 'args': parse_args(item),
+                'returns': item.get('returns'), 'self': 'const' if item.get('const') else 'mut',
+                'target': item.get('cpp_name') or item['name'],
+            })
+    for item in manifest.get('functions', []):
+        shims.append({
+            'kind': 'function', 'class_name': None, 'name': item['name'],
+            'rust_name': item['name'], 'args': parse_args(item),
+            'returns': item.get('returns'), 'self': None,
+            'target': f"{qualified}{item.get('cpp_name') or item['name']}",
+        })
+    return shims
+
+
+def generate_cxx_bridge(manifest, crate_name, shim_name):
+    """
+    Generate the cxx bridge module and the C++ shim implementing it.
+
+    The bridge declares the manifest's classes as opaque C++ types and one
+    shim function per function, constructor and method. Shims live in the
+    <namespace>::cxxshim namespace and convert between cxx types (rust::Str,
+    rust::String, std::unique_ptr) and those of the C++ API; methods and
+    constructors are also exposed as Rust methods on the types.
+
+    Args:
+        manifest: Manifest from load_cxx_manifest()
+        crate_name: Crate name (the bridge includes "<crate_name>/shim/<shim_name>.h")
+        shim_name: Base name of the shim files
+
+    Returns:
+        Tuple of (src/bridge.rs, shim/<shim_name>.h, shim/<shim_name>.cc) contents
+    """
+    namespace = manifest.get('namespace', '')
+    shim_namespace = f"{namespace}::cxxshim" if namespace else "cxxshim"
+    class_names = {cls['name'] for cls in manifest.get('classes', [])}
+    shims = cxx_shim_functions(manifest)
+
+    def rust_signature(shim, rust_prefix='', method=False):
+        params = []
+        if shim['self'] and not method:
+            self_type = cxx_arg_types(shim['class_name'] if shim['self'] == 'const' else f"mut {shim['class_name']}",
+                                      class_names, rust_prefix)[0]
+            params.append(f"this: {self_type}")
+        elif shim['self']:
+            params.append('&self' if shim['self'] == 'const' else 'self: Pin<&mut Self>')
+        params += [f"{name}: {cxx_arg_types(arg_type, class_names, rust_prefix)[0]}" for name, arg_type in shim['args']]
+        returns = ''
+        if shim['returns']:
+            returns = f" -> {cxx_return_types(shim['returns'], class_names, rust_prefix)[0]}"
+        return f"fn {shim['name'] if method else shim['rust_name']}({', '.join(params)}){returns}"
+
+    def cpp_signature(shim):
+        params = []
+        if shim['self']:
+            params.append(f"{'const ' if shim['self'] == 'const' else ''}{shim['class_name']}& self")
+        params += [f"{cxx_arg_types(arg_type, class_names)[1]} {name}" for name, arg_type in shim['args']]
+        returns = cxx_return_types(shim['returns'], class_names)[1] if shim['returns'] else 'void'
+        return f"{returns} {shim['rust_name']}({', '.join(params)})"
+
+    # src/bridge.rs
+    types = ''.join(f"        type {name};\n" for name in sorted(class_names))
+    functions = ''.join(f"        {rust_signature(shim)};\n" for shim in shims)
+    impls = ''
+    for cls in manifest.get('classes', []):
+        class_shims = [shim for shim in shims if shim['class_name'] == cls['name']]
+        if not class_shims:
+            continue
+        impls += f"\nimpl ffi::{cls['name']} {{\n"
+        for shim in class_shims:
+            call_args = (['self'] if shim['self'] else []) + [name for name, _ in shim['args']]
+            impls += f"    pub {rust_signature(shim, 'ffi::', method=True)} {{\n"
+            impls += f"        ffi::{shim['rust_name']}({', '.join(call_args)})\n"
+            impls += "    }\n"
+        impls += "}\n"
+
+    uses = []
+    if any(shim['self'] == 'mut' or any(arg_type.startswith('mut ') for _, arg_type in shim['args']) for shim in shims):
+        uses.append('use std::pin::Pin;')
+    if any(shim['returns'] in class_names for shim in shims):
+        uses.append('use cxx::UniquePtr;')
+    uses_section = '\n'.join(uses) + '\n\n' if uses else ''
+
+    bridge_namespace = f'(namespace = "{namespace}")' if namespace else ''
+    bridge_rs = f'''//! cxx bridge to the C++ API of the package, generated by conancrates
+//! from its cxx manifest. The functions are implemented by shim/{shim_name}.cc.
+
+{uses_section}#[cxx::bridge{bridge_namespace}]
+pub mod ffi {{
+    unsafe extern "C++" {{
+        include!("{crate_name}/shim/{shim_name}.h");
+
+{types}    }}
+
+    #[namespace = "{shim_namespace}"]
+    unsafe extern "C++" {{
+{functions}    }}
+}}
+{impls}'''
+
+    # shim/<shim_name>.h
+    includes = ''.join(f'#include "{header}"\n' for header in manifest['headers'])
+    declarations = ''.join(f"{cpp_signature(shim)};\n" for shim in shims)
+    open_namespace = ''.join(f"namespace {part} {{\n" for part in shim_namespace.split('::'))
+    close_namespace = ''.join(f"}}  // namespace {part}\n" for part in reversed(shim_namespace.split('::')))
+    shim_h = f'''// C++ shim behind src/bridge.rs, generated by conancrates
+#pragma once
+
+#include <cstdint>
+#include <memory>
+#include <string>
+
+#include "rust/cxx.h"
+{includes}
+{open_namespace}
+{declarations}
+{close_namespace}'''
+
+    # shim/<shim_name>.cc
+    definitions = ''
+    for shim in shims:
+        call_args = ', '.join(cxx_arg_types(arg_type, class_names)[2].format(name) for name, arg_type in shim['args'])
+        if shim['kind'] == 'constructor':
+            statement = f"return std::make_unique<{shim['class_name']}>({call_args});"
+        else:
+            call = f"self.{shim['target']}({call_args})" if shim['self'] else f"{shim['target']}({call_args})"
+            statement = cxx_return_types(shim['returns'], class_names)[2].format(call) if shim['returns'] else f"{call};"
+        definitions += f"{cpp_signature(shim)} {{\n    {statement}\n}}\n\n"
+    shim_cc = f'''// C++ shim behind src/bridge.rs, generated by conancrates
+#include "{crate_name}/shim/{shim_name}.h"
+#include "{crate_name}/src/bridge.rs.h"
+
+{open_namespace}
+{definitions}{close_namespace}'''
+
+    return bridge_rs, shim_h, shim_cc
+
+
+# Semantic version (https://semver.org) as required by Cargo
+SEMVER_RE = re.compile(
+    r'^(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)'
+    r'(?:-((?:0|[1-9]\d*|\d*[A-Za-z-][0-9A-Za-z-]*)(?:\.(?:0|[1-9]\d*|\d*[A-Za-z-][0-9A-Za-z-]*))*))?'
+    r'(?:\+([0-9A-Za-z-]+(?:\.[0-9A-Za-z-]+)*))?$'
+)
+
+
+def semver_identifiers(text):
+    """Split dotted text into identifiers, replacing characters semver doesn't allow."""
+    identifiers = []
+    for part in text.split('.'):
+        part = re.sub(r'[^0-9A-Za-z-]', '-', part)
+        if part:
+            identifiers.append(part)
+    return identifiers
+
+
+def cargo_version(version):
+    """
+    Translate a Conan version into a Cargo version (same rules as the server's
+    packages/cargo_versions.py).
+
+    The first three numeric components become major.minor.patch (padded with
+    zeros), anything else is kept as build metadata:
+        "3.0" -> "3.0.0", "1.2.13.1" -> "1.2.13+1", "cci.20230101" -> "20230101.0.0+cci"
+    """
+    version = version.strip()
+    if SEMVER_RE.match(version):
+        return version
+
+    version, _, build = version.partition('+')
+    version, _, prerelease = version.partition('-')
+    if re.match(r'^[vV]\d', version):
+        version = version[1:]
+
+    numbers = []
+    extra = []
+    for part in version.split('.'):
+        match = re.match(r'^(\d+)(.*)$', part)
+        if match and len(numbers) < 3 and not (numbers and extra):
+            numbers.append(str(int(match.group(1))))
+            extra.extend(semver_identifiers(match.group(2)))
+        else:
+            extra.extend(semver_identifiers(part))
+    numbers += ['0'] * (3 - len(numbers))
+
+    result = '.'.join(numbers)
+    prerelease = [
+        str(int(identifier)) if identifier.isdigit() else identifier
+        for identifier in semver_identifiers(prerelease)
+    ]
+    if prerelease:
+        result += '-' + '.'.join(prerelease)
+    metadata = extra + semver_identifiers(build)
+    if metadata:
+        result += '+' + '.'.join(metadata)
+    return result
+
+
+def version_requirement(version):
+    """Get the Cargo requirement for a Cargo version (build metadata is ignored by Cargo)."""
+    return version.split('+', 1)[0]
+
+
+# Conan range operators, longest first so ">=" isn't read as ">"
+RANGE_OPERATORS = ('>=', '<=', '>', '<', '=', '~', '^')
+
+
+def version_key(version):
+    """Numeric (major, minor, patch) of a Cargo version, for comparisons."""
+    return tuple(int(number) for number in re.match(r'^(\d+)\.(\d+)\.(\d+)', version).groups())
+
+
+def partial_version(version):
+    """
+    Keep the components of a version used with ~ or ^.
+
+    Cargo reads "~1" as >=1.0.0, <2.0.0 but "~1.0.0" as >=1.0.0, <1.1.0, the
+    same way Conan does, so plain numeric versions aren't padded.
+    """
+    if re.match(r'^\d+(\.\d+){0,2}$', version):
+        return '.'.join(str(int(number)) for number in version.split('.'))
+    return version_requirement(cargo_version(version))
+
+
+def range_comparators(expression):
+    """
+    Translate one alternative of a Conan version range into Cargo comparators.
+
+    Examples:
+        ">=1.2 <2"  -> [">=1.2.0", "<2.0.0"]
+        "~1.2"      -> ["~1.2"]
+        "1.2.13.1"  -> ["=1.2.13"]
+    """
+    comparators = []
+    for condition in expression.split():
+        if condition == '*':
+            continue
+        operator = next((op for op in RANGE_OPERATORS if condition.startswith(op)), '=')
+        version = condition[len(operator):] if condition.startswith(operator) else condition
+        if operator in ('~', '^'):
+            comparators.append(f"{operator}{partial_version(version)}")
+        else:
+            comparators.append(f"{operator}{version_requirement(cargo_version(version))}")
+    return comparators or ['*']
+
+
+def comparator_matches(comparator, version):
+    """Check if a Cargo version (major, minor, patch) satisfies a comparator."""
+    if comparator == '*':
+        return True
+    operator = next(op for op in RANGE_OPERATORS if comparator.startswith(op))
+    parts = [int(number) for number in comparator[len(operator):].split('-')[0].split('.')]
+    bound = tuple(parts + [0] * (3 - len(parts)))
+
+    if operator == '~':
+        upper = (bound[0] + 1, 0, 0) if len(parts) == 1 else (bound[0], bound[1] + 1, 0)
+        return bound <= version < upper
+    if operator == '^':
+        significant = next((index for index, number in enumerate(parts) if number), len(parts) - 1)
+        upper = list(bound[:significant]) + [bound[significant] + 1] + [0] * (2 - significant)
+        return bound <= version < tuple(upper)
+    return {
+        '>=': version >= bound,
+        '<=': version <= bound,
+        '>': version > bound,
+        '<': version < bound,
+        '=': version == bound,
+    }[operator]
+
+
+def cargo_requirement(requirement, resolved_version=None):
+    """
+    Translate the version part of a Conan requirement into a Cargo requirement
+    (same rules as the server's packages/cargo_versions.py).
+
+    Plain versions become caret requirements (Cargo's default), ranges keep
+    their bounds. Cargo has no "||": the alternative containing the resolved
+    version is used, and a range that can't match the resolved version falls
+    back to a caret requirement on it.
+
+    Examples:
+        "1.2.13"                     -> "^1.2.13"
+        "[>=1.2 <2]"                 -> ">=1.2.0, <2.0.0"
+        "[~3.0, include_prerelease]" -> "~3.0"
+        "[>=1 <2 || >=3 <4]"         -> ">=3.0.0, <4.0.0" (resolved 3.1)
+
+    Args:
+        requirement: Version requirement (e.g. "[>=1.2 <2]"), user/channel and
+                     recipe revision are ignored
+        resolved_version: Conan version the graph resolved the requirement to
+    """
+    requirement = requirement.split('#', 1)[0].split('@', 1)[0].strip()
+    resolved = version_requirement(cargo_version(resolved_version)) if resolved_version else None
+
+    if not (requirement.startswith('[') and requirement.endswith(']')):
+        if requirement:
+            return f"^{version_requirement(cargo_version(requirement))}"
+        return f"^{resolved}" if resolved else '*'
+
+    expression = requirement[1:-1].split(',', 1)[0]
+    alternatives = [range_comparators(alternative) for alternative in expression.split('||')]
+    if resolved:
+        matching = [
+            comparators for comparators in alternatives
+            if all(comparator_matches(comparator, version_key(resolved)) for comparator in comparators)
+        ]
+        if not matching:
+            return f"^{resolved}"
+        alternatives = matching
+    return ', '.join(alternatives[-1])
+
+
+def graph_requirements(nodes):
+    """
+    Map the node ids of a conan graph to the version requirement they were required with.
+
+    conan graph info records every edge in the requiring node's "dependencies"
+    with the original require (e.g. "zlib/[>=1.2 <2]"); the root node's edges
+    are used when several nodes require the same dependency.
+
+    Returns:
+        Dict of node id -> requirement (e.g. {"1": "[>=1.2 <2]"})
+    """
+    requirements = {}
+    for node_id in sorted(nodes, key=lambda node_id: node_id != '0'):
+        for dep_id, edge in (nodes[node_id].get('dependencies') or {}).items():
+            require = edge.get('require') if isinstance(edge, dict) else None
+            if require and '/' in require and dep_id not in requirements:
+                requirements[dep_id] = require.split('/', 1)[1]
+    return requirements
+
+
+# Entry mtime used by cargo package, so archives are reproducible
+CRATE_MTIME = 1153704088
+
+CARGO_TOML_HEADER = """# THIS FILE IS AUTOMATICALLY GENERATED BY CARGO
+#
+# When uploading crates to the registry Cargo will automatically
+# "normalize" Cargo.toml files for maximal compatibility
+# with all versions of Cargo and also rewrite `path` dependencies
+# to registry (e.g., crates.io) dependencies.
+#
+# If you are reading this file be aware that the original Cargo.toml
+# will likely look very different (and much more reasonable).
+# See Cargo.toml.orig for the original contents.
+"""
+
+DEPENDENCY_TABLES = ('dependencies', 'dev-dependencies', 'build-dependencies')
+
+
+def toml_key(key):
+    """Format a TOML key (bare if possible, quoted otherwise)."""
+    return key if re.match(r'^[A-Za-z0-9_-]+$', key) else json.dumps(key, ensure_ascii=False)
+
+
+def toml_value(value):
+    """Format a TOML value the way cargo writes normalized manifests."""
+    if isinstance(value, bool):
+        return 'true' if value else 'false'
+    if isinstance(value, (int, float)):
+        return str(value)
+    if isinstance(value, str):
+        return json.dumps(value, ensure_ascii=False)
+    if isinstance(value, list):
+        if not value:
+            return '[]'
+        return '[\n' + ''.join(f"    {toml_value(item)},\n" for item in value) + ']'
+    if isinstance(value, dict):
+        if not value:
+            return '{}'
+        return '{ ' + ', '.join(f"{toml_key(key)} = {toml_value(item)}" for key, item in value.items()) + ' }'
+    raise ValueError(f"Unsupported TOML value: {value!r}")
+
+
+def is_table_array(value):
+    """Check if a value is written as an array of tables ([[bin]], [[test]], ...)."""
+    return isinstance(value, list) and bool(value) and all(isinstance(item, dict) for item in value)
+
+
+def toml_table(path, table, lines, array=False):
+    """Append a table (and its sub-tables and arrays of tables) to lines."""
+    values = [(key, value) for key, value in table.items() if not isinstance(value, dict) and not is_table_array(value)]
+    tables = [(key, value) for key, value in table.items() if isinstance(value, dict)]
+    arrays = [(key, value) for key, value in table.items() if is_table_array(value)]
+    if values or not (tables or arrays) or array:
+        name = '.'.join(toml_key(part) for part in path)
+        lines.append(f"[[{name}]]" if array else f"[{name}]")
+        lines.extend(f"{toml_key(key)} = {toml_value(value)}" for key, value in values)
+        lines.append('')
+    for key, value in tables:
+        toml_table(path + [key], value, lines)
+    for key, value in arrays:
+        toml_table_array(path + [key], value, lines)
+
+
+def toml_table_array(path, items, lines):
+    """Append an array of tables to lines, one [[path]] header per item."""
+    for item in items:
+        toml_table(path, item, lines, array=True)
+
+
+def normalize_dependencies(dependencies):
+    """
+    Rewrite dependencies for the registry: path dependencies become version
+    dependencies (path-only ones are dropped, like cargo does for dev-dependencies).
+    """
+    normalized = {}
+    for name, spec in dependencies.items():
+        if isinstance(spec, str):
+            spec = {'version': spec}
+        spec = {key: value for key, value in spec.items() if key != 'path'}
+        if 'version' not in spec and 'git' not in spec:
+            continue
+        normalized[name] = spec
+    return normalized
+
+
+# [package] keys in the order cargo writes them in normalized manifests
+PACKAGE_KEY_ORDER = (
+    'edition', 'rust-version', 'name', 'version', 'authors', 'build', 'metabuild', 'default-target',
+    'forced-target', 'links', 'exclude', 'include', 'publish', 'workspace', 'im-a-teapot', 'autolib',
+    'autobins', 'autoexamples', 'autotests', 'autobenches', 'default-run', 'description', 'homepage',
+    'documentation', 'readme', 'keywords', 'categories', 'license', 'license-file', 'repository',
+    'resolver', 'metadata',
+)
+
+# Top-level tables in the order cargo writes them
+MANIFEST_TABLE_ORDER = (
+    'cargo-features', 'package', 'badges', 'features', 'lib', 'bin', 'example', 'test', 'bench',
+) + DEPENDENCY_TABLES + ('target', 'lints', 'workspace', 'profile', 'patch', 'replace')
+
+# Target kinds cargo discovers: (table, auto* key, directory)
+TARGET_KINDS = (
+    ('bin', 'autobins', 'src/bin'),
+    ('example', 'autoexamples', 'examples'),
+    ('test', 'autotests', 'tests'),
+    ('bench', 'autobenches', 'benches'),
+)
+
+
+def discover_targets(manifest, files):
+    """
+    List the targets of a crate explicitly, like cargo package does.
+
+    Targets cargo would find on its own (src/lib.rs, src/main.rs, src/bin/*.rs,
+    tests/*.rs, ...) are added to the declared ones, unless the matching
+    auto* key turns discovery off, so the normalized manifest can set all of
+    them to false.
+
+    Args:
+        manifest: Parsed original Cargo.toml
+        files: Relative paths of the crate's files
+
+    Returns:
+        Dict of 'lib' (a table, if any) and 'bin', 'example', 'test', 'bench' (lists of tables)
+    """
+    package = manifest.get('package', {})
+    crate_name = package.get('name', '')
+    files = set(files)
+    targets = {}
+
+    if 'lib' in manifest or ('src/lib.rs' in files and package.get('autolib', True)):
+        lib = dict(manifest.get('lib', {}))
+        lib.setdefault('name', crate_name.replace('-', '_'))
+        lib.setdefault('path', 'src/lib.rs')
+        targets['lib'] = {'name': lib.pop('name'), 'path': lib.pop('path'), **lib}
+
+    for kind, auto_key, directory in TARGET_KINDS:
+        declared = {target['name']: dict(target) for target in manifest.get(kind, []) if 'name' in target}
+        found = {}
+        if package.get(auto_key, True):
+            if kind == 'bin' and 'src/main.rs' in files:
+                found[crate_name] = 'src/main.rs'
+            for path in sorted(files):
+                if not path.startswith(f"{directory}/"):
+                    continue
+                parts = path[len(directory) + 1:].split('/')
+                if len(parts) == 1 and parts[0].endswith('.rs'):
+                    found.setdefault(parts[0][:-len('.rs')], path)
+                elif len(parts) == 2 and parts[1] == 'main.rs':
+                    found.setdefault(parts[0], path)
+        for name, path in found.items():
+            declared.setdefault(name, {'name': name, 'path': path})
+        for name, target in declared.items():
+            # Declared targets without a path use the discovered file, or cargo's default one
+            target.setdefault('path', found.get(name, f"{directory}/{name}.rs"))
+        if declared:
+            targets[kind] = [
+                {'name': target.pop('name'), 'path': target.pop('path'), **target}
+                for _, target in sorted(declared.items())
+            ]
+    return targets
+
+
+def normalize_cargo_toml(content, files=()):
+    """
+    Normalize a Cargo.toml like cargo package does before publishing.
+
+    Targets are listed explicitly (discover_targets()) with automatic
+    discovery turned off, so the manifest means the same wherever the crate
+    is unpacked: tests/link_smoke.rs runs from the registry's archive too.
+
+    Args:
+        content: Original Cargo.toml (kept in the archive as Cargo.toml.orig)
+        files: Relative paths of the crate's files
+
+    Returns:
+        Normalized Cargo.toml content
+    """
+    import tomllib
+    manifest = tomllib.loads(content)
+
+    package = dict(manifest.get('package', {}))
+    package.setdefault('build', 'build.rs')
+    for key in ('autolib', 'autobins', 'autoexamples', 'autotests', 'autobenches'):
+        package[key] = False
+    package.setdefault('readme', 'README.md' if 'README.md' in files else False)
+    ordered = {key: package.pop(key) for key in PACKAGE_KEY_ORDER if key in package}
+    # Keys cargo doesn't know go before [package.metadata], which must stay last
+    metadata = ordered.pop('metadata', None)
+    ordered.update(package)
+    if metadata is not None:
+        ordered['metadata'] = metadata
+
+    manifest = {**manifest, 'package': ordered, **discover_targets(manifest, files)}
+    tables = {key: manifest[key] for key in MANIFEST_TABLE_ORDER if key in manifest}
+    tables.update((key, value) for key, value in manifest.items() if key not in tables)
+    for key in DEPENDENCY_TABLES:
+        if key in tables:
+            tables[key] = normalize_dependencies(tables[key])
+    for target, target_tables in tables.get('target', {}).items():
+        for key in DEPENDENCY_TABLES:
+            if key in target_tables:
+                target_tables[key] = normalize_dependencies(target_tables[key])
+
+    lines = []
+    for key, value in tables.items():
+        if is_table_array(value):
+            toml_table_array([key], value, lines)
+        else:
+            toml_table([key], value, lines)
+    return CARGO_TOML_HEADER + '\n' + '\n'.join(lines)
+
+
+def write_crate_archive(crate_dir, crate_name, version, output_path):
+    """
+    Package a crate directory as a .crate archive, the way cargo package does.
+
+    The archive has a single {crate_name}-{version}/ directory with the
+    normalized Cargo.toml and the original as Cargo.toml.orig. Files are
+    sorted and get a fixed mtime, mode (0644, also on Windows) and owner, and
+    the gzip header has no timestamp, so the same input always produces the same bytes (and SHA256).
+    Build output (target/, Cargo.lock) is left out.
+    """
+    import gzip
+    import io
+    crate_dir = Path(crate_dir)
+    root = f"{crate_name}-{version}"
+
+    files = {}
+    for path in crate_dir.rglob('*'):
+        relative = path.relative_to(crate_dir).as_posix()
+        if not path.is_file() or relative.split('/')[0] in ('target', 'Cargo.lock'):
+            continue
+        files[relative] = path.read_bytes()
+
+    original = files['Cargo.toml'].decode('utf-8')
+    files['Cargo.toml.orig'] = original.encode('utf-8')
+    files['Cargo.toml'] = normalize_cargo_toml(original, files).encode('utf-8')
+
+    tar_buffer = io.BytesIO()
+    with tarfile.open(fileobj=tar_buffer, mode='w', format=tarfile.GNU_FORMAT) as tar:
+        for relative in sorted(files):
+            data = files[relative]
+            info = tarfile.TarInfo(f"{root}/{relative}")
+            info.size = len(data)
+            info.mtime = CRATE_MTIME
+            info.mode = 0o644
+            info.uid = info.gid = 0
+            info.uname = info.gname = ''
+            tar.addfile(info, io.BytesIO(data))
+
+    with open(output_path, 'wb') as f:
+        with gzip.GzipFile(filename=Path(output_path).name, mode='wb', fileobj=f, compresslevel=9, mtime=0) as gz:
+            gz.write(tar_buffer.getvalue())
+    return output_path
 
 
 def cmd_generate_rust_crate(args):
@@ -1321,10 +3487,51 @@ def cmd_generate_rust_crate(args):
     # Get dependency graph
     dependency_graph = get_dependency_graph(package_ref, package_id, cache_path, profile)
 
+    try:
+        return generate_rust_crate(package_ref, binary_path, output_dir, dependency_graph,
+                                   recipe_path=find_recipe_file(cache_path), profile=profile, args=args)
+    except CrateGenerationError as e:
+        print(f"✗ Error: {e}")
+        return 1
+
+
+class CrateGenerationError(Exception):
+    """Exception raised when a crate can't be generated from a binary package"""
+    pass
+
+
+def generate_rust_crate(package_ref, binary_path, output_dir, dependency_graph=None,
+                        recipe_path=None, profile=None, args=None):
+    """
+    Generate a Rust crate from a binary package folder.
+
+    Doesn't need the package in the Conan cache: cmd_generate_rust_crate()
+    passes the cache folders, the server's packages/crate_generator.py the
+    contents of an uploaded binary tarball.
+
+    Args:
+        package_ref: Package reference (name/version)
+        binary_path: Package folder (lib/, include/, conaninfo.txt)
+        output_dir: Directory to write the crate folder and .crate archive to
+        dependency_graph: Output of conan graph info (optional)
+        recipe_path: conanfile.py, next to its exported conancrates.ini (optional)
+        profile: Conan profile to query cpp_info with when the graph has none
+                 (optional, needs the package in the Conan cache)
+        args: Generator options (multi_target, bindgen, bindgen_header,
+              bindgen_allowlist, cxx_manifest)
+
+    Returns:
+        (0, path to the .crate archive)
+
+    Raises:
+        CrateGenerationError: If the crate can't be generated (progress is
+                              printed, errors are only raised)
+    """
     # Extract dependencies from graph
     dependencies = []
     if dependency_graph:
         nodes = dependency_graph.get('graph', {}).get('nodes', {})
+        requirements = graph_requirements(nodes)
         for node_id, node in nodes.items():
             if node_id == "0":  # Skip root node
                 continue
@@ -1337,7 +3544,9 @@ def cmd_generate_rust_crate(args):
             dep_version = dep_version_with_hash.split('#')[0]
             dependencies.append({
                 'name': dep_name,
-                'version': dep_version
+                'version': dep_version,
+                'requirement': requirements.get(node_id),
+                'header_only': is_header_only_node(node)
             })
 
     if dependencies:
@@ -1348,11 +3557,11 @@ def cmd_generate_rust_crate(args):
 
     # Parse package name and version
     if '/' not in package_ref:
-        print(f"✗ Error: Invalid package reference format. Expected: name/version")
-        return 1
+        raise CrateGenerationError("Invalid package reference format. Expected: name/version")
 
     pkg_name, pkg_version = package_ref.split('/', 1)
     crate_name = f"{pkg_name.replace('_', '-')}-sys"
+    crate_version = cargo_version(pkg_version)
 
     # Create output directory
     crate_dir = Path(output_dir) / crate_name
@@ -1367,35 +3576,47 @@ def cmd_generate_rust_crate(args):
     include_dir = binary_path / 'include'
 
     # Find libraries
-    libraries = []
-    if lib_dir.exists():
-        for lib_file in lib_dir.iterdir():
-            if lib_file.suffix in ['.a', '.lib', '.so', '.dylib']:
-                # Extract library name (remove lib prefix and extension)
-                lib_name = lib_file.stem
-                # Only strip "lib" prefix from Unix-style libraries (.a, .so, .dylib)
-                # Windows .lib files don't follow the "lib" prefix convention:
-                #   - Static libs on Windows: libfoo.lib (keep "lib")
-                #   - Import libs on Windows: foo.lib (no "lib" to strip)
-                if lib_name.startswith('lib') and lib_file.suffix in ['.a', '.so', '.dylib']:
-                    lib_name = lib_name[3:]
-                libraries.append((lib_name, lib_file))
-
-    if not libraries:
+    libraries, runtime_files = find_libraries(binary_path)
+
+    # Link order, system libraries and frameworks from the package's cpp_info
+    package_node = find_package_node(dependency_graph, pkg_name)
+    cpp_info = package_node.get('cpp_info') if package_node else None
+    link_info = extract_link_info(cpp_info)
+    if link_info is None and profile:
+        cpp_info = get_package_cpp_info(package_ref, profile) or cpp_info
+        link_info = extract_link_info(cpp_info)
+
+    # Header-only and interface packages: nothing to link, no `links` key.
+    # When the graph has the recipe's package_type or cpp_info, decide like
+    # the crates depending on this one do (is_header_only_node())
+    if package_node and (package_node.get('package_type') or package_node.get('cpp_info') is not None):
+        header_only = is_header_only_node(package_node)
+    else:
+        header_only = not libraries and not runtime_files and link_info is None
+    if link_info is None:
+        if not header_only:
+            print(f"⚠ Warning: No cpp_info available, linking every library in {lib_dir} in directory order")
+    else:
+        unlisted = [lib_name for lib_name, _, _ in libraries if lib_name not in link_info['libs']]
+        if unlisted:
+            print(f"⚠ Warning: Not linking libraries missing from cpp_info.libs: {', '.join(unlisted)}")
+
+    if header_only:
+        print(f"{pkg_name} has nothing to link: generating a header-only crate")
+    elif not libraries:
         print(f"⚠ Warning: No libraries found in {lib_dir}")
     else:
         print(f"Found {len(libraries)} librar{'y' if len(libraries) == 1 else 'ies'}:")
-        for lib_name, lib_file in libraries:
-            print(f"  - {lib_name} ({lib_file.name})")
+        for lib_name, lib_file, kind in libraries:
+            print(f"  - {lib_name} ({lib_file.name}, {'shared' if kind == 'dylib' else 'static'})")
+    if runtime_files:
+        print(f"Found {len(runtime_files)} runtime file{'s' if len(runtime_files) != 1 else ''}:")
+        for runtime_file in runtime_files:
+            print(f"  - {runtime_file.name}")
     print()
 
     # Find headers
-    headers = []
-    if include_dir.exists():
-        for header_file in include_dir.rglob('*.h'):
-            headers.append(header_file)
-        for header_file in include_dir.rglob('*.hpp'):
-            headers.append(header_file)
+    headers = find_headers(include_dir)
 
     if not headers:
         print(f"⚠ Warning: No headers found in {include_dir}")
@@ -1407,13 +3628,57 @@ def cmd_generate_rust_crate(args):
             print(f"  ... and {len(headers) - 5} more")
     print()
 
-    # Copy libraries
-    native_dir = crate_dir / 'native' / 'current'
-    native_dir.mkdir(parents=True, exist_ok=True)
-
-    for lib_name, lib_file in libraries:
-        import shutil
-        shutil.copy2(lib_file, native_dir / lib_file.name)
+    # Copy libraries: native/current, or native/<target-triple> for multi-target crates
+    native_dir_name = 'current'
+    if not header_only:
+        if getattr(args, 'multi_target', False):
+            native_dir_name = conan_settings_to_rust_target(read_conaninfo_settings(binary_path))
+            if not native_dir_name:
+                raise CrateGenerationError(
+                    "Could not map the binary's settings to a Rust target triple "
+                    "(generate without --multi-target to use native/current)"
+                )
+            print(f"Rust target: {native_dir_name}\n")
+
+        native_dir = crate_dir / 'native' / native_dir_name
+        native_dir.mkdir(parents=True, exist_ok=True)
+
+        # Runtime files (DLLs, versioned shared objects) go next to the libraries
+        for lib_file in [lib_file for _, lib_file, _ in libraries] + runtime_files:
+            import shutil
+            shutil.copy2(lib_file, native_dir / lib_file.name)
+
+        # Link manifest read by build.rs, so build.rs itself doesn't depend on the target
+        manifest = link_manifest(libraries, link_info)
+
+        # Static C++ libraries need the C++ runtime, unless cpp_info's system_libs has it
+        if any(kind == 'static' for _, _, kind in libraries):
+            linked = {line.split('=', 1)[-1] for line in manifest}
+            runtime_libs = [lib for lib in cxx_runtime_libs(read_conaninfo_settings(binary_path)) if lib not in linked]
+            if runtime_libs:
+                print(f"C++ runtime: {', '.join(runtime_libs)}\n")
+            manifest += runtime_libs
+
+        with open(native_dir / 'libs.txt', 'w') as f:
+            for line in manifest:
+                f.write(f"{line}\n")
+
+        # Conan settings of the binary, compared with the Rust target by build.rs
+        settings = read_conaninfo_settings(binary_path)
+        if settings:
+            with open(native_dir / 'settings.txt', 'w') as f:
+                for key, value in sorted(settings.items()):
+                    f.write(f"{key}={value}\n")
+
+        # Preprocessor defines from cpp_info, exported to dependent crates by build.rs.
+        # C++ compiled against libstdc++'s pre-C++11 ABI has to select it too
+        defines = list(link_info['defines']) if link_info else []
+        if settings.get('compiler.libcxx') == 'libstdc++':
+            defines.append('_GLIBCXX_USE_CXX11_ABI=0')
+        if defines:
+            with open(native_dir / 'defines.txt', 'w') as f:
+                for define in defines:
+                    f.write(f"{define}\n")
 
     # Copy headers
     if headers:
@@ -1422,28 +3687,81 @@ def cmd_generate_rust_crate(args):
         import shutil
         shutil.copytree(include_dir, crate_include_dir, dirs_exist_ok=True)
 
+    # Link smoke test: exported functions that are declared in the headers
+    smoke_functions = smoke_test_functions(libraries, headers)
+    if smoke_functions:
+        (crate_dir / 'tests').mkdir(parents=True, exist_ok=True)
+        dependency_crates = [f"{dep['name'].replace('_', '-')}-sys" for dep in dependencies]
+        with open(crate_dir / 'tests' / 'link_smoke.rs', 'w') as f:
+            f.write(generate_link_smoke_test(crate_name, smoke_functions, dependency_crates))
+        print(f"✓ Generated tests/link_smoke.rs ({len(smoke_functions)} function{'s' if len(smoke_functions) != 1 else ''})\n")
+    elif not header_only:
+        print(f"⚠ Warning: No exported functions found in the headers, not generating a link smoke test\n")
+
+    # cxx bridge: CLI flag overrides the recipe's conancrates.ini [rust] section
+    rust_config = load_rust_config(recipe_path)
+    cxx_manifest_path = getattr(args, 'cxx_manifest', None)
+    if not cxx_manifest_path and rust_config.get('cxx_manifest'):
+        cxx_manifest_path = Path(recipe_path).parent / rust_config['cxx_manifest']
+
+    cxx_manifest = None
+    shim_name = f"{pkg_name}_shim"
+    if cxx_manifest_path:
+        try:
+            cxx_manifest = load_cxx_manifest(cxx_manifest_path)
+        except CxxManifestError as e:
+            raise CrateGenerationError(str(e))
+        missing = [header for header in cxx_manifest['headers'] if not (crate_dir / 'include' / header).exists()]
+        if missing:
+            print(f"⚠ Warning: Headers from the cxx manifest not in include/: {', '.join(missing)}")
+
+        print(f"Generating cxx bridge from {cxx_manifest_path}...")
+        bridge_rs, shim_h, shim_cc = generate_cxx_bridge(cxx_manifest, crate_name, shim_name)
+        (crate_dir / 'src').mkdir(parents=True, exist_ok=True)
+        (crate_dir / 'shim').mkdir(parents=True, exist_ok=True)
+        with open(crate_dir / 'src' / 'bridge.rs', 'w') as f:
+            f.write(bridge_rs)
+        with open(crate_dir / 'shim' / f'{shim_name}.h', 'w') as f:
+            f.write(shim_h)
+        with open(crate_dir / 'shim' / f'{shim_name}.cc', 'w') as f:
+            f.write(shim_cc)
+        print(f"✓ Generated src/bridge.rs and shim/{shim_name}.cc\n")
+
     # Generate Cargo.toml with dependencies
     dependencies_section = ""
-    if dependencies:
+    if dependencies or cxx_manifest:
         dependencies_section = "\n[dependencies]\n"
+        if cxx_manifest:
+            dependencies_section += 'cxx = "1.0"\n'
         for dep in dependencies:
             dep_crate_name = f"{dep['name'].replace('_', '-')}-sys"
+            # Keep the recipe's version range so workspaces can unify -sys crates
+            dep_requirement = cargo_requirement(dep['requirement'] or dep['version'], dep['version'])
             # Use path dependencies so crates work when extracted together
-            dependencies_section += f'{dep_crate_name} = {{ version = "{dep["version"]}", path = "../{dep_crate_name}" }}\n'
+            dependencies_section += f'{dep_crate_name} = {{ version = "{dep_requirement}", path = "../{dep_crate_name}" }}\n'
+    # Header-only dependencies have no `links` key: build.rs reads their include paths itself
+    build_dependencies = [dep for dep in dependencies if dep['header_only']]
+    if cxx_manifest or build_dependencies:
+        dependencies_section += '\n[build-dependencies]\n'
+        if cxx_manifest:
+            dependencies_section += 'cxx-build = "1.0"\n'
+        for dep in build_dependencies:
+            dep_crate_name = f"{dep['name'].replace('_', '-')}-sys"
+            dep_requirement = cargo_requirement(dep['requirement'] or dep['version'], dep['version'])
+            dependencies_section += f'{dep_crate_name} = {{ version = "{dep_requirement}", path = "../{dep_crate_name}" }}\n'
 
     cargo_toml_content = f'''[package]
 name = "{crate_name}"
-version = "{pkg_version}"
-edition = "2021"
-links = "{pkg_name}"
+version = "{crate_version}"
+edition = "2021"{'' if header_only else chr(10) + f'links = "{pkg_name}"'}
 
 # Include the binaries and headers in the published crate
 include = [
-    "src/**/*",
-    "native/**/*",
-    "include/**/*",
+    "src/**/*",{'' if header_only else chr(10) + '    "native/**/*",'}
+    "include/**/*",{chr(10) + '    "shim/**/*",' if cxx_manifest else ''}{chr(10) + '    "tests/**/*",' if smoke_functions else ''}
     "build.rs",
     "Cargo.toml",
+    "README.md",
 ]
 
 [lib]
@@ -1459,22 +3777,554 @@ path = "src/lib.rs"
     with open(crate_dir / 'Cargo.toml', 'w') as f:
         f.write(cargo_toml_content)
 
+    # Include paths of dependency crates, forwarded in this crate's cargo:include
+    # Overrides for linking an installed copy instead of the bundled libraries
+    env_prefix = sys_env_prefix(pkg_name)
+    pkg_config_module = pkg_config_name(cpp_info, pkg_name)
+    package_libraries = ', '.join(f'"{lib_name}"' for lib_name in (link_info['libs'] if link_info else [lib_name for lib_name, _, _ in libraries]))
+
+    dependency_include_vars = ', '.join(f'"{links_env_var(dep["name"], "include")}"' for dep in dependencies if not dep['header_only'])
+    dependency_libcxx_vars = ', '.join(f'"{links_env_var(dep["name"], "libcxx")}"' for dep in dependencies if not dep['header_only'])
+    header_only_includes = ''.join(f"{dep['name'].replace('-', '_')}_sys::include_paths(), " for dep in build_dependencies)
+
+    # Defines of header-only packages (they have no native/ directory for defines.txt)
+    package_defines = []
+    for section in (cpp_info or {}).values():
+        if isinstance(section, dict):
+            package_defines += [define for define in section.get('defines') or [] if define not in package_defines]
+    header_defines = ''
+    if cxx_manifest:
+        header_defines = f"\n    let defines: Vec<String> = vec![{', '.join(json.dumps(define) + '.to_string()' for define in package_defines)}];"
+
+    # cxx bridge: the shim is compiled against the same headers and defines as dependent crates
+    cxx_build_section = ''
+    if cxx_manifest:
+        cxx_build_section = f'''
+    // C++ shim behind the cxx bridge in src/bridge.rs
+    let mut bridge = cxx_build::bridge("src/bridge.rs");
+    bridge.file("shim/{shim_name}.cc").includes(&include_paths).std("{cxx_manifest.get('std', 'c++17')}");
+    for define in &defines {{
+        match define.split_once('=') {{
+            Some((name, value)) => bridge.define(name, Some(value)),
+            None => bridge.define(define, None),
+        }};
+    }}
+    bridge.compile("{pkg_name}_cxxbridge");
+    println!("cargo:rerun-if-changed=src/bridge.rs");
+    println!("cargo:rerun-if-changed=shim/");
+'''
+
+    # Header-only dependencies (build-dependencies), after the DEP_<LINKS>_INCLUDE ones
+    header_only_section = ''
+    if build_dependencies:
+        header_only_section = f'''    // Header-only dependencies have no `links` key, so no DEP_ variables: ask them
+    for path in [{header_only_includes.rstrip(', ')}].into_iter().flatten() {{
+        if !include_paths.contains(&path) {{
+            include_paths.push(path);
+        }}
+    }}
+'''
+
     # Generate build.rs
-    lib_links = '\n    '.join([f'println!("cargo:rustc-link-lib=static={lib_name}");' for lib_name, _ in libraries])
+    build_rs_content = f'''use std::collections::BTreeSet;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{{Path, PathBuf}};
+use std::process::Command;
 
-    build_rs_content = f'''fn main() {{
-    // Tell cargo where to find the pre-compiled libraries
-    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-    let lib_path = std::path::Path::new(&manifest_dir).join("native/current");
+/// Prefix of the environment variables overriding the bundled libraries
+const ENV_PREFIX: &str = "{env_prefix}";
 
-    println!("cargo:rustc-link-search=native={{}}", lib_path.display());
+/// pkg-config module of the package (used with {env_prefix}_USE_PKG_CONFIG)
+const PKG_CONFIG_NAME: &str = "{pkg_config_module}";
 
-    // Link the libraries
-    {lib_links}
+/// Libraries of the package, in link order
+const LIBRARIES: &[&str] = &[{package_libraries}];
 
+/// DEP_<LINKS>_INCLUDE variables of the dependency -sys crates
+const DEPENDENCY_INCLUDE_VARS: &[&str] = &[{dependency_include_vars}];
+
+/// DEP_<LINKS>_LIBCXX variables of the dependency -sys crates (C++ standard library)
+const DEPENDENCY_LIBCXX_VARS: &[&str] = &[{dependency_libcxx_vars}];
+
+fn main() {{
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let target = env::var("TARGET").unwrap();
+    let native_dir = Path::new(&manifest_dir).join("native");
+
+    for var in ["LIB_DIR", "INCLUDE_DIR", "STATIC", "USE_PKG_CONFIG", "STRICT"] {{
+        println!("cargo:rerun-if-env-changed={{}}_{{}}", ENV_PREFIX, var);
+    }}
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+
+    let lib_path = find_native_dir(&native_dir, &target);
+
+    let mut include_paths = Vec::new();
+    let mut defines = Vec::new();
+
+    if env_flag("USE_PKG_CONFIG") == Some(true) {{
+        // Installed copy found by pkg-config
+        link_pkg_config(&mut include_paths, &mut defines);
+    }} else if let Some(lib_dir) = env_var("LIB_DIR") {{
+        // Installed copy in {env_prefix}_LIB_DIR, linked like the bundled libraries
+        let lib_dir = PathBuf::from(lib_dir);
+        println!("cargo:rustc-link-search=native={{}}", lib_dir.display());
+        for lib in link_manifest(lib_path.as_deref()) {{
+            println!("cargo:rustc-link-lib={{}}", system_link_lib(&lib_dir, &lib));
+        }}
+        defines = read_defines(lib_path.as_deref());
+    }} else if lib_path.is_none() && env::var_os("DOCS_RS").is_some() {{
+        // Documentation builds (docs.rs, the registry's rustdoc hosting) don't link,
+        // so a crate without libraries for the documenting host is fine
+        println!("cargo:warning={crate_name} has no pre-compiled libraries for target {{}}, not linking (DOCS_RS is set)", target);
+    }} else {{
+        let lib_path = lib_path
+            .unwrap_or_else(|| panic!("{crate_name} has no pre-compiled libraries for target {{}} (set {env_prefix}_LIB_DIR or {env_prefix}_USE_PKG_CONFIG to use an installed copy)", target));
+
+        // The binaries have to match the target's ABI (OS, architecture, C and C++ runtime)
+        check_abi(&lib_path);
+
+        // Tell cargo where to find the pre-compiled libraries
+        println!("cargo:rustc-link-search=native={{}}", lib_path.display());
+
+        // Link the libraries listed in libs.txt, in order
+        for lib in link_manifest(Some(&lib_path)) {{
+            println!("cargo:rustc-link-lib={{}}", lib);
+        }}
+
+        // Shared libraries also have to be found at runtime
+        setup_shared_libraries(&lib_path);
+
+        defines = read_defines(Some(&lib_path));
+    }}
+
+    // Headers: {env_prefix}_INCLUDE_DIR, pkg-config's, or the bundled include/
+    if let Some(include_dir) = env_var("INCLUDE_DIR") {{
+        include_paths = env::split_paths(&include_dir).collect();
+    }} else if include_paths.is_empty() {{
+        let include_dir = Path::new(&manifest_dir).join("include");
+        if include_dir.is_dir() {{
+            include_paths.push(include_dir);
+        }}
+    }}
+
+    // Preprocessor defines of the package (DEP_<LINKS>_DEFINES in dependent build scripts)
+    if !defines.is_empty() {{
+        println!("cargo:defines={{}}", defines.join(","));
+    }}
+
+    // Metadata for dependent build scripts: DEP_<LINKS>_ROOT and DEP_<LINKS>_INCLUDE
+    // (this crate's headers followed by those of its dependencies, in PATH format)
+    println!("cargo:root={{}}", manifest_dir);
+    for var in DEPENDENCY_INCLUDE_VARS {{
+        if let Some(paths) = env::var_os(var) {{
+            for path in env::split_paths(&paths) {{
+                if !include_paths.contains(&path) {{
+                    include_paths.push(path);
+                }}
+            }}
+        }}
+    }}
+{header_only_section}    if !include_paths.is_empty() {{
+        let include = env::join_paths(&include_paths).unwrap();
+        println!("cargo:include={{}}", include.to_string_lossy());
+    }}
+{cxx_build_section}
     // Re-run if libraries change
     println!("cargo:rerun-if-changed=native/");
 }}
+
+/// Find the pre-compiled libraries for the target. Multi-target crates have
+/// native/<target-triple>/, single-target crates native/current/. Crates merged
+/// from binaries with different Conan options have one native/<target-triple>+<feature>...
+/// directory per option variant: the one whose features are exactly the enabled
+/// features is used.
+fn find_native_dir(native_dir: &Path, target: &str) -> Option<PathBuf> {{
+    let mut variants: Vec<(String, BTreeSet<String>)> = Vec::new();
+    for entry in fs::read_dir(native_dir).into_iter().flatten().flatten() {{
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let mut parts = name.split('+');
+        if parts.next() == Some(target) {{
+            let features = parts.map(String::from).collect();
+            variants.push((name, features));
+        }}
+    }}
+
+    if variants.is_empty() {{
+        let current = native_dir.join("current");
+        return current.is_dir().then_some(current);
+    }}
+
+    let enabled: BTreeSet<String> = variants
+        .iter()
+        .flat_map(|(_, features)| features.iter())
+        .filter(|feature| env::var_os(format!("CARGO_FEATURE_{{}}", feature.to_uppercase().replace('-', "_"))).is_some())
+        .cloned()
+        .collect();
+    match variants.iter().find(|(_, features)| *features == enabled) {{
+        Some((name, _)) => Some(native_dir.join(name)),
+        None => {{
+            let available: Vec<String> = variants
+                .iter()
+                .map(|(_, features)| format!("[{{}}]", features.iter().cloned().collect::<Vec<_>>().join(", ")))
+                .collect();
+            println!(
+                "cargo:warning=No pre-compiled libraries for {{}} with features [{{}}]; available feature sets: {{}}",
+                target,
+                enabled.iter().cloned().collect::<Vec<_>>().join(", "),
+                available.join(" ")
+            );
+            None
+        }}
+    }}
+}}
+
+/// Value of {env_prefix}_<name>, if set and not empty.
+fn env_var(name: &str) -> Option<OsString> {{
+    env::var_os(format!("{{}}_{{}}", ENV_PREFIX, name)).filter(|value| !value.is_empty())
+}}
+
+/// Value of a boolean {env_prefix}_<name> ("0", "false", "no" and "off" are false).
+fn env_flag(name: &str) -> Option<bool> {{
+    env_var(name).map(|value| {{
+        let value = value.to_string_lossy().to_lowercase();
+        !matches!(value.as_str(), "0" | "false" | "no" | "off")
+    }})
+}}
+
+/// Lines of libs.txt (cargo rustc-link-lib values), or the package's libraries
+/// when the target has no pre-compiled libraries.
+fn link_manifest(lib_path: Option<&Path>) -> Vec<String> {{
+    match lib_path.and_then(|path| fs::read_to_string(path.join("libs.txt")).ok()) {{
+        Some(libs) => libs.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect(),
+        None => LIBRARIES.iter().map(|lib| lib.to_string()).collect(),
+    }}
+}}
+
+/// Conan settings the binaries in lib_path were built with (settings.txt).
+fn read_settings(lib_path: &Path) -> Vec<(String, String)> {{
+    fs::read_to_string(lib_path.join("settings.txt"))
+        .map(|settings| {{
+            settings
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .collect()
+        }})
+        .unwrap_or_default()
+}}
+
+/// Rust target_os of a Conan os setting.
+fn rust_os(os: &str) -> Option<&'static str> {{
+    Some(match os {{
+        "Windows" | "WindowsStore" | "WindowsCE" => "windows",
+        "Linux" => "linux",
+        "Macos" => "macos",
+        "iOS" => "ios",
+        "watchOS" => "watchos",
+        "tvOS" => "tvos",
+        "Android" => "android",
+        "FreeBSD" => "freebsd",
+        "Emscripten" => "emscripten",
+        _ => return None,
+    }})
+}}
+
+/// Rust target_arch of a Conan arch setting.
+fn rust_arch(arch: &str) -> Option<&'static str> {{
+    Some(match arch {{
+        "x86_64" => "x86_64",
+        "x86" => "x86",
+        "armv8" | "armv8.3" | "arm64ec" => "aarch64",
+        "armv5el" | "armv5hf" | "armv6" | "armv7" | "armv7hf" | "armv7s" | "armv7k" => "arm",
+        "ppc64le" | "ppc64" => "powerpc64",
+        "ppc32" => "powerpc",
+        "s390x" => "s390x",
+        "riscv64" => "riscv64",
+        "riscv32" => "riscv32",
+        "mips" => "mips",
+        "mips64" => "mips64",
+        "wasm" => "wasm32",
+        _ => return None,
+    }})
+}}
+
+/// Compare the Conan settings of the bundled binaries with the Rust target
+/// (CARGO_CFG_TARGET_*). Mismatches are reported as cargo warnings, or fail
+/// the build when {env_prefix}_STRICT is set.
+fn check_abi(lib_path: &Path) {{
+    let settings = read_settings(lib_path);
+    if settings.is_empty() {{
+        return;
+    }}
+    let setting = |key: &str| settings.iter().find(|(name, _)| name == key).map_or("", |(_, value)| value.as_str());
+    let cfg = |name: &str| env::var(format!("CARGO_CFG_TARGET_{{}}", name)).unwrap_or_default();
+    let (target_os, target_arch, target_env) = (cfg("OS"), cfg("ARCH"), cfg("ENV"));
+    let crt_static = cfg("FEATURE").split(',').any(|feature| feature == "crt-static");
+    let mut problems = Vec::new();
+
+    if let Some(os) = rust_os(setting("os")).filter(|os| *os != target_os) {{
+        problems.push(format!("built for os={{}} ({{}}), the target OS is {{}}", setting("os"), os, target_os));
+    }}
+    if let Some(arch) = rust_arch(setting("arch")).filter(|arch| *arch != target_arch) {{
+        problems.push(format!("built for arch={{}} ({{}}), the target architecture is {{}}", setting("arch"), arch, target_arch));
+    }}
+
+    // Windows: MSVC and MinGW binaries don't mix, nor do the MSVC runtime variants
+    let runtime = setting("compiler.runtime");
+    let msvc_abi = matches!(setting("compiler"), "msvc" | "Visual Studio") || !runtime.is_empty();
+    if target_os == "windows" && setting("os").starts_with("Windows") {{
+        if msvc_abi && target_env != "msvc" {{
+            problems.push(format!("built with the MSVC ABI (compiler={{}}), the target environment is {{}}", setting("compiler"), target_env));
+        }} else if !msvc_abi && target_env == "msvc" {{
+            problems.push(format!("built with the MinGW ABI (compiler={{}}), the target environment is msvc", setting("compiler")));
+        }} else if msvc_abi {{
+            let static_runtime = matches!(runtime, "static" | "MT" | "MTd");
+            if static_runtime && !crt_static {{
+                problems.push(format!("built with the static MSVC runtime (compiler.runtime={{}}), build with -C target-feature=+crt-static", runtime));
+            }} else if !static_runtime && !runtime.is_empty() && crt_static {{
+                problems.push(format!("built with the dynamic MSVC runtime (compiler.runtime={{}}), the target links it statically (crt-static)", runtime));
+            }}
+            if setting("compiler.runtime_type") == "Debug" || matches!(runtime, "MTd" | "MDd") {{
+                problems.push("built with the debug MSVC runtime, Rust links the release runtime".to_string());
+            }}
+        }}
+    }}
+
+    // C++ standard library: exported as DEP_<LINKS>_LIBCXX and compared with the dependencies'
+    let libcxx = setting("compiler.libcxx");
+    if !libcxx.is_empty() {{
+        println!("cargo:libcxx={{}}", libcxx);
+        for var in DEPENDENCY_LIBCXX_VARS {{
+            if let Some(dep_libcxx) = env::var(var).ok().filter(|dep_libcxx| dep_libcxx != libcxx) {{
+                problems.push(format!("built with compiler.libcxx={{}}, a dependency with compiler.libcxx={{}} ({{}})", libcxx, dep_libcxx, var));
+            }}
+        }}
+    }}
+
+    if problems.is_empty() {{
+        return;
+    }}
+    if env_flag("STRICT") == Some(true) {{
+        panic!("{crate_name} binaries don't match the target {{}} ({{}}_STRICT is set):\\n  {{}}", env::var("TARGET").unwrap(), ENV_PREFIX, problems.join("\\n  "));
+    }}
+    for problem in problems {{
+        println!("cargo:warning={{}} (set {{}}_STRICT=1 to make this an error)", problem, ENV_PREFIX);
+    }}
+}}
+
+fn read_defines(lib_path: Option<&Path>) -> Vec<String> {{
+    lib_path
+        .and_then(|path| fs::read_to_string(path.join("defines.txt")).ok())
+        .map(|defines| defines.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect())
+        .unwrap_or_default()
+}}
+
+/// Link kind of a libs.txt entry for a library in lib_dir: {env_prefix}_STATIC
+/// forces static (1) or shared (0) linking, otherwise shared libraries are
+/// preferred. Libraries not in lib_dir (system libraries) are kept as listed.
+fn system_link_lib(lib_dir: &Path, lib: &str) -> String {{
+    let (kind, name) = match lib.split_once('=') {{
+        Some((kind, name)) => (Some(kind), name),
+        None => (None, lib),
+    }};
+    if kind == Some("framework") {{
+        return lib.to_string();
+    }}
+    let exists = |files: &[String]| files.iter().any(|file| lib_dir.join(file).exists());
+    let has_static = exists(&[format!("lib{{}}.a", name), format!("{{}}.lib", name)]);
+    let has_shared = exists(&[format!("lib{{}}.so", name), format!("lib{{}}.dylib", name), format!("lib{{}}.dll.a", name), format!("{{}}.lib", name)]);
+    let kind = match env_flag("STATIC") {{
+        Some(true) if has_static => "static",
+        Some(false) if has_shared => "dylib",
+        None if has_shared => "dylib",
+        None if has_static => "static",
+        _ => return lib.to_string(),
+    }};
+    format!("{{}}={{}}", kind, name)
+}}
+
+/// Link the installed package found by pkg-config.
+fn link_pkg_config(include_paths: &mut Vec<PathBuf>, defines: &mut Vec<String>) {{
+    for var in ["PKG_CONFIG", "PKG_CONFIG_PATH", "PKG_CONFIG_LIBDIR", "PKG_CONFIG_SYSROOT_DIR"] {{
+        println!("cargo:rerun-if-env-changed={{}}", var);
+    }}
+    let pkg_config = env::var("PKG_CONFIG").unwrap_or_else(|_| "pkg-config".to_string());
+    let is_static = env_flag("STATIC") == Some(true);
+    let mut command = Command::new(&pkg_config);
+    command.args(["--libs", "--cflags"]);
+    if is_static {{
+        command.arg("--static");
+    }}
+    let output = command
+        .arg(PKG_CONFIG_NAME)
+        .output()
+        .unwrap_or_else(|e| panic!("Could not run {{}}: {{}}", pkg_config, e));
+    if !output.status.success() {{
+        panic!(
+            "{{}} could not find {{}}: {{}}",
+            pkg_config,
+            PKG_CONFIG_NAME,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }}
+
+    let flags = String::from_utf8_lossy(&output.stdout);
+    let mut flags = flags.split_whitespace();
+    while let Some(flag) = flags.next() {{
+        if let Some(path) = flag.strip_prefix("-L") {{
+            println!("cargo:rustc-link-search=native={{}}", path);
+        }} else if let Some(lib) = flag.strip_prefix("-l") {{
+            if is_static && LIBRARIES.contains(&lib) {{
+                println!("cargo:rustc-link-lib=static={{}}", lib);
+            }} else {{
+                println!("cargo:rustc-link-lib={{}}", lib);
+            }}
+        }} else if let Some(path) = flag.strip_prefix("-I") {{
+            include_paths.push(PathBuf::from(path));
+        }} else if let Some(define) = flag.strip_prefix("-D") {{
+            defines.push(define.to_string());
+        }} else if flag == "-framework" {{
+            if let Some(framework) = flags.next() {{
+                println!("cargo:rustc-link-lib=framework={{}}", framework);
+            }}
+        }}
+    }}
+}}
+
+/// Make the shared libraries in lib_path loadable by the binaries being built.
+fn setup_shared_libraries(lib_path: &Path) {{
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let runtime_files: Vec<PathBuf> = fs::read_dir(lib_path)
+        .unwrap()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_runtime_library(path))
+        .collect();
+    if runtime_files.is_empty() {{
+        return;
+    }}
+
+    // Search paths inside the target directory are added to the library path
+    // of `cargo run` and `cargo test`, including those of dependent crates
+    for file in &runtime_files {{
+        fs::copy(file, out_dir.join(file.file_name().unwrap())).unwrap();
+    }}
+    println!("cargo:rustc-link-search=native={{}}", out_dir.display());
+
+    // Dependents' build scripts get the directory as DEP_<LINKS>_RUNTIME_DIR
+    println!("cargo:runtime_dir={{}}", out_dir.display());
+
+    // Copy them next to the artifacts too: Windows loads DLLs from the executable's
+    // directory, and on Linux and macOS executables linked with an $ORIGIN/@loader_path
+    // rpath (conancrates cargo-setup configures one) find them there. Only done when
+    // OUT_DIR is in the target directory's <profile>/build/<crate>-<hash>/out layout,
+    // otherwise where the artifacts go isn't known
+    match artifact_dir(&out_dir) {{
+        Some(profile_dir) => {{
+            for dir in [profile_dir.clone(), profile_dir.join("deps"), profile_dir.join("examples")] {{
+                for file in &runtime_files {{
+                    if let Err(error) = copy_if_changed(file, &dir) {{
+                        println!("cargo:warning=Could not copy {{}} to {{}}: {{}}", file.display(), dir.display(), error);
+                    }}
+                }}
+            }}
+        }}
+        None => println!(
+            "cargo:warning=Not copying the shared libraries of {pkg_name} next to the executables \\
+             (OUT_DIR {{}} isn't in <target-dir>/<profile>/build/): outside cargo, load them from there",
+            out_dir.display()
+        ),
+    }}
+    if env::var("CARGO_CFG_TARGET_OS").unwrap() != "windows" {{
+        // rpath to the copies (only applies to this crate's own tests and examples)
+        println!("cargo:rustc-link-arg=-Wl,-rpath,{{}}", out_dir.display());
+    }}
+}}
+
+/// <target-dir>/<profile> when OUT_DIR is <target-dir>/<profile>/build/<crate>-<hash>/out
+fn artifact_dir(out_dir: &Path) -> Option<PathBuf> {{
+    // A separate build directory holds OUT_DIR, not the artifacts
+    if env::var_os("CARGO_BUILD_BUILD_DIR").is_some() {{
+        return None;
+    }}
+    let build_dir = out_dir.parent()?.parent()?;
+    if out_dir.file_name()? != "out" || build_dir.file_name()? != "build" {{
+        return None;
+    }}
+    let profile_dir = build_dir.parent()?;
+    if !profile_dir.join("deps").is_dir() {{
+        return None;
+    }}
+    Some(profile_dir.to_path_buf())
+}}
+
+/// Copy a file into an existing directory unless an identical copy is there. The copy
+/// is renamed into place, so crates built in parallel never load a partial library.
+fn copy_if_changed(file: &Path, dir: &Path) -> std::io::Result<()> {{
+    if !dir.is_dir() {{
+        return Ok(());
+    }}
+    let name = file.file_name().unwrap();
+    let destination = dir.join(name);
+    let contents = fs::read(file)?;
+    if fs::read(&destination).map_or(false, |existing| existing == contents) {{
+        return Ok(());
+    }}
+    let partial = dir.join(format!(".{{}}.{{}}.partial", name.to_string_lossy(), std::process::id()));
+    fs::write(&partial, &contents)?;
+    fs::rename(&partial, &destination).map_err(|error| {{
+        let _ = fs::remove_file(&partial);
+        error
+    }})
+}}
+
+fn is_runtime_library(path: &Path) -> bool {{
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    name.ends_with(".dll") || name.ends_with(".dylib") || name.ends_with(".so") || name.contains(".so.")
+}}
+'''
+
+    if header_only:
+        build_rs_content = f'''//! {pkg_name} is header-only: nothing to link. Cargo only passes build script
+//! metadata (DEP_ variables) to dependents of crates with a `links` key, so the
+//! include paths are compiled into the crate instead: see include_paths() in
+//! src/lib.rs.
+
+use std::env;
+use std::path::PathBuf;
+
+/// Prefix of the environment variables overriding the bundled headers
+const ENV_PREFIX: &str = "{env_prefix}";
+
+/// DEP_<LINKS>_INCLUDE variables of the dependency -sys crates
+const DEPENDENCY_INCLUDE_VARS: &[&str] = &[{dependency_include_vars}];
+
+fn main() {{
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    println!("cargo:rerun-if-env-changed={{}}_INCLUDE_DIR", ENV_PREFIX);
+
+    // Headers: {env_prefix}_INCLUDE_DIR or the bundled include/, then those of the dependencies
+    let mut include_paths: Vec<PathBuf> = match env::var_os(format!("{{}}_INCLUDE_DIR", ENV_PREFIX)).filter(|value| !value.is_empty()) {{
+        Some(include_dir) => env::split_paths(&include_dir).collect(),
+        None => vec![PathBuf::from(&manifest_dir).join("include")],
+    }};{header_defines}
+    for var in DEPENDENCY_INCLUDE_VARS {{
+        if let Some(paths) = env::var_os(var) {{
+            for path in env::split_paths(&paths) {{
+                if !include_paths.contains(&path) {{
+                    include_paths.push(path);
+                }}
+            }}
+        }}
+    }}
+{header_only_section}
+    // Read by include_paths() in src/lib.rs
+    let include = env::join_paths(&include_paths).unwrap();
+    println!("cargo:rustc-env=CONANCRATES_INCLUDE_PATHS={{}}", include.to_string_lossy());
+{cxx_build_section}
+    println!("cargo:rerun-if-changed=include/");
+}}
 '''
 
     with open(crate_dir / 'build.rs', 'w') as f:
@@ -1484,47 +4334,145 @@ path = "src/lib.rs"
     src_dir = crate_dir / 'src'
     src_dir.mkdir(parents=True, exist_ok=True)
 
-    lib_rs_content = f'''//! Rust FFI bindings for {pkg_name}
-//!
-//! This crate provides pre-compiled binaries for {pkg_name}.
-//! The binaries are linked statically.
-
-#![allow(non_upper_case_globals)]
-#![allow(non_camel_case_types)]
-#![allow(non_snake_case)]
+    # Bindgen: CLI flags override the recipe's conancrates.ini [rust] section
+    use_bindgen = getattr(args, 'bindgen', False) or rust_config.get('bindgen', '').lower() in ('1', 'true', 'yes', 'on')
+    bindgen_header = getattr(args, 'bindgen_header', None) or rust_config.get('bindgen_header')
+    bindgen_allowlist = getattr(args, 'bindgen_allowlist', None) or rust_config.get('bindgen_allowlist', '').split()
 
-// TODO: Add your FFI declarations here
+    has_bindings = False
+    if use_bindgen:
+        if not headers:
+            print(f"⚠ Warning: bindgen requested but the package has no headers")
+        else:
+            if not bindgen_header:
+                bindgen_header = find_bindgen_header(include_dir, pkg_name, headers)
+            if not bindgen_header:
+                print(f"⚠ Warning: Could not pick a header for bindgen, set one with --bindgen-header")
+            else:
+                print(f"Generating bindings from include/{bindgen_header}...")
+                # Run against the copied headers so the bindings don't depend on the cache path
+                has_bindings = run_bindgen(
+                    crate_dir / 'include' / bindgen_header,
+                    crate_dir / 'include',
+                    src_dir / 'bindings.rs',
+                    bindgen_allowlist,
+                    link_info['defines'] if link_info else None
+                )
+                if has_bindings:
+                    print(f"✓ Generated src/bindings.rs\n")
+
+    if has_bindings:
+        bindings_section = f'''// Generated by bindgen from include/{bindgen_header}
+mod bindings;
+pub use bindings::*;'''
+    elif cxx_manifest:
+        bindings_section = ''
+    else:
+        bindings_section = '''// TODO: Add your FFI declarations here
 // You can use bindgen to auto-generate bindings from the C headers in include/
+// (conancrates generate-rust-crate --bindgen)
 //
 // Example:
-// extern "C" {{
+// extern "C" {
 //     pub fn my_function() -> i32;
-// }}
-
+// }'''
+
+    cxx_section = ''
+    if cxx_manifest:
+        cxx_section = '''// C++ API exposed through the cxx bridge (src/bridge.rs, shim/)
+pub mod bridge;
+pub use bridge::ffi;'''
+        if has_bindings:
+            cxx_section = '\n\n' + cxx_section
+
+    # tests/link_smoke.rs already checks the crate links
+    tests_section = '''
 #[cfg(test)]
-mod tests {{
+mod tests {
     use super::*;
 
     #[test]
-    fn test_link() {{
+    fn test_link() {
         // Add a test that uses your FFI functions
-    }}
+    }
+}
+''' if not smoke_functions else ''
+
+    linkage = 'dynamically' if any(kind == 'dylib' for _, _, kind in libraries) else 'statically'
+    lib_rs_content = f'''//! Rust FFI bindings for {pkg_name}
+//!
+//! This crate provides pre-compiled binaries for {pkg_name}.
+//! The binaries are linked {linkage}.
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+{bindings_section}{cxx_section}
+{tests_section}'''
+
+    if header_only:
+        defines_list = ', '.join(json.dumps(define) for define in package_defines)
+        lib_rs_content = f'''//! Headers of {pkg_name}
+//!
+//! {pkg_name} is header-only: this crate has no library to link and no `links`
+//! key. Add it to `[build-dependencies]` and pass include_paths() to the C/C++
+//! compiler (cc, cxx-build, bindgen) in your build script.
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use std::path::PathBuf;
+
+/// Preprocessor definitions required by the headers ("NAME" or "NAME=VALUE")
+pub const DEFINES: &[&str] = &[{defines_list}];
+
+/// Include directories of {pkg_name} and its dependencies, as seen when this crate was built
+pub fn include_paths() -> Vec<PathBuf> {{
+    std::env::split_paths(env!("CONANCRATES_INCLUDE_PATHS")).collect()
 }}
-'''
+{chr(10) + bindings_section + cxx_section + chr(10) if has_bindings or cxx_manifest else ''}'''
 
     with open(src_dir / 'lib.rs', 'w') as f:
         f.write(lib_rs_content)
 
     # Generate README
-    readme_content = f'''# {crate_name}
+    if header_only:
+        contents_section = f'''This crate contains the headers of the Conan package. {pkg_name} is
+header-only: there is no library to link.
 
-Rust FFI bindings for {pkg_name} {pkg_version}.
+## Usage
+
+Add this to your `Cargo.toml`:
+
+```toml
+[build-dependencies]
+{crate_name} = "{version_requirement(crate_version)}"
+```
+
+Then pass its include paths to the C/C++ compiler in your `build.rs`:
+
+```rust
+let mut build = cc::Build::new();
+build.includes({crate_name.replace('-', '_')}::include_paths());
+for define in {crate_name.replace('-', '_')}::DEFINES {{
+    match define.split_once('=') {{
+        Some((name, value)) => build.define(name, value),
+        None => build.define(define, None),
+    }};
+}}
+```
+
+## Building
 
-This crate contains pre-compiled binaries from the Conan package.
+To use an installed copy of the headers instead, set `{env_prefix}_INCLUDE_DIR`.'''
+    else:
+        contents_section = f'''This crate contains pre-compiled binaries from the Conan package.
 
 ## Libraries included:
 
-{chr(10).join([f"- {lib_name}" for lib_name, _ in libraries])}
+{chr(10).join([f"- {lib_name} ({'shared' if kind == 'dylib' else 'static'})" for lib_name, _, kind in libraries])}
 
 ## Usage
 
@@ -1532,7 +4480,7 @@ Add this to your `Cargo.toml`:
 
 ```toml
 [dependencies]
-{crate_name} = "{pkg_version}"
+{crate_name} = "{version_requirement(crate_version)}"
 ```
 
 Then use it in your code:
@@ -1545,9 +4493,18 @@ extern crate {crate_name.replace('-', '_')};
 
 ## Building
 
-This crate includes pre-compiled static libraries and does not require compilation
+This crate includes pre-compiled libraries and does not require compilation
 of the C/C++ source code. The libraries are linked during the Rust build process.
 
+To link an installed copy instead, set `{env_prefix}_LIB_DIR` (and optionally
+`{env_prefix}_INCLUDE_DIR` and `{env_prefix}_STATIC`), or `{env_prefix}_USE_PKG_CONFIG=1`.'''
+
+    readme_content = f'''# {crate_name}
+
+Rust FFI bindings for {pkg_name} {pkg_version}.
+
+{contents_section}
+
 ## Source
 
 Generated from Conan package: {package_ref}
@@ -1563,26 +4520,37 @@ Generated from Conan package: {package_ref}
     print(f"  ├── build.rs")
     print(f"  ├── README.md")
     print(f"  ├── src/")
-    print(f"  │   └── lib.rs")
-    print(f"  ├── native/")
-    print(f"  │   └── current/       ({len(libraries)} librar{'y' if len(libraries) == 1 else 'ies'})")
+    if has_bindings:
+        print(f"  │   ├── lib.rs")
+        print(f"  │   └── bindings.rs")
+    else:
+        print(f"  │   └── lib.rs")
+    if not header_only:
+        print(f"  ├── native/")
+        print(f"  │   └── {native_dir_name}/       ({len(libraries)} librar{'y' if len(libraries) == 1 else 'ies'})")
     if headers:
-        print(f"  └── include/           ({len(headers)} header file{'s' if len(headers) != 1 else ''})")
+        print(f"  {'├' if smoke_functions else '└'}── include/           ({len(headers)} header file{'s' if len(headers) != 1 else ''})")
+    if smoke_functions:
+        print(f"  └── tests/")
+        print(f"      └── link_smoke.rs")
 
     # Package as a .crate file (tar.gz)
     print(f"\nPackaging as .crate archive...")
-    import tarfile
-    crate_archive_name = f"{crate_name}-{pkg_version}.crate"
+    crate_archive_name = f"{crate_name}-{crate_version}.crate"
     crate_archive_path = Path(output_dir) / crate_archive_name
 
-    with tarfile.open(crate_archive_path, "w:gz") as tar:
-        tar.add(crate_dir, arcname=crate_name)
+    write_crate_archive(crate_dir, crate_name, crate_version, crate_archive_path)
 
     print(f"✓ Created: {crate_archive_path}")
 
     print(f"\nNext steps:")
     print(f"  1. cd {crate_dir}")
-    print(f"  2. Edit src/lib.rs to add FFI declarations")
+    if has_bindings:
+        print(f"  2. Review the generated bindings in src/bindings.rs")
+    elif header_only:
+        print(f"  2. Add the crate to [build-dependencies] and use include_paths() in build.rs")
+    else:
+        print(f"  2. Edit src/lib.rs to add FFI declarations")
     print(f"  3. Run: cargo build")
     print(f"  4. Run: cargo test")
     print(f"  5. Optional: cargo publish (if you want to publish to crates.io)")
@@ -1591,6 +4559,60 @@ Generated from Conan package: {package_ref}
     return (0, str(crate_archive_path))
 
 
+def cmd_merge_rust_crates(args):
+    """
+    Merge the Rust crates of every uploaded binary of a package version into
+    one multi-target crate with a native/<target-triple>/ directory per binary.
+    """
+    package_ref = args.package_ref
+    server_url = args.server or "http://localhost:8000"
+
+    if '/' not in package_ref:
+        print(f"Error: Invalid package reference. Use format: package_name/version")
+        return 1
+
+    package_name, version = package_ref.split('/', 1)
+
+    print(f"ConanCrates Rust Crate Merge")
+    print(f"{'='*60}")
+    print(f"Package: {package_ref}")
+    print(f"Server: {server_url}")
+    print(f"{'='*60}\n")
+
+    merge_url = f"{server_url}/api/packages/{package_name}/{version}/rust-crate/merge"
+    try:
+        response = requests.post(merge_url)
+    except Exception as e:
+        print(f"✗ Error: {e}")
+        return 1
+
+    try:
+        data = response.json()
+    except ValueError:
+        print(f"✗ Merge failed: HTTP {response.status_code}")
+        return 1
+
+    if response.status_code != 200:
+        print(f"✗ Merge failed: {data.get('error', f'HTTP {response.status_code}')}")
+        return 1
+
+    print(f"✓ Merged {data['crate_name']} {data['version']} for {len(data['targets'])} target(s):")
+    for target in data['targets']:
+        print(f"  - {target}")
+    if data.get('features'):
+        print(f"Features (Conan option variants):")
+        for feature, options in data['features'].items():
+            print(f"  - {feature}: {', '.join(f'{name}={value}' for name, value in options.items())}")
+    if data['skipped_binaries']:
+        print(f"\n⚠ Skipped {len(data['skipped_binaries'])} binar{'y' if len(data['skipped_binaries']) == 1 else 'ies'} "
+              f"(unknown target or another build for the same target):")
+        for package_id in data['skipped_binaries']:
+            print(f"  - {package_id[:8]}...")
+    print(f"\nSHA256: {data['sha256']}")
+
+    return 0
+
+
 def main():
     parser = argparse.ArgumentParser(
         description='ConanCrates CLI - Upload and download Conan packages to/from ConanCrates registry'
@@ -1666,6 +4688,126 @@ def main():
         '-o', '--output',
         help='Output directory (default: ./rust_crates)'
     )
+    rust_parser.add_argument(
+        '--package-id',
+        help='Package ID of the binary to generate the crate from'
+    )
+    rust_parser.add_argument(
+        '--multi-target',
+        action='store_true',
+        help='Put libraries in native/<rust-target-triple>/ instead of native/current/'
+    )
+    rust_parser.add_argument(
+        '--bindgen',
+        action='store_true',
+        help='Generate src/bindings.rs from the package headers with bindgen'
+    )
+    rust_parser.add_argument(
+        '--bindgen-header',
+        help='Header to run bindgen on, relative to include/ (e.g., mylib/mylib.h)'
+    )
+    rust_parser.add_argument(
+        '--bindgen-allowlist',
+        action='append',
+        help='Regex of functions, types and variables to generate bindings for (repeatable)'
+    )
+    rust_parser.add_argument(
+        '--cxx-manifest',
+        help='cxx bridge manifest (TOML) listing the C++ classes and functions to expose'
+    )
+
+    # Cargo setup command
+    cargo_setup_parser = subparsers.add_parser('cargo-setup',
+                                               help="Download a package's crates and set up a Rust project to use them")
+    cargo_setup_parser.add_argument(
+        'package_ref',
+        help='Package reference (e.g., mylib/1.0.0)'
+    )
+    cargo_setup_parser.add_argument(
+        '-pr', '--profile',
+        required=True,
+        help='Conan profile to match the binary with (required)'
+    )
+    cargo_setup_parser.add_argument(
+        '--project-dir',
+        default='.',
+        help='Rust project to set up (default: current directory)'
+    )
+    cargo_setup_parser.add_argument(
+        '--vendor-dir',
+        default='vendor/conancrates',
+        help='Directory to unpack the crates into, relative to the project (default: vendor/conancrates)'
+    )
+    cargo_setup_parser.add_argument(
+        '--mode',
+        choices=['patch', 'source', 'registry'],
+        default='patch',
+        help='patch: [patch.crates-io] entries for the vendored crates (default); '
+             'source: replace crates-io with the vendor directory; '
+             'registry: use the ConanCrates registry, nothing is vendored'
+    )
+
+    # Lock command
+    lock_parser = subparsers.add_parser('lock',
+                                        help='Resolve packages for a profile and write conancrates.lock')
+    lock_parser.add_argument(
+        'package_refs',
+        nargs='+',
+        help='Package references (e.g., mylib/1.0.0)'
+    )
+    lock_parser.add_argument(
+        '-pr', '--profile',
+        required=True,
+        help='Conan profile to match the binaries with (required)'
+    )
+    lock_parser.add_argument(
+        '--project-dir',
+        default='.',
+        help='Rust project to write conancrates.lock in (default: current directory)'
+    )
+
+    # Install command
+    install_parser = subparsers.add_parser('install',
+                                           help='Install the crates of conancrates.lock into a Rust project')
+    install_parser.add_argument(
+        'package_refs',
+        nargs='*',
+        help='Package references (default: the packages of conancrates.lock)'
+    )
+    install_parser.add_argument(
+        '-pr', '--profile',
+        help='Conan profile to lock the packages with, when conancrates.lock needs updating'
+    )
+    install_parser.add_argument(
+        '--locked',
+        action='store_true',
+        help='Fail instead of updating conancrates.lock when it is missing or out of date'
+    )
+    install_parser.add_argument(
+        '--project-dir',
+        default='.',
+        help='Rust project to install into (default: current directory)'
+    )
+    install_parser.add_argument(
+        '--vendor-dir',
+        default='vendor/conancrates',
+        help='Directory to unpack the crates into, relative to the project (default: vendor/conancrates)'
+    )
+    install_parser.add_argument(
+        '--mode',
+        choices=['patch', 'source'],
+        default='patch',
+        help='patch: [patch.crates-io] entries for the vendored crates (default); '
+             'source: replace crates-io with the vendor directory'
+    )
+
+    # Merge Rust crates command
+    merge_parser = subparsers.add_parser('merge-rust-crates',
+                                         help='Merge the Rust crates of all binaries of a package version into one multi-target crate')
+    merge_parser.add_argument(
+        'package_ref',
+        help='Package reference (e.g., mylib/1.0.0)'
+    )
 
     args = parser.parse_args()
 
@@ -1675,6 +4817,14 @@ def main():
         return cmd_download(args)
     elif args.command == 'generate-rust-crate':
         return cmd_generate_rust_crate(args)
+    elif args.command == 'cargo-setup':
+        return cmd_cargo_setup(args)
+    elif args.command == 'lock':
+        return cmd_lock(args)
+    elif args.command == 'install':
+        return cmd_install(args)
+    elif args.command == 'merge-rust-crates':
+        return cmd_merge_rust_crates(args)
     else:
         parser.print_help()
         return 1
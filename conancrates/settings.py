@@ -155,6 +155,24 @@ STORAGES = {
     },
 }
 
+# Rust crates for binaries uploaded without one (see packages/crate_generator.py):
+# "background" (thread after upload), "inline" (during the upload request) or "off"
+RUST_CRATE_GENERATION = 'background'
+
+# Rustdoc of generated and published crates, served at /docs/<crate>/<version>/ (see packages/crate_docs.py):
+# "background", "inline" or "off", like RUST_CRATE_GENERATION
+RUST_DOCS_GENERATION = 'background'
+# Sandbox of cargo doc, which runs the build scripts of published crates: "bubblewrap"
+# (bwrap without network, only system and toolchain directories read-only), or a command
+# prefix whose arguments may use {build_dir}. Docs aren't built without a sandbox.
+RUST_DOCS_SANDBOX = 'bubblewrap'
+# Seconds before a cargo doc run is killed
+RUST_DOCS_TIMEOUT = 600
+
+# Symbols and headers of uploaded binaries, searched at /symbols/ (see packages/symbol_index.py):
+# "background", "inline" or "off", like RUST_CRATE_GENERATION
+SYMBOL_INDEXING = 'background'
+
 # REST Framework settings
 REST_FRAMEWORK = {
     'DEFAULT_PAGINATION_CLASS': 'rest_framework.pagination.PageNumberPagination',
@@ -4,6 +4,8 @@ Django admin configuration for ConanCrates
 from .package_admin import PackageAdmin
 from .package_version_admin import PackageVersionAdmin
 from .binary_package_admin import BinaryPackageAdmin
+from .binary_symbol_admin import BinarySymbolAdmin
+from .cargo_token_admin import CargoTokenAdmin
 from .dependency_admin import DependencyAdmin
 from .topic_admin import TopicAdmin
 
@@ -11,6 +13,8 @@ __all__ = [
     'PackageAdmin',
     'PackageVersionAdmin',
     'BinaryPackageAdmin',
+    'BinarySymbolAdmin',
+    'CargoTokenAdmin',
     'DependencyAdmin',
     'TopicAdmin',
 ]
@@ -6,7 +6,7 @@ from packages.models import BinaryPackage
 class BinaryPackageAdmin(admin.ModelAdmin):
     list_display = ['package_version', 'package_id_short', 'os', 'arch', 'compiler',
                     'build_type', 'file_size_mb', 'download_count', 'created_at']
-    list_filter = ['os', 'arch', 'compiler', 'build_type', 'created_at']
+    list_filter = ['os', 'arch', 'compiler', 'build_type', 'rust_target', 'created_at']
     search_fields = ['package_version__package__name', 'package_id']
     readonly_fields = ['created_at', 'download_count', 'file_size']
 
@@ -15,11 +15,15 @@ class BinaryPackageAdmin(admin.ModelAdmin):
             'fields': ['package_version', 'package_id']
         }),
         ('Configuration', {
-            'fields': ['os', 'arch', 'compiler', 'compiler_version', 'build_type', 'options']
+            'fields': ['os', 'arch', 'compiler', 'compiler_version', 'compiler_libcxx', 'compiler_runtime',
+                       'build_type', 'rust_target', 'options']
         }),
         ('Binary File', {
             'fields': ['binary_file', 'file_size', 'sha256']
         }),
+        ('Rust Crate', {
+            'fields': ['rust_crate_file', 'rust_crate_sha256', 'rust_crate_header_only']
+        }),
         ('Statistics', {
             'fields': ['download_count', 'created_at'],
             'classes': ['collapse']
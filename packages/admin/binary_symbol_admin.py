@@ -0,0 +1,17 @@
+from django.contrib import admin
+from packages.models import BinarySymbol
+
+
+@admin.register(BinarySymbol)
+class BinarySymbolAdmin(admin.ModelAdmin):
+    list_display = ['name', 'kind', 'library', 'binary']
+    list_filter = ['kind', 'binary__os', 'binary__arch']
+    search_fields = ['name', 'binary__package_version__package__name']
+    # One binary has thousands of symbols: don't render a select of all binaries
+    raw_id_fields = ['binary']
+
+    fieldsets = [
+        ('Symbol Information', {
+            'fields': ['binary', 'kind', 'name', 'basename', 'library']
+        }),
+    ]
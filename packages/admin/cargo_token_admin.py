@@ -0,0 +1,13 @@
+from django.contrib import admin
+from packages.models import CargoToken
+
+
+@admin.register(CargoToken)
+class CargoTokenAdmin(admin.ModelAdmin):
+    list_display = ['user', 'name', 'created_at', 'last_used_at']
+    search_fields = ['user__username', 'name']
+    # Tokens are created with the create_cargo_token command, which prints them once
+    readonly_fields = ['token_sha256', 'created_at', 'last_used_at']
+
+    def has_add_permission(self, request):
+        return False
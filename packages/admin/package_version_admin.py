@@ -34,6 +34,13 @@ class PackageVersionAdmin(admin.ModelAdmin):
         ('Recipe', {
             'fields': ['recipe_revision', 'recipe_file']
         }),
+        ('Rust Crate', {
+            'fields': ['cargo_version', 'rust_crate_file', 'rust_crate_sha256', 'rust_crate_targets', 'rust_crate_features',
+                       'rust_crate_indexed_at']
+        }),
+        ('Rust Docs', {
+            'fields': ['rust_docs_path', 'rust_docs_error']
+        }),
         ('Upload Information', {
             'fields': ['uploaded_by', 'created_at', 'updated_at'],
             'classes': ['collapse']
@@ -0,0 +1,785 @@
+"""
+Cargo registry support for ConanCrates
+
+Maps Conan packages to the Rust -sys crates generated from them (and to
+crates published with cargo publish) and builds the entries served by the
+Cargo sparse index. The index layout, entry format and publish payload follow
+the Cargo registry specification:
+https://doc.rust-lang.org/cargo/reference/registry-index.html
+https://doc.rust-lang.org/cargo/reference/registry-web-api.html
+"""
+import io
+import re
+import gzip
+import json
+import struct
+import tarfile
+import hashlib
+import secrets
+import tomllib
+from django.db.models import Q
+from django.utils import timezone
+from packages.models import Package, PackageVersion, BinaryPackage, CargoToken
+from packages.cargo_versions import SEMVER_RE, version_requirement, cargo_version_for, cargo_requirement
+from packages.rust_targets import rust_target_for_binary
+
+
+class PublishError(Exception):
+    """Exception raised when a cargo publish request is invalid"""
+    pass
+
+
+# Crate names: ASCII letter first, then letters, digits, '-' or '_' (max 64 chars)
+CRATE_NAME_RE = re.compile(r'^[A-Za-z][A-Za-z0-9_-]{0,63}$')
+
+DEPENDENCY_KINDS = ('normal', 'dev', 'build')
+
+# Name prefix of the packages holding crates published with cargo publish that
+# aren't the -sys crate of a Conan package. Conan package names can't contain
+# ':', so a wrapper crate named "zlib" never ends up in the Conan package zlib.
+PUBLISHED_PACKAGE_PREFIX = 'cargo:'
+
+# Prefix of the tokens of cargo login, so leaked ones are easy to recognize
+CARGO_TOKEN_PREFIX = 'ccr_'
+
+# Entry mtime used by cargo package (and the conancrates CLI), so archives are reproducible
+CRATE_MTIME = 1153704088
+
+
+def crate_name_for_package(package_name):
+    """
+    Get the -sys crate name generated for a Conan package.
+
+    Args:
+        package_name: Conan package name (e.g., "my_lib")
+
+    Returns:
+        Crate name (e.g., "my-lib-sys")
+    """
+    return f"{package_name.replace('_', '-')}-sys"
+
+
+def find_package_for_crate(crate_name):
+    """
+    Find the package a crate belongs to.
+
+    Crate names are case-insensitive and the generator replaces '_' with '-',
+    so "my-lib-sys" matches a package named "my_lib" or "my-lib". Other
+    crates (e.g., published wrapper crates, or -sys crates without a Conan
+    package) belong to the package of published_package_name(), never to a
+    Conan package.
+
+    Returns:
+        Package or None
+    """
+    crate_name = crate_name.lower()
+
+    if crate_name.endswith('-sys'):
+        base_name = crate_name[:-len('-sys')]
+        if base_name:
+            package = Package.objects.filter(
+                Q(name__iexact=base_name) | Q(name__iexact=base_name.replace('-', '_'))
+            ).order_by('name').first()
+            if package:
+                return package
+
+    return Package.objects.filter(
+        Q(name__iexact=published_package_name(crate_name))
+        | Q(name__iexact=published_package_name(crate_name.replace('-', '_')))
+        | Q(name__iexact=published_package_name(crate_name.replace('_', '-')))
+    ).order_by('name').first()
+
+
+def published_package_name(crate_name):
+    """Get the name of the package holding a published crate (e.g. "cargo:zlib-safe")."""
+    return f"{PUBLISHED_PACKAGE_PREFIX}{crate_name}"
+
+
+def is_published_package(package):
+    """Check if a package holds a published crate rather than a Conan package."""
+    return package.name.startswith(PUBLISHED_PACKAGE_PREFIX)
+
+
+def can_publish_to_package(user, package):
+    """
+    Check if a user may publish a crate version to a package: staff, or
+    someone who uploaded one of its versions. For a Conan package these are
+    its uploaders; the package of a published crate is created by its first
+    publish, so its first publisher owns it.
+    """
+    if user is None or not user.is_authenticated:
+        return False
+    return user.is_staff or package.versions.filter(uploaded_by=user).exists()
+
+
+def create_cargo_token(user, name=''):
+    """
+    Create a token for cargo login.
+
+    Returns:
+        The token; only its SHA256 is stored, so it can't be shown again
+    """
+    token = f"{CARGO_TOKEN_PREFIX}{secrets.token_urlsafe(32)}"
+    CargoToken.objects.create(user=user, name=name, token_sha256=hashlib.sha256(token.encode()).hexdigest())
+    return token
+
+
+def user_for_cargo_token(token):
+    """
+    Get the user of a token cargo sent in the Authorization header.
+
+    Returns:
+        User, or None if the token is unknown or its user is inactive
+    """
+    token = (token or '').strip()
+    if token.lower().startswith('bearer '):
+        token = token[len('bearer '):].strip()
+    if not token:
+        return None
+    cargo_token = CargoToken.objects.select_related('user').filter(
+        token_sha256=hashlib.sha256(token.encode()).hexdigest()
+    ).first()
+    if cargo_token is None or not cargo_token.user.is_active:
+        return None
+    CargoToken.objects.filter(pk=cargo_token.pk).update(last_used_at=timezone.now())
+    return cargo_token.user
+
+
+def is_generated_crate(package, crate_name):
+    """Check if a crate name is the -sys crate generated for a package."""
+    return crate_name_for_package(package.name) == crate_name.lower()
+
+
+def crate_name_for_binary(binary):
+    """
+    Get the name of the crate attached to a binary.
+
+    Published crates record their own name; generated crates use the -sys name.
+    """
+    published_name = (binary.rust_crate_metadata or {}).get('name')
+    if published_name:
+        return published_name
+    return crate_name_for_package(binary.package_version.package.name)
+
+
+def sparse_index_path(crate_name):
+    """
+    Get the path of a crate's file inside the index (1/2/3/xx layout).
+
+    Examples:
+        "a"        -> "1/a"
+        "ab"       -> "2/ab"
+        "abc"      -> "3/a/abc"
+        "zlib-sys" -> "zl/ib/zlib-sys"
+    """
+    name = crate_name.lower()
+    if len(name) == 1:
+        return f"1/{name}"
+    if len(name) == 2:
+        return f"2/{name}"
+    if len(name) == 3:
+        return f"3/{name[0]}/{name}"
+    return f"{name[0:2]}/{name[2:4]}/{name}"
+
+
+def binaries_with_crates(package_version):
+    """Binaries of a package version that have a Rust crate attached."""
+    return package_version.binaries.exclude(
+        Q(rust_crate_file='') | Q(rust_crate_file__isnull=True)
+    )
+
+
+def select_crate_binary(package_version, crate_name=None):
+    """
+    Pick the binary whose crate is served for a package version.
+
+    Cargo only knows one archive per crate version, so the choice has to be
+    stable across requests: the oldest upload with a crate wins, which means
+    later uploads never change the checksum already published in the index.
+
+    Args:
+        package_version: PackageVersion to select from
+        crate_name: Only consider binaries carrying this crate (default: the -sys crate)
+
+    Returns:
+        BinaryPackage or None
+    """
+    if crate_name is None:
+        crate_name = crate_name_for_package(package_version.package.name)
+
+    for binary in binaries_with_crates(package_version).order_by('created_at', 'id'):
+        if crate_name_for_binary(binary).lower() == crate_name.lower():
+            return binary
+    return None
+
+
+def has_merged_crate(package_version, crate_name=None):
+    """
+    Check if the merged multi-target crate is served for a crate version.
+
+    Only the generated -sys crate is merged (see packages/crate_merge.py);
+    once a package version has one it replaces the per-binary crates.
+    """
+    if not package_version.rust_crate_file:
+        return False
+    generated_name = crate_name_for_package(package_version.package.name)
+    return crate_name is None or crate_name.lower() == generated_name
+
+
+def crate_checksum(binary):
+    """
+    Get the SHA256 of a binary's .crate archive.
+
+    Uses the checksum recorded at upload time, computing and storing it for
+    crates uploaded before checksums were recorded.
+    """
+    if binary.rust_crate_sha256:
+        return binary.rust_crate_sha256
+
+    sha256_hash = hashlib.sha256()
+    binary.rust_crate_file.open('rb')
+    try:
+        for chunk in binary.rust_crate_file.chunks():
+            sha256_hash.update(chunk)
+    finally:
+        binary.rust_crate_file.close()
+
+    binary.rust_crate_sha256 = sha256_hash.hexdigest()
+    binary.save(update_fields=['rust_crate_sha256'])
+    return binary.rust_crate_sha256
+
+
+def is_header_only_archive(crate_bytes):
+    """
+    Check if a generated .crate archive is header-only: its Cargo.toml has
+    no `links` key. Archives without a readable Cargo.toml count as linking.
+    """
+    try:
+        with tarfile.open(fileobj=io.BytesIO(crate_bytes), mode='r:gz') as tar:
+            for member in tar:
+                parts = member.name.strip('/').split('/')
+                if len(parts) == 2 and parts[1] == 'Cargo.toml' and member.isfile():
+                    manifest = tomllib.loads(tar.extractfile(member).read().decode('utf-8'))
+                    return 'links' not in manifest.get('package', {})
+    except (tarfile.TarError, OSError, UnicodeDecodeError, tomllib.TOMLDecodeError):
+        pass
+    return False
+
+
+def crate_is_header_only(binary):
+    """
+    Check if a binary's generated crate is header-only (has no `links` key).
+
+    The generator decides when it writes the crate, so the index follows the
+    crate instead of deciding again. Uses the decision recorded with the
+    crate, reading and storing it for crates recorded before it was.
+    """
+    if binary.rust_crate_header_only is None:
+        binary.rust_crate_file.open('rb')
+        try:
+            binary.rust_crate_header_only = is_header_only_archive(binary.rust_crate_file.read())
+        finally:
+            binary.rust_crate_file.close()
+        binary.save(update_fields=['rust_crate_header_only'])
+    return binary.rust_crate_header_only
+
+
+def graph_dependencies(dependency_graph):
+    """
+    Extract the dependencies of a package from its stored dependency graph.
+
+    Args:
+        dependency_graph: Dependency graph dict from conan graph info
+
+    The requirement is the version part of the require that pulled the node
+    in (e.g. "[>=1.2 <2]"), preferring the package's own requires over those
+    of its dependencies; it's None for graphs that don't record requires.
+
+    Returns:
+        List of dicts: [{'name': ..., 'version': ..., 'package_id': ..., 'requirement': ...,
+                         'header_only': ...}, ...]
+    """
+    dependencies = []
+    if not dependency_graph:
+        return dependencies
+
+    nodes = dependency_graph.get('graph', {}).get('nodes', {})
+    requirements = graph_requirements(nodes)
+    for node_id, node in nodes.items():
+        # Skip root node (the package itself)
+        if node_id == "0":
+            continue
+
+        ref = node.get('ref', '')
+        if '/' not in ref:
+            continue
+
+        dep_name, dep_version_with_hash = ref.split('/', 1)
+        # Remove recipe revision hash if present (e.g., "1.0.0#hash" -> "1.0.0")
+        dep_version = dep_version_with_hash.split('#')[0]
+        dependencies.append({
+            'name': dep_name,
+            'version': dep_version,
+            'package_id': node.get('package_id'),
+            'requirement': requirements.get(node_id),
+            'header_only': is_header_only_node(node),
+        })
+
+    return dependencies
+
+
+def is_header_only_node(node):
+    """
+    Check if a graph node is a header-only or interface package (nothing to link).
+
+    Uses the recipe's package_type, or a cpp_info that declares no libraries,
+    system libraries or frameworks. Same check as is_header_only_node() in
+    the CLI, which generates the crates of these packages without a `links`
+    key when their own graph node has either.
+    """
+    if node.get('package_type') == 'header-library':
+        return True
+    cpp_info = node.get('cpp_info')
+    if not isinstance(cpp_info, dict):
+        return False
+    return not any(
+        section.get(key)
+        for section in cpp_info.values() if isinstance(section, dict)
+        for key in ('libs', 'system_libs', 'frameworks')
+    )
+
+
+def graph_requirements(nodes):
+    """
+    Map the node ids of a conan graph to the version requirement they were required with.
+
+    conan graph info records every edge in the requiring node's "dependencies"
+    with the original require (e.g. "zlib/[>=1.2 <2]"); the root node's edges
+    are used when several nodes require the same dependency.
+
+    Returns:
+        Dict of node id -> requirement (e.g. {"1": "[>=1.2 <2]"})
+    """
+    requirements = {}
+    for node_id in sorted(nodes, key=lambda node_id: node_id != '0'):
+        for dep_id, edge in (nodes[node_id].get('dependencies') or {}).items():
+            require = edge.get('require') if isinstance(edge, dict) else None
+            if require and '/' in require and dep_id not in requirements:
+                requirements[dep_id] = require.split('/', 1)[1]
+    return requirements
+
+
+def published_index_deps(published_deps):
+    """
+    Convert dependencies from cargo publish metadata to index format.
+
+    The publish API uses "version_req" and "explicit_name_in_toml", the index
+    uses "req" and "package" (for renamed dependencies).
+    """
+    deps = []
+    for dep in published_deps:
+        index_dep = {
+            'name': dep.get('explicit_name_in_toml') or dep['name'],
+            'req': dep['version_req'],
+            'features': dep.get('features', []),
+            'optional': dep.get('optional', False),
+            'default_features': dep.get('default_features', True),
+            'target': dep.get('target'),
+            'kind': dep.get('kind', 'normal'),
+        }
+        if dep.get('registry'):
+            index_dep['registry'] = dep['registry']
+        if dep.get('explicit_name_in_toml'):
+            index_dep['package'] = dep['name']
+        deps.append(index_dep)
+    return deps
+
+
+def build_index_entry(package_version, binary):
+    """
+    Build the index entry (one JSON line) for a crate version.
+
+    Dependencies of generated crates come from the binary's stored dependency
+    graph, matching the dependencies written into the generated Cargo.toml.
+    Their requirements keep the recipe's version ranges (the package version's
+    Dependency records, then the requires in the graph), see
+    cargo_versions.cargo_requirement(). Header-only dependencies are build
+    dependencies too (build.rs reads their include paths), and header-only
+    crates have no `links` key (crate_is_header_only()). The checksum and
+    features (option variants) are the merged crate's once one exists.
+    Published crates use the metadata sent by cargo publish.
+    """
+    package_name = package_version.package.name
+
+    published = binary.rust_crate_metadata
+    if published:
+        return {
+            'name': published['name'],
+            'vers': published['vers'],
+            'deps': published_index_deps(published.get('deps', [])),
+            'cksum': crate_checksum(binary),
+            'features': published.get('features', {}),
+            'yanked': False,
+            'links': published.get('links'),
+        }
+
+    recipe_requirements = {
+        dependency.requires_package.name: dependency.version_requirement
+        for dependency in package_version.dependencies.filter(dependency_type='requires').select_related('requires_package')
+    }
+
+    deps = []
+    for dep in graph_dependencies(binary.dependency_graph):
+        requirement = recipe_requirements.get(dep['name']) or dep['requirement'] or dep['version']
+        for kind in ('normal', 'build') if dep['header_only'] else ('normal',):
+            deps.append({
+                'name': crate_name_for_package(dep['name']),
+                'req': cargo_requirement(requirement, dep['version']),
+                'features': [],
+                'optional': False,
+                'default_features': True,
+                'target': None,
+                'kind': kind,
+            })
+
+    features = {}
+    if has_merged_crate(package_version):
+        cksum = package_version.rust_crate_sha256
+        features = {feature: [] for feature in package_version.rust_crate_features or {}}
+    else:
+        cksum = crate_checksum(binary)
+
+    return {
+        'name': crate_name_for_package(package_name),
+        'vers': cargo_version_for(package_version),
+        'deps': deps,
+        'cksum': cksum,
+        'features': features,
+        'yanked': False,
+        'links': None if crate_is_header_only(binary) else package_name,
+    }
+
+
+def indexed_versions(package, crate_name):
+    """
+    Iterate over the package versions listed in a crate's index file.
+
+    Only versions with the crate are listed, and cargo ignores build metadata
+    when comparing versions, so of the Conan versions translating to the same
+    Cargo version only the oldest one with the crate is.
+
+    Yields:
+        Tuples of (PackageVersion, BinaryPackage carrying the crate), oldest version first
+    """
+    listed = set()
+    for package_version in package.versions.order_by('created_at', 'id'):
+        binary = select_crate_binary(package_version, crate_name)
+        if binary is None:
+            continue
+        precedence = version_requirement(cargo_version_for(package_version))
+        if precedence in listed:
+            continue
+        listed.add(precedence)
+        yield package_version, binary
+
+
+def build_index_entries(package, crate_name):
+    """
+    Build the index entries for every version of a package that has the crate.
+
+    Returns:
+        List of (PackageVersion, entry dict), oldest version first
+    """
+    return [
+        (package_version, build_index_entry(package_version, binary))
+        for package_version, binary in indexed_versions(package, crate_name)
+    ]
+
+
+def record_crates_indexed(package_versions):
+    """
+    Record that Cargo got the crates of package versions from the index or
+    the download endpoint.
+
+    Their checksums are then in Cargo.lock files, so the crate served for them
+    must never change (see merge_crates in packages/views/cargo_views.py).
+    """
+    PackageVersion.objects.filter(
+        pk__in=[package_version.pk for package_version in package_versions],
+        rust_crate_indexed_at__isnull=True
+    ).update(rust_crate_indexed_at=timezone.now())
+
+
+def find_version_for_crate(package, vers, crate_name):
+    """
+    Find the package version served as a crate version.
+
+    This is the version the index lists (indexed_versions()): when Conan
+    versions like "1.2" and "1.2.0" both translate to 1.2.0, one without
+    the crate is skipped.
+
+    Args:
+        package: Package the crate belongs to
+        vers: Cargo version (e.g. "1.2.13+1" for Conan version 1.2.13.1)
+        crate_name: Crate served
+
+    Returns:
+        PackageVersion or None
+    """
+    for package_version, _ in indexed_versions(package, crate_name):
+        if cargo_version_for(package_version) == vers:
+            return package_version
+    return None
+
+
+def registry_targets(package_version, crate_name=None):
+    """
+    Get the Rust targets the crate the registry serves for a package version covers.
+
+    That's the crate of the version the index lists (find_version_for_crate()):
+    the merged crate's targets, or the target of the one binary whose crate
+    is served (select_crate_binary()).
+
+    Args:
+        package_version: PackageVersion
+        crate_name: Crate served (default: the -sys crate)
+
+    Returns:
+        List of target triples (empty if the index doesn't list the version)
+    """
+    if crate_name is None:
+        crate_name = crate_name_for_package(package_version.package.name)
+    listed_version = find_version_for_crate(package_version.package, cargo_version_for(package_version), crate_name)
+    if listed_version is None:
+        return []
+    if has_merged_crate(listed_version, crate_name):
+        return list(listed_version.rust_crate_targets or [])
+    rust_target = rust_target_for_binary(select_crate_binary(listed_version, crate_name))
+    return [rust_target] if rust_target else []
+
+
+def find_version_for_publish(package, vers):
+    """
+    Find the package version a published crate version is attached to.
+
+    Args:
+        package: Package the crate belongs to
+        vers: Cargo version of the published crate
+
+    Returns:
+        PackageVersion or None
+    """
+    for package_version in package.versions.order_by('created_at', 'id'):
+        if cargo_version_for(package_version) == vers:
+            return package_version
+    return package.versions.filter(version=vers).first()
+
+
+def parse_publish_payload(body):
+    """
+    Parse the body of a cargo publish request.
+
+    Format (all lengths are 32-bit little-endian):
+        <json length> <json metadata> <crate length> <.crate archive>
+
+    Returns:
+        Tuple of (metadata dict, crate bytes)
+
+    Raises:
+        PublishError: If the payload is truncated or the metadata isn't JSON
+    """
+    offset = 0
+
+    def read_chunk(what):
+        nonlocal offset
+        if len(body) < offset + 4:
+            raise PublishError(f"Invalid publish payload: missing {what} length")
+        (length,) = struct.unpack_from('<I', body, offset)
+        offset += 4
+        if len(body) < offset + length:
+            raise PublishError(f"Invalid publish payload: {what} is truncated")
+        chunk = body[offset:offset + length]
+        offset += length
+        return chunk
+
+    metadata_bytes = read_chunk('metadata')
+    crate_bytes = read_chunk('crate')
+
+    try:
+        metadata = json.loads(metadata_bytes.decode('utf-8'))
+    except (UnicodeDecodeError, json.JSONDecodeError) as e:
+        raise PublishError(f"Invalid publish metadata: {e}")
+
+    if not isinstance(metadata, dict):
+        raise PublishError("Invalid publish metadata: expected a JSON object")
+
+    return metadata, crate_bytes
+
+
+def validate_publish_metadata(metadata, crate_bytes):
+    """
+    Validate cargo publish metadata and the .crate archive it describes.
+
+    Raises:
+        PublishError: With a message suitable for showing to the cargo user
+    """
+    name = metadata.get('name')
+    vers = metadata.get('vers')
+
+    if not isinstance(name, str) or not CRATE_NAME_RE.match(name):
+        raise PublishError(f"Invalid crate name: {name!r}")
+    if not isinstance(vers, str) or not SEMVER_RE.match(vers):
+        raise PublishError(f"Invalid crate version: {vers!r} (must be semver, e.g. 1.0.0)")
+
+    deps = metadata.get('deps', [])
+    if not isinstance(deps, list):
+        raise PublishError("Invalid deps: expected a list")
+    for dep in deps:
+        if not isinstance(dep, dict) or not dep.get('name') or not dep.get('version_req'):
+            raise PublishError(f"Invalid dependency: {dep!r}")
+        if dep.get('kind', 'normal') not in DEPENDENCY_KINDS:
+            raise PublishError(f"Invalid dependency kind for {dep['name']}: {dep.get('kind')!r}")
+
+    features = metadata.get('features', {})
+    if not isinstance(features, dict):
+        raise PublishError("Invalid features: expected a table")
+
+    validate_crate_archive(crate_bytes, name, vers)
+    check_crate_manifest(read_crate_manifest(crate_bytes, f"{name}-{vers}"), metadata)
+
+
+def read_crate_manifest(crate_bytes, crate_root):
+    """
+    Read the Cargo.toml of a .crate archive.
+
+    Raises:
+        PublishError: If it can't be read or isn't valid TOML
+    """
+    try:
+        with tarfile.open(fileobj=io.BytesIO(crate_bytes), mode='r:gz') as tar:
+            return tomllib.loads(tar.extractfile(f"{crate_root}/Cargo.toml").read().decode('utf-8'))
+    except (tarfile.TarError, OSError, AttributeError, KeyError, UnicodeDecodeError, tomllib.TOMLDecodeError) as e:
+        raise PublishError(f"Invalid Cargo.toml in the .crate archive: {e}")
+
+
+def normalize_requirement(requirement):
+    """
+    Write a version requirement the way cargo sends it in publish metadata:
+    bare versions get the default caret ("1.0" -> "^1.0").
+    """
+    parts = [part.replace(' ', '') for part in requirement.split(',')]
+    return ', '.join(f"^{part}" if part[:1].isdigit() else part for part in parts)
+
+
+def manifest_dependencies(manifest):
+    """
+    List the registry dependencies of a parsed Cargo.toml.
+
+    Returns:
+        Set of (crate name, kind, target, requirement) tuples
+    """
+    tables = [(None, manifest)]
+    tables.extend((target, target_tables) for target, target_tables in manifest.get('target', {}).items())
+
+    deps = set()
+    for target, target_tables in tables:
+        for table, kind in zip(('dependencies', 'dev-dependencies', 'build-dependencies'), DEPENDENCY_KINDS):
+            for key, spec in target_tables.get(table, {}).items():
+                if isinstance(spec, str):
+                    spec = {'version': spec}
+                deps.add((spec.get('package', key), kind, target, normalize_requirement(spec.get('version', '*'))))
+    return deps
+
+
+def check_crate_manifest(manifest, metadata):
+    """
+    Check that the Cargo.toml of a published crate says what the publish
+    metadata (which the index serves) says: name, version, links and dependencies.
+
+    Raises:
+        PublishError: On the first difference
+    """
+    package = manifest.get('package', {})
+    for key, manifest_key in (('name', 'name'), ('vers', 'version'), ('links', 'links')):
+        if package.get(manifest_key) != metadata.get(key):
+            raise PublishError(
+                f"Cargo.toml has {manifest_key} {package.get(manifest_key)!r}, "
+                f"the publish metadata {metadata.get(key)!r}"
+            )
+
+    published = {
+        (dep['name'], dep.get('kind', 'normal'), dep.get('target'), normalize_requirement(dep['version_req']))
+        for dep in metadata.get('deps', [])
+    }
+    declared = manifest_dependencies(manifest)
+    if published != declared:
+        def describe(deps):
+            return ', '.join(
+                f"{name} {requirement} ({kind}{f', {target}' if target else ''})"
+                for name, kind, target, requirement in sorted(deps, key=str)
+            ) or 'none'
+        raise PublishError(
+            f"Cargo.toml dependencies don't match the publish metadata: only in Cargo.toml: "
+            f"{describe(declared - published)}; only in the metadata: {describe(published - declared)}"
+        )
+
+
+def validate_crate_archive(crate_bytes, name, vers):
+    """
+    Check that a .crate archive has the layout cargo extracts from a registry:
+    {name}-{vers}/Cargo.toml and nothing outside that directory.
+
+    Raises:
+        PublishError: If the archive can't be read or has another layout
+    """
+    crate_root = f"{name}-{vers}"
+    try:
+        with tarfile.open(fileobj=io.BytesIO(crate_bytes), mode='r:gz') as tar:
+            member_names = tar.getnames()
+    except (tarfile.TarError, OSError) as e:
+        raise PublishError(f"Invalid .crate archive: {e}")
+
+    for member_name in member_names:
+        if member_name != crate_root and not member_name.startswith(f"{crate_root}/"):
+            raise PublishError(f"Invalid .crate archive: {member_name} is outside {crate_root}/")
+    if f"{crate_root}/Cargo.toml" not in member_names:
+        raise PublishError(f"Invalid .crate archive: missing {crate_root}/Cargo.toml")
+
+
+def build_crate_archive(crate_root, files):
+    """
+    Build a reproducible .crate archive like cargo package: files sorted under
+    crate_root/, fixed mtime, mode and owner, no timestamp in the gzip header.
+
+    Args:
+        crate_root: Top-level directory ({name}-{version})
+        files: Dict of relative path -> bytes
+
+    Returns:
+        Archive bytes
+    """
+    tar_buffer = io.BytesIO()
+    with tarfile.open(fileobj=tar_buffer, mode='w', format=tarfile.GNU_FORMAT) as tar:
+        for path in sorted(files):
+            data = files[path]
+            info = tarfile.TarInfo(f"{crate_root}/{path}")
+            info.size = len(data)
+            info.mtime = CRATE_MTIME
+            info.mode = 0o644
+            tar.addfile(info, io.BytesIO(data))
+
+    buffer = io.BytesIO()
+    with gzip.GzipFile(filename=f"{crate_root}.crate", mode='wb', fileobj=buffer, compresslevel=9, mtime=0) as gz:
+        gz.write(tar_buffer.getvalue())
+    return buffer.getvalue()
+
+
+def published_crate_metadata(metadata):
+    """Subset of cargo publish metadata stored on the BinaryPackage for the index."""
+    return {
+        'name': metadata['name'],
+        'vers': metadata['vers'],
+        'deps': metadata.get('deps', []),
+        'features': metadata.get('features', {}),
+        'links': metadata.get('links'),
+    }
@@ -0,0 +1,214 @@
+"""
+Mapping between Conan versions and Cargo (semver) versions
+
+Conan accepts almost any version string (1.2.13.1, 3.0, cci.20230101,
+1.1.1w), Cargo only semver (https://semver.org). Generated -sys crates use
+the translated version: the first three numeric components become
+major.minor.patch (padded with zeros), anything else is kept as build
+metadata so the original version stays visible. Every PackageVersion
+records its translation (cargo_version) and the Rust-facing APIs (index,
+downloads, crate file names) use it.
+
+Cargo ignores build metadata when comparing versions, so Conan versions
+that only differ in the extra components (1.2.13.1 and 1.2.13.2) translate
+to the same Cargo version; the index only serves the oldest of them.
+
+The CLI has its own copy (cargo_version() and cargo_requirement() in
+conancrates/conancrates.py), which writes the versions into generated
+crates; packages/tests/test_cargo_versions.py checks that both agree.
+"""
+import re
+
+
+# Semantic version (https://semver.org) as required by Cargo
+SEMVER_RE = re.compile(
+    r'^(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)'
+    r'(?:-((?:0|[1-9]\d*|\d*[A-Za-z-][0-9A-Za-z-]*)(?:\.(?:0|[1-9]\d*|\d*[A-Za-z-][0-9A-Za-z-]*))*))?'
+    r'(?:\+([0-9A-Za-z-]+(?:\.[0-9A-Za-z-]+)*))?$'
+)
+
+
+def semver_identifiers(text):
+    """Split dotted text into identifiers, replacing characters semver doesn't allow."""
+    identifiers = []
+    for part in text.split('.'):
+        part = re.sub(r'[^0-9A-Za-z-]', '-', part)
+        if part:
+            identifiers.append(part)
+    return identifiers
+
+
+def cargo_version(version):
+    """
+    Translate a Conan version into a Cargo version.
+
+    Examples:
+        "1.2.3"          -> "1.2.3"
+        "3.0"            -> "3.0.0"
+        "1.2.13.1"       -> "1.2.13+1"
+        "1.1.1w"         -> "1.1.1+w"
+        "cci.20230101"   -> "20230101.0.0+cci"
+        "2.0-rc1"        -> "2.0.0-rc1"
+        "1.81.0+build"   -> "1.81.0+build"
+    """
+    version = version.strip()
+    if SEMVER_RE.match(version):
+        return version
+
+    version, _, build = version.partition('+')
+    version, _, prerelease = version.partition('-')
+    if re.match(r'^[vV]\d', version):
+        version = version[1:]
+
+    numbers = []
+    extra = []
+    for part in version.split('.'):
+        match = re.match(r'^(\d+)(.*)$', part)
+        if match and len(numbers) < 3 and not (numbers and extra):
+            numbers.append(str(int(match.group(1))))
+            extra.extend(semver_identifiers(match.group(2)))
+        else:
+            extra.extend(semver_identifiers(part))
+    numbers += ['0'] * (3 - len(numbers))
+
+    result = '.'.join(numbers)
+    prerelease = [
+        str(int(identifier)) if identifier.isdigit() else identifier
+        for identifier in semver_identifiers(prerelease)
+    ]
+    if prerelease:
+        result += '-' + '.'.join(prerelease)
+    metadata = extra + semver_identifiers(build)
+    if metadata:
+        result += '+' + '.'.join(metadata)
+    return result
+
+
+def version_requirement(version):
+    """
+    Get the Cargo requirement matching a Cargo version.
+
+    Build metadata is dropped: Cargo ignores it in requirements and warns
+    about it.
+    """
+    return version.split('+', 1)[0]
+
+
+def cargo_version_for(package_version):
+    """
+    Get the Cargo version of a PackageVersion.
+
+    Uses the translation recorded at upload, falling back to translating the
+    version for package versions without one.
+    """
+    if package_version.cargo_version:
+        return package_version.cargo_version
+    return cargo_version(package_version.version)
+
+
+# Conan range operators, longest first so ">=" isn't read as ">"
+RANGE_OPERATORS = ('>=', '<=', '>', '<', '=', '~', '^')
+
+
+def version_key(version):
+    """Numeric (major, minor, patch) of a Cargo version, for comparisons."""
+    return tuple(int(number) for number in re.match(r'^(\d+)\.(\d+)\.(\d+)', version).groups())
+
+
+def partial_version(version):
+    """
+    Keep the components of a version used with ~ or ^.
+
+    Cargo reads "~1" as >=1.0.0, <2.0.0 but "~1.0.0" as >=1.0.0, <1.1.0, the
+    same way Conan does, so plain numeric versions aren't padded.
+    """
+    if re.match(r'^\d+(\.\d+){0,2}$', version):
+        return '.'.join(str(int(number)) for number in version.split('.'))
+    return version_requirement(cargo_version(version))
+
+
+def range_comparators(expression):
+    """
+    Translate one alternative of a Conan version range into Cargo comparators.
+
+    Examples:
+        ">=1.2 <2"  -> [">=1.2.0", "<2.0.0"]
+        "~1.2"      -> ["~1.2"]
+        "1.2.13.1"  -> ["=1.2.13"]
+    """
+    comparators = []
+    for condition in expression.split():
+        if condition == '*':
+            continue
+        operator = next((op for op in RANGE_OPERATORS if condition.startswith(op)), '=')
+        version = condition[len(operator):] if condition.startswith(operator) else condition
+        if operator in ('~', '^'):
+            comparators.append(f"{operator}{partial_version(version)}")
+        else:
+            comparators.append(f"{operator}{version_requirement(cargo_version(version))}")
+    return comparators or ['*']
+
+
+def comparator_matches(comparator, version):
+    """Check if a Cargo version (major, minor, patch) satisfies a comparator."""
+    if comparator == '*':
+        return True
+    operator = next(op for op in RANGE_OPERATORS if comparator.startswith(op))
+    parts = [int(number) for number in comparator[len(operator):].split('-')[0].split('.')]
+    bound = tuple(parts + [0] * (3 - len(parts)))
+
+    if operator == '~':
+        upper = (bound[0] + 1, 0, 0) if len(parts) == 1 else (bound[0], bound[1] + 1, 0)
+        return bound <= version < upper
+    if operator == '^':
+        significant = next((index for index, number in enumerate(parts) if number), len(parts) - 1)
+        upper = list(bound[:significant]) + [bound[significant] + 1] + [0] * (2 - significant)
+        return bound <= version < tuple(upper)
+    return {
+        '>=': version >= bound,
+        '<=': version <= bound,
+        '>': version > bound,
+        '<': version < bound,
+        '=': version == bound,
+    }[operator]
+
+
+def cargo_requirement(requirement, resolved_version=None):
+    """
+    Translate the version part of a Conan requirement into a Cargo requirement.
+
+    Plain versions become caret requirements (Cargo's default), ranges keep
+    their bounds. Cargo has no "||": the alternative containing the resolved
+    version is used, and a range that can't match the resolved version falls
+    back to a caret requirement on it.
+
+    Examples:
+        "1.2.13"                     -> "^1.2.13"
+        "[>=1.2 <2]"                 -> ">=1.2.0, <2.0.0"
+        "[~3.0, include_prerelease]" -> "~3.0"
+        "[>=1 <2 || >=3 <4]"         -> ">=3.0.0, <4.0.0" (resolved 3.1)
+
+    Args:
+        requirement: Version requirement (e.g. "[>=1.2 <2]"), user/channel and
+                     recipe revision are ignored
+        resolved_version: Conan version the graph resolved the requirement to
+    """
+    requirement = requirement.split('#', 1)[0].split('@', 1)[0].strip()
+    resolved = version_requirement(cargo_version(resolved_version)) if resolved_version else None
+
+    if not (requirement.startswith('[') and requirement.endswith(']')):
+        if requirement:
+            return f"^{version_requirement(cargo_version(requirement))}"
+        return f"^{resolved}" if resolved else '*'
+
+    expression = requirement[1:-1].split(',', 1)[0]
+    alternatives = [range_comparators(alternative) for alternative in expression.split('||')]
+    if resolved:
+        matching = [
+            comparators for comparators in alternatives
+            if all(comparator_matches(comparator, version_key(resolved)) for comparator in comparators)
+        ]
+        if not matching:
+            return f"^{resolved}"
+        alternatives = matching
+    return ', '.join(alternatives[-1])
@@ -0,0 +1,208 @@
+"""
+Workspace bundles of Rust crates
+
+A bundle is a zip of a -sys crate and the crates of its dependencies, laid out
+as a Cargo workspace that builds without a registry:
+
+    Cargo.toml        [workspace] with every crate as a member
+    Cargo.lock        the members and their dependencies
+    README.md
+    crates/<name>/    one directory per crate (the .crate contents)
+
+Dependencies between bundled crates get `path = "../<name>"`. Manifests are
+parsed and written back with the CLI's TOML writer rather than edited
+textually, so every dependency form (inline, [dependencies.<name>] tables,
+build-dependencies, target-specific and renamed dependencies) is handled.
+"""
+import io
+import re
+import tarfile
+import tomllib
+import zipfile
+
+
+CRATES_DIR = 'crates'
+
+# Cargo.lock format understood by every cargo since 1.53
+LOCK_VERSION = 3
+
+
+def read_crate_files(crate_bytes):
+    """
+    Read the files of a .crate archive.
+
+    Archives have a single top-level directory ({name}-{version}/, or the
+    crate name for crates generated by older CLIs), which is stripped.
+
+    Returns:
+        Dict of {relative path: bytes}
+    """
+    files = {}
+    with tarfile.open(fileobj=io.BytesIO(crate_bytes), mode='r:gz') as tar:
+        for member in tar.getmembers():
+            parts = member.name.strip('/').split('/', 1)
+            if len(parts) < 2 or not member.isfile():
+                continue
+            relative = '/'.join(part for part in parts[1].split('/') if part not in ('', '.'))
+            if not relative or '..' in relative.split('/'):
+                continue
+            files[relative] = tar.extractfile(member).read()
+    return files
+
+
+def dependency_tables(manifest):
+    """Yield every dependency table of a manifest, including target-specific ones."""
+    # Imported here: the CLI module is only needed for its manifest helpers
+    from conancrates.conancrates import DEPENDENCY_TABLES
+
+    for key in DEPENDENCY_TABLES:
+        if key in manifest:
+            yield manifest[key]
+    for target_tables in manifest.get('target', {}).values():
+        for key in DEPENDENCY_TABLES:
+            if key in target_tables:
+                yield target_tables[key]
+
+
+def add_path_dependencies(manifest, crate_names):
+    """
+    Point the dependencies of a parsed manifest at the bundled crates.
+
+    Dependencies on a crate in crate_names get `path = "../<crate>"` (keeping
+    their version requirement, so the manifest stays publishable).
+
+    Returns:
+        Sorted names of the bundled crates the manifest depends on
+    """
+    used = set()
+    for table in dependency_tables(manifest):
+        for key, spec in table.items():
+            if isinstance(spec, str):
+                spec = {'version': spec}
+            crate_name = spec.get('package', key)
+            if crate_name not in crate_names or 'git' in spec:
+                continue
+            spec = {name: value for name, value in spec.items() if name not in ('path', 'registry')}
+            table[key] = {'path': f"../{crate_name}", **spec}
+            used.add(crate_name)
+    return sorted(used)
+
+
+def write_manifest(manifest, header=''):
+    """Format a parsed manifest as TOML, after the given comment header."""
+    # Imported here: the CLI module is only needed for its TOML writer
+    from conancrates.conancrates import is_table_array, toml_key, toml_table, toml_table_array, toml_value
+
+    lines = []
+    for key, value in manifest.items():
+        if isinstance(value, dict):
+            toml_table([key], value, lines)
+        elif is_table_array(value):
+            toml_table_array([key], value, lines)
+        else:
+            lines.insert(0, f"{toml_key(key)} = {toml_value(value)}")
+    return header + '\n'.join(lines).rstrip('\n') + '\n'
+
+
+def workspace_manifest(crate_names):
+    """Top-level Cargo.toml with every bundled crate as a workspace member."""
+    return write_manifest({
+        'workspace': {
+            'members': [f"{CRATES_DIR}/{name}" for name in crate_names],
+            'resolver': '2',
+        }
+    })
+
+
+def lock_file(packages):
+    """
+    Cargo.lock for the bundled crates.
+
+    Path packages have no checksum entry in Cargo.lock. Dependencies that
+    aren't bundled are left out: cargo adds them when it resolves (the lock is
+    then updated, so --locked requires a complete bundle).
+
+    Args:
+        packages: List of dicts with name, version and dependencies
+    """
+    # Imported here: the CLI module is only needed for its TOML writer
+    from conancrates.conancrates import toml_value
+
+    lines = [
+        "# This file is automatically @generated by ConanCrates.",
+        "# It is not intended for manual editing.",
+        f"version = {LOCK_VERSION}",
+    ]
+    packages = sorted(packages, key=lambda package: package['name'])
+    for package in packages:
+        lines.extend(['', '[[package]]', f"name = {toml_value(package['name'])}", f"version = {toml_value(package['version'])}"])
+        if package['dependencies']:
+            lines.append('dependencies = [')
+            lines.extend(f" {toml_value(dependency)}," for dependency in package['dependencies'])
+            lines.append(']')
+    return '\n'.join(lines) + '\n'
+
+
+def workspace_files(crates, dev_dependencies=True):
+    """
+    Lay out crates as a Cargo workspace.
+
+    Args:
+        crates: List of (crate name, .crate bytes)
+        dev_dependencies: Keep the crates' dev-dependencies (without them,
+                          nothing outside the workspace has to be resolved)
+
+    Returns:
+        Dict of {path: bytes}: Cargo.toml, Cargo.lock and crates/<name>/...
+    """
+    crate_names = {name for name, _ in crates}
+    files = {}
+    members = []
+    packages = []
+    for crate_name, crate_bytes in crates:
+        crate_files = read_crate_files(crate_bytes)
+        crate_dir = f"{CRATES_DIR}/{crate_name}"
+        if 'Cargo.toml' in crate_files:
+            content = crate_files['Cargo.toml'].decode('utf-8')
+            manifest = tomllib.loads(content)
+            if not dev_dependencies:
+                manifest.pop('dev-dependencies', None)
+                for target_tables in manifest.get('target', {}).values():
+                    target_tables.pop('dev-dependencies', None)
+            dependencies = add_path_dependencies(manifest, crate_names - {crate_name})
+            # Keep the "generated by cargo" comments of normalized manifests
+            header = re.match(r'(?:#[^\n]*\n|\n)*', content).group(0)
+            crate_files['Cargo.toml'] = write_manifest(manifest, header).encode('utf-8')
+            members.append(crate_name)
+            packages.append({
+                'name': manifest.get('package', {}).get('name', crate_name),
+                'version': manifest.get('package', {}).get('version', '0.0.0'),
+                'dependencies': dependencies,
+            })
+        files.update({f"{crate_dir}/{path}": data for path, data in crate_files.items()})
+
+    files['Cargo.toml'] = workspace_manifest(members).encode('utf-8')
+    files['Cargo.lock'] = lock_file(packages).encode('utf-8')
+    return files
+
+
+def build_bundle(crates, readme=None):
+    """
+    Build a workspace bundle.
+
+    Args:
+        crates: List of (crate name, .crate bytes), the requested crate first
+        readme: Content of the top-level README.md
+
+    Returns:
+        Zip archive bytes
+    """
+    files = workspace_files(crates)
+    if readme:
+        files['README.md'] = readme.encode('utf-8')
+
+    buffer = io.BytesIO()
+    with zipfile.ZipFile(buffer, 'w', zipfile.ZIP_DEFLATED) as bundle:
+        for path in sorted(files):
+            bundle.writestr(path, files[path])
+    return buffer.getvalue()
@@ -0,0 +1,365 @@
+"""
+Rustdoc hosting for the crates of the registry
+
+After a crate is generated, uploaded, published or merged, `cargo doc` runs on
+the crate version the registry serves, and the HTML is stored under
+rust_docs/<crate>/<version>/ in the default storage and served at
+/docs/<crate>/<version>/ - docs.rs for this registry.
+
+The build runs offline in a temporary Cargo workspace: the crate and the
+crates it depends on, resolved in this registry the way Cargo would from the
+index, are unpacked as path dependencies (packages/crate_bundle.py), so build
+scripts find their bundled native libraries and nothing is downloaded.
+Build scripts of published crates are untrusted code, and `--offline` only
+keeps cargo itself off the network: cargo runs inside a sandbox
+(RUST_DOCS_SANDBOX, bubblewrap by default) without network and with only the
+system's and the toolchain's directories visible, with an empty environment,
+its own CARGO_HOME and a timeout. Without a sandbox, docs aren't built.
+
+Builds run in a background thread (RUST_DOCS_GENERATION setting, like crate
+generation) and from the build_rust_docs management command.
+"""
+import os
+import shutil
+import subprocess
+import tempfile
+import threading
+import tomllib
+from pathlib import Path
+
+from django.conf import settings
+from django.core.files.base import ContentFile
+from django.core.files.storage import default_storage
+from django.db import connection, transaction
+
+from packages.cargo_registry import (
+    binaries_with_crates, build_index_entry, crate_name_for_binary, find_package_for_crate,
+    has_merged_crate, select_crate_binary,
+)
+from packages.cargo_versions import SEMVER_RE, RANGE_OPERATORS, comparator_matches, cargo_version_for, version_key
+from packages.crate_bundle import CRATES_DIR, workspace_files
+from packages.models import PackageVersion
+
+
+# Storage directory of the generated HTML: rust_docs/<crate>/<version>/...
+DOCS_PREFIX = 'rust_docs'
+
+# One cargo doc at a time: builds are CPU and disk heavy
+build_lock = threading.Lock()
+
+# Directories the bubblewrap sandbox shows read-only: programs and libraries,
+# not /etc, /home or the server's files (docs could leak what build scripts read)
+SANDBOX_SYSTEM_DIRS = ('/usr', '/bin', '/lib', '/lib32', '/lib64', '/etc/alternatives')
+
+
+class DocsError(Exception):
+    """Exception raised when the docs of a crate can't be built"""
+    pass
+
+
+def served_crate(package_version):
+    """
+    Get the crate the registry serves for a package version.
+
+    Returns:
+        Tuple of (crate name, binary selected for the index, owner of the
+        .crate file: the package version once merged, else the binary),
+        or None if the version has no crate
+    """
+    binary = binaries_with_crates(package_version).order_by('created_at', 'id').first()
+    if binary is None:
+        return None
+    crate_name = crate_name_for_binary(binary)
+    binary = select_crate_binary(package_version, crate_name)
+    owner = package_version if has_merged_crate(package_version, crate_name) else binary
+    return crate_name, binary, owner
+
+
+def docs_storage_dir(crate_name, crate_version):
+    """Storage directory of a crate version's docs."""
+    return f"{DOCS_PREFIX}/{crate_name.lower()}/{crate_version}"
+
+
+def read_crate(owner):
+    """Read the .crate file of a binary or merged package version."""
+    owner.rust_crate_file.open('rb')
+    try:
+        return owner.rust_crate_file.read()
+    finally:
+        owner.rust_crate_file.close()
+
+
+def requirement_matches(requirement, version):
+    """Check if a Cargo version satisfies a requirement (e.g. ">=1.2.0, <2.0.0")."""
+    if not SEMVER_RE.match(version):
+        return False
+    for comparator in requirement.split(','):
+        comparator = comparator.strip()
+        # A bare version is a caret requirement
+        if comparator != '*' and not comparator.startswith(RANGE_OPERATORS):
+            comparator = f"^{comparator}"
+        if not comparator_matches(comparator, version_key(version)):
+            return False
+    return True
+
+
+def resolve_crate(crate_name, requirement):
+    """
+    Find the newest version of a crate in the registry matching a requirement.
+
+    Returns:
+        Tuple of (PackageVersion, binary selected for the index), or None
+    """
+    package = find_package_for_crate(crate_name)
+    if package is None:
+        return None
+    candidates = []
+    for package_version in package.versions.all():
+        crate_version = cargo_version_for(package_version)
+        if not requirement_matches(requirement, crate_version):
+            continue
+        binary = select_crate_binary(package_version, crate_name)
+        if binary is not None:
+            candidates.append((version_key(crate_version), package_version.created_at, package_version, binary))
+    if not candidates:
+        return None
+    _, _, package_version, binary = max(candidates, key=lambda candidate: candidate[:2])
+    return package_version, binary
+
+
+def dependency_crates(package_version, binary, resolved):
+    """
+    Resolve the normal and build dependencies of a crate, recursively.
+
+    Args:
+        resolved: Dict of {crate name: .crate bytes} filled with the dependencies
+
+    Raises:
+        DocsError: If a dependency isn't in the registry
+    """
+    entry = build_index_entry(package_version, binary)
+    for dep in entry['deps']:
+        if dep['kind'] == 'dev':
+            continue
+        crate_name = dep.get('package') or dep['name']
+        if crate_name in resolved:
+            continue
+        match = resolve_crate(crate_name, dep['req'])
+        if match is None:
+            raise DocsError(f"{entry['name']} depends on {crate_name} {dep['req']}, which isn't in this registry")
+        dep_version, dep_binary = match
+        owner = dep_version if has_merged_crate(dep_version, crate_name) else dep_binary
+        resolved[crate_name] = read_crate(owner)
+        dependency_crates(dep_version, dep_binary, resolved)
+
+
+def toolchain_dirs():
+    """Directories of the Rust toolchain outside the system's: cargo's (rustup proxies) and RUSTUP_HOME."""
+    directories = set()
+    cargo = shutil.which('cargo')
+    if cargo:
+        directories.add(str(Path(cargo).parent))
+        directories.add(str(Path(cargo).resolve().parent))
+    directories.add(os.environ.get('RUSTUP_HOME', str(Path.home() / '.rustup')))
+    return sorted(directories)
+
+
+def bubblewrap_command(build_dir, workspace):
+    """
+    bwrap command prefix: no network or other namespaces shared with the
+    server, the system and toolchain directories read-only, and only the
+    build directory writable.
+    """
+    command = ['bwrap', '--unshare-all', '--die-with-parent', '--new-session']
+    for directory in SANDBOX_SYSTEM_DIRS + tuple(toolchain_dirs()):
+        command += ['--ro-bind-try', directory, directory]
+    return command + [
+        '--proc', '/proc', '--dev', '/dev', '--tmpfs', '/tmp',
+        '--bind', str(build_dir), str(build_dir), '--chdir', str(workspace),
+    ]
+
+
+def sandbox_command(build_dir, workspace):
+    """
+    Command prefix confining cargo doc (RUST_DOCS_SANDBOX setting): "bubblewrap",
+    or a command whose arguments may use {build_dir}.
+
+    Raises:
+        DocsError: If no sandbox is configured
+    """
+    sandbox = getattr(settings, 'RUST_DOCS_SANDBOX', 'bubblewrap')
+    if sandbox == 'bubblewrap':
+        return bubblewrap_command(build_dir, workspace)
+    if not sandbox:
+        raise DocsError("No sandbox configured (RUST_DOCS_SANDBOX): crate build scripts aren't run unconfined")
+    return [str(argument).format(build_dir=build_dir) for argument in sandbox]
+
+
+def cargo_doc_command(crate_name, build_dir):
+    """cargo doc command line for the workspace/ of build_dir, inside the sandbox."""
+    build_dir = Path(build_dir)
+    return sandbox_command(build_dir, build_dir / 'workspace') + [
+        'cargo', 'doc', '--offline', '--no-deps',
+        '--package', crate_name, '--target-dir', str(build_dir / 'target'),
+    ]
+
+
+def cargo_doc_environment(home):
+    """
+    Environment of cargo doc: nothing from the server's environment but the
+    toolchain location, so build scripts don't see settings or credentials.
+    """
+    environment = {
+        'PATH': os.environ.get('PATH', '/usr/bin:/bin'),
+        'HOME': str(home),
+        'CARGO_HOME': str(Path(home) / 'cargo'),
+        'RUSTUP_HOME': os.environ.get('RUSTUP_HOME', str(Path.home() / '.rustup')),
+        'CARGO_NET_OFFLINE': 'true',
+        # Set by docs.rs too: lets crates skip native work that docs don't need
+        'DOCS_RS': '1',
+    }
+    if os.environ.get('RUSTUP_TOOLCHAIN'):
+        environment['RUSTUP_TOOLCHAIN'] = os.environ['RUSTUP_TOOLCHAIN']
+    return environment
+
+
+def store_docs(doc_dir, storage_dir):
+    """Replace the stored docs of a crate version with the files of doc_dir."""
+    delete_docs(storage_dir)
+    for path in sorted(Path(doc_dir).rglob('*')):
+        if path.is_file():
+            default_storage.save(f"{storage_dir}/{path.relative_to(doc_dir).as_posix()}", ContentFile(path.read_bytes()))
+
+
+def delete_docs(storage_dir):
+    """Delete a stored docs directory."""
+    try:
+        directories, files = default_storage.listdir(storage_dir)
+    except FileNotFoundError:
+        return
+    for name in files:
+        default_storage.delete(f"{storage_dir}/{name}")
+    for name in directories:
+        delete_docs(f"{storage_dir}/{name}")
+
+
+def build_docs(package_version):
+    """
+    Build and store the rustdoc of the crate served for a package version.
+
+    Records the entry page in rust_docs_path, or the failure in rust_docs_error.
+
+    Raises:
+        DocsError: If the version has no crate, a dependency is missing, no sandbox
+                   is configured, or cargo doc fails
+    """
+    try:
+        generate_docs(package_version)
+    except DocsError as e:
+        package_version.rust_docs_error = str(e)
+        package_version.save(update_fields=['rust_docs_error'])
+        raise
+
+
+def generate_docs(package_version):
+    """Run cargo doc on a package version's crate and store the HTML (see build_docs)."""
+    served = served_crate(package_version)
+    if served is None:
+        raise DocsError(f"{package_version} has no Rust crate")
+    crate_name, binary, owner = served
+    crate_version = cargo_version_for(package_version)
+
+    dependencies = {}
+    dependency_crates(package_version, binary, dependencies)
+    crates = [(crate_name, read_crate(owner))] + sorted(dependencies.items())
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        workspace = Path(tmpdir) / 'workspace'
+        try:
+            files = workspace_files(crates, dev_dependencies=False)
+        except (OSError, ValueError, tomllib.TOMLDecodeError) as e:
+            raise DocsError(f"Could not unpack the crates: {e}")
+        for path, data in files.items():
+            (workspace / path).parent.mkdir(parents=True, exist_ok=True)
+            (workspace / path).write_bytes(data)
+
+        manifest = tomllib.loads(files[f"{CRATES_DIR}/{crate_name}/Cargo.toml"].decode('utf-8'))
+        package_name = manifest.get('package', {}).get('name', crate_name)
+        lib_name = manifest.get('lib', {}).get('name') or package_name.replace('-', '_')
+
+        command = cargo_doc_command(package_name, tmpdir)
+        home = Path(tmpdir) / 'home'
+        home.mkdir()
+        timeout = getattr(settings, 'RUST_DOCS_TIMEOUT', 600)
+        with build_lock:
+            try:
+                result = subprocess.run(
+                    command, cwd=workspace, env=cargo_doc_environment(home),
+                    stdin=subprocess.DEVNULL, capture_output=True, text=True, timeout=timeout,
+                )
+            except FileNotFoundError as e:
+                raise DocsError(f"Could not run {command[0]}: {e}")
+            except subprocess.TimeoutExpired:
+                raise DocsError(f"cargo doc timed out after {timeout}s")
+        if result.returncode != 0:
+            errors = [line for line in result.stderr.splitlines() if line.startswith('error')]
+            raise DocsError(errors[0] if errors else f"cargo doc failed:\n{result.stderr[-2000:]}")
+
+        doc_dir = Path(tmpdir) / 'target' / 'doc'
+        if not (doc_dir / lib_name / 'index.html').exists():
+            raise DocsError(f"cargo doc produced no {lib_name}/index.html (does {crate_name} have a library?)")
+        store_docs(doc_dir, docs_storage_dir(crate_name, crate_version))
+
+    package_version.rust_docs_path = f"{lib_name}/index.html"
+    package_version.rust_docs_error = ''
+    package_version.save(update_fields=['rust_docs_path', 'rust_docs_error'])
+
+
+def build_missing_docs(package_version_id, rebuild=False):
+    """
+    Build the docs of a package version unless it has them by now.
+
+    Errors are logged, not raised: this runs after the upload has been answered.
+    """
+    package_version = PackageVersion.objects.select_related('package').filter(pk=package_version_id).first()
+    if package_version is None or (package_version.rust_docs_path and not rebuild):
+        return
+    try:
+        build_docs(package_version)
+        print(f"✓ Built Rust docs for {package_version}")
+    except DocsError as e:
+        print(f"✗ Error building Rust docs for {package_version}: {e}")
+
+
+def run_in_background(package_version_id, rebuild):
+    """Thread target: build the docs, then release the thread's database connection."""
+    try:
+        build_missing_docs(package_version_id, rebuild)
+    finally:
+        connection.close()
+
+
+def schedule_docs_build(package_version, rebuild=False):
+    """
+    Build the docs of a package version's crate once it has one.
+
+    Versions that already have docs are skipped unless rebuild is set (the
+    served crate changed, e.g. merged): later uploads of other binaries don't
+    change the crate the registry serves.
+
+    The RUST_DOCS_GENERATION setting selects how:
+    - "background" (default): in a thread started once the upload is committed
+    - "inline": before returning (tests, single-process deployments)
+    - "off": not at all (use the build_rust_docs management command)
+    """
+    if package_version.rust_docs_path and not rebuild:
+        return
+
+    mode = getattr(settings, 'RUST_DOCS_GENERATION', 'background')
+    if mode == 'inline':
+        build_missing_docs(package_version.pk, rebuild)
+    elif mode == 'background':
+        package_version_id = package_version.pk
+        transaction.on_commit(
+            lambda: threading.Thread(target=run_in_background, args=(package_version_id, rebuild), daemon=True).start()
+        )
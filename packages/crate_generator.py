@@ -0,0 +1,164 @@
+"""
+Server-side Rust crate generation
+
+Crates are normally generated by the uploader's CLI and sent with the binary.
+Binaries uploaded with --no-rust, or through the Conan REST endpoints
+(/v2/v1/...), have none: this generates their crate from the stored binary
+tarball (`conan cache save` format, or the bare package folder) and the
+dependency_graph, with the same generator as the CLI
+(generate_rust_crate() in conancrates/conancrates.py).
+
+Generation runs in a background thread after upload (RUST_CRATE_GENERATION
+setting) and from the generate_rust_crates management command, which
+backfills binaries uploaded without a crate. Generated crates then get their
+docs built (packages/crate_docs.py).
+"""
+import hashlib
+import tarfile
+import tempfile
+import threading
+from pathlib import Path
+
+from django.conf import settings
+from django.core.files.base import ContentFile
+from django.db import connection, transaction
+
+from packages.cargo_registry import PublishError, crate_name_for_package, is_header_only_archive, validate_crate_archive
+from packages.cargo_versions import cargo_version_for
+from packages.crate_docs import schedule_docs_build
+from packages.models import BinaryPackage
+
+
+class GenerationError(Exception):
+    """Exception raised when a crate can't be generated for a binary"""
+    pass
+
+
+def find_package_folder(extract_dir):
+    """
+    Find the package folder and recipe in an extracted binary tarball.
+
+    `conan cache save` tarballs hold the package folder (b/<hash>/p/) next to
+    the recipe's export folder (<hash>/e/); tarballs of the bare package folder
+    have conaninfo.txt at the root and no recipe.
+
+    Returns:
+        Tuple of (package folder, conanfile.py or None)
+
+    Raises:
+        GenerationError: If the tarball has no conaninfo.txt
+    """
+    extract_dir = Path(extract_dir)
+    conaninfo_files = sorted(extract_dir.rglob('conaninfo.txt'), key=lambda path: len(path.parts))
+    if not conaninfo_files:
+        raise GenerationError("Binary tarball has no conaninfo.txt")
+    package_folder = conaninfo_files[0].parent
+
+    recipes = [path for path in extract_dir.rglob('conanfile.py') if package_folder not in path.parents]
+    recipes.sort(key=lambda path: (path.parent.name != 'e', len(path.parts)))
+    return package_folder, recipes[0] if recipes else None
+
+
+def generate_crate(binary):
+    """
+    Generate the -sys crate of a binary and attach it as its rust_crate_file.
+
+    Raises:
+        GenerationError: If the binary has no tarball, or the generator fails
+    """
+    # Imported here: the CLI module is only needed by the generator
+    from conancrates.conancrates import CrateGenerationError, generate_rust_crate
+
+    if not binary.binary_file:
+        raise GenerationError(f"Binary {binary.package_id} has no uploaded tarball")
+
+    package_version = binary.package_version
+    package_name = package_version.package.name
+    crate_name = crate_name_for_package(package_name)
+    crate_version = cargo_version_for(package_version)
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        extract_dir = Path(tmpdir) / 'package'
+        binary.binary_file.open('rb')
+        try:
+            with tarfile.open(fileobj=binary.binary_file, mode='r:*') as tar:
+                # Uploads are untrusted: refuse links and paths outside extract_dir
+                tar.extractall(extract_dir, filter='data')
+        except (tarfile.TarError, OSError) as e:
+            raise GenerationError(f"Could not extract the binary tarball: {e}")
+        finally:
+            binary.binary_file.close()
+
+        package_folder, recipe_path = find_package_folder(extract_dir)
+
+        # The generator prints its progress (to the server's log), and raises errors
+        try:
+            _, crate_path = generate_rust_crate(
+                f"{package_name}/{package_version.version}",
+                package_folder,
+                Path(tmpdir) / 'crates',
+                binary.dependency_graph or None,
+                recipe_path=recipe_path,
+            )
+        except CrateGenerationError as e:
+            raise GenerationError(str(e))
+
+        crate_bytes = Path(crate_path).read_bytes()
+
+    try:
+        validate_crate_archive(crate_bytes, crate_name, crate_version)
+    except PublishError as e:
+        raise GenerationError(f"Generated crate is invalid: {e}")
+
+    binary.rust_crate_file.save(f"{crate_name}-{crate_version}.crate", ContentFile(crate_bytes), save=False)
+    binary.rust_crate_sha256 = hashlib.sha256(crate_bytes).hexdigest()
+    binary.rust_crate_header_only = is_header_only_archive(crate_bytes)
+    binary.save(update_fields=['rust_crate_file', 'rust_crate_sha256', 'rust_crate_header_only'])
+
+    schedule_docs_build(package_version)
+
+
+def generate_missing_crate(binary_id):
+    """
+    Generate the crate of a binary unless it has one by now.
+
+    Errors are logged, not raised: this runs after the upload has been answered.
+    """
+    binary = BinaryPackage.objects.select_related('package_version__package').filter(pk=binary_id).first()
+    if binary is None or binary.rust_crate_file:
+        return
+    try:
+        generate_crate(binary)
+        print(f"✓ Generated Rust crate for {binary.package_version}:{binary.package_id}")
+    except GenerationError as e:
+        print(f"✗ Error generating Rust crate for {binary.package_version}:{binary.package_id}: {e}")
+
+
+def run_in_background(binary_id):
+    """Thread target: generate the crate, then release the thread's database connection."""
+    try:
+        generate_missing_crate(binary_id)
+    finally:
+        connection.close()
+
+
+def schedule_crate_generation(binary):
+    """
+    Generate a crate for a binary uploaded without one.
+
+    The RUST_CRATE_GENERATION setting selects how:
+    - "background" (default): in a thread started once the upload is committed
+    - "inline": before returning (tests, single-process deployments)
+    - "off": not at all (use the generate_rust_crates management command)
+    """
+    if binary.rust_crate_file or not binary.binary_file:
+        return
+
+    mode = getattr(settings, 'RUST_CRATE_GENERATION', 'background')
+    if mode == 'inline':
+        generate_missing_crate(binary.pk)
+    elif mode == 'background':
+        binary_id = binary.pk
+        transaction.on_commit(
+            lambda: threading.Thread(target=run_in_background, args=(binary_id,), daemon=True).start()
+        )
@@ -0,0 +1,249 @@
+"""
+Multi-target ("fat") Rust crates for ConanCrates
+
+Every binary of a package version gets its own -sys crate with the libraries
+in native/current/. Merging combines those crates into one archive with a
+native/<target-triple>/ directory per binary, so a single crate version works
+on every platform: the generated build.rs links native/$TARGET when it exists.
+
+Each native directory carries a libs.txt link manifest (one cargo
+rustc-link-lib value per line), which is what makes build.rs independent of
+the target it was generated on. Crates generated before the manifest existed
+can't be merged and have to be regenerated.
+
+Binaries that differ in Conan options (shared, with_ssl, ...) become Cargo
+features: each option value that differs from the first merged binary is a
+feature, and the binary's libraries go to native/<target-triple>+<feature>...
+build.rs links the directory matching the enabled features.
+
+Headers aren't per target: crates whose include/ differs (e.g., a generated
+config.h) aren't merged, since one copy would be used on every platform.
+"""
+import io
+import re
+import tarfile
+import hashlib
+from django.core.files.base import ContentFile
+from packages.cargo_registry import binaries_with_crates, build_crate_archive, crate_name_for_binary, crate_name_for_package
+from packages.rust_targets import rust_target_for_binary
+from packages.cargo_versions import cargo_version_for
+
+
+NATIVE_MANIFEST = 'libs.txt'
+
+
+class MergeError(Exception):
+    """Exception raised when the crates of a package version can't be merged"""
+    pass
+
+
+def read_crate_members(binary):
+    """
+    Read a binary's .crate archive.
+
+    Returns:
+        Tuple of (root directory name, list of (relative path, TarInfo, bytes or None))
+    """
+    binary.rust_crate_file.open('rb')
+    try:
+        crate_bytes = binary.rust_crate_file.read()
+    finally:
+        binary.rust_crate_file.close()
+
+    root = None
+    members = []
+    with tarfile.open(fileobj=io.BytesIO(crate_bytes), mode='r:gz') as tar:
+        for member in tar.getmembers():
+            parts = member.name.strip('/').split('/', 1)
+            if root is None:
+                root = parts[0]
+            if parts[0] != root:
+                raise MergeError(f"Crate of binary {binary.package_id} has more than one top-level directory")
+            if len(parts) == 1:
+                continue
+            data = tar.extractfile(member).read() if member.isfile() else None
+            members.append((parts[1], member, data))
+
+    if root is None:
+        raise MergeError(f"Crate of binary {binary.package_id} is empty")
+    return root, members
+
+
+def native_directory(relative_path):
+    """Get the native/<dir> component of a path inside a crate (None if not under native/)."""
+    parts = relative_path.split('/')
+    if len(parts) >= 2 and parts[0] == 'native':
+        return parts[1]
+    return None
+
+
+def option_feature(option, value):
+    """
+    Get the Cargo feature selecting a Conan option value.
+
+    Examples:
+        ("shared", "True")       -> "shared"
+        ("shared", "False")      -> "shared-false"
+        ("with_ssl", "openssl")  -> "with_ssl-openssl"
+    """
+    name = option if value == 'True' else f"{option}-{value}"
+    return re.sub(r'[^a-z0-9_-]+', '-', name.lower()).strip('-')
+
+
+def option_variants(binaries):
+    """
+    Get the Cargo features of each binary: one per option value that differs
+    from the first binary's (the default variant, which has no features).
+
+    Returns:
+        Tuple of (dict of binary id -> sorted features,
+                  dict of feature -> {option: value})
+    """
+    base_options = binaries[0].options or {}
+    option_names = sorted({name for binary in binaries for name in (binary.options or {})})
+
+    variants = {}
+    features = {}
+    for binary in binaries:
+        options = binary.options or {}
+        variant = []
+        for name in option_names:
+            value = options.get(name)
+            if value is None or str(value) == str(base_options.get(name)):
+                continue
+            feature = option_feature(name, str(value))
+            variant.append(feature)
+            features[feature] = {name: str(value)}
+        variants[binary.id] = sorted(variant)
+    return variants, features
+
+
+def variant_directory(target, features):
+    """Get the native/ directory of a target and option variant (e.g., "x86_64-unknown-linux-gnu+shared")."""
+    return target + ''.join(f"+{feature}" for feature in features)
+
+
+def merge_rust_crates(package_version):
+    """
+    Merge the generated -sys crates of all binaries of a package version.
+
+    The first merged crate provides everything outside native/ (Cargo.toml,
+    build.rs, src/, include/); the headers of the others must be the same.
+    Its native/ directories, and those of every other binary, are added under
+    the binary's target triple and option variant, and Cargo.toml gets a
+    [features] table for the variants. When two
+    binaries map to the same directory (e.g., Debug and Release), the Release
+    build wins, then the oldest upload. The result is a reproducible archive
+    under {crate_name}-{version}/, whatever the root of the merged crates.
+
+    Returns:
+        Tuple of (crate bytes, list of target triples,
+                  dict of feature -> {option: value}, list of skipped package_ids)
+
+    Raises:
+        MergeError: If there's nothing to merge, a crate predates libs.txt
+                    manifests (or feature selection, for option variants), or
+                    the crates' headers differ
+    """
+    crate_name = crate_name_for_package(package_version.package.name)
+
+    binaries = [
+        binary for binary in binaries_with_crates(package_version).order_by('created_at', 'id')
+        if crate_name_for_binary(binary).lower() == crate_name
+    ]
+    if not binaries:
+        raise MergeError(f"{package_version} has no generated Rust crates to merge")
+
+    # Release first, then upload order (sort is stable)
+    binaries.sort(key=lambda binary: binary.build_type != 'Release')
+    variants, option_features = option_variants(binaries)
+
+    base_binary = None
+    base_headers = {}
+    files = {}
+    merged_dirs = []
+    used_features = set()
+    skipped = []
+
+    for binary in binaries:
+        _, members = read_crate_members(binary)
+        target = rust_target_for_binary(binary)
+
+        native_dirs = {native_directory(path) for path, _, _ in members} - {None}
+        for native_dir in native_dirs:
+            if f"native/{native_dir}/{NATIVE_MANIFEST}" not in {path for path, _, _ in members}:
+                raise MergeError(
+                    f"Crate of binary {binary.package_id} has no native/{native_dir}/{NATIVE_MANIFEST}; "
+                    f"regenerate it with the current conancrates CLI"
+                )
+
+        # Map native/current to this binary's triple, then add its option variant
+        renamed = {}
+        for native_dir in native_dirs:
+            dir_target = target if native_dir == 'current' else native_dir
+            renamed[native_dir] = variant_directory(dir_target, variants[binary.id]) if dir_target else None
+        if None in renamed.values() or any(name in merged_dirs for name in renamed.values()):
+            skipped.append(binary.package_id)
+            continue
+
+        # One include/ serves every target: refuse headers that differ
+        headers = {path: data for path, _, data in members if data is not None and path.startswith('include/')}
+        is_base = base_binary is None
+        if is_base:
+            base_binary, base_headers = binary, headers
+        elif headers != base_headers:
+            differing = sorted(path for path in set(headers) | set(base_headers) if headers.get(path) != base_headers.get(path))
+            raise MergeError(
+                f"Headers of binary {binary.package_id} differ from those of binary {base_binary.package_id} "
+                f"({', '.join(differing[:5])}{', ...' if len(differing) > 5 else ''}): a merged crate has "
+                f"one include/ for every target"
+            )
+
+        for path, member, data in members:
+            if data is None:
+                continue
+            native_dir = native_directory(path)
+            if native_dir is None:
+                if is_base:
+                    files[path] = data
+                continue
+            parts = path.split('/')
+            parts[1] = renamed[native_dir]
+            files['/'.join(parts)] = data
+
+        merged_dirs.extend(sorted(set(renamed.values())))
+        used_features.update(variants[binary.id])
+
+    if not merged_dirs:
+        raise MergeError(f"None of the crates of {package_version} could be mapped to a Rust target")
+
+    features = {feature: option_features[feature] for feature in sorted(used_features)}
+    if features:
+        if b'CARGO_FEATURE_' not in files.get('build.rs', b''):
+            raise MergeError(
+                f"Binaries of {package_version} differ in options but the crate's build.rs can't "
+                f"select option variants; regenerate it with the current conancrates CLI"
+            )
+        feature_lines = ''.join(f"{feature} = []\n" for feature in features)
+        for manifest in ('Cargo.toml', 'Cargo.toml.orig'):
+            if manifest in files:
+                files[manifest] = files[manifest].rstrip(b'\n') + f"\n\n[features]\n{feature_lines}".encode()
+
+    crate_bytes = build_crate_archive(f"{crate_name}-{cargo_version_for(package_version)}", files)
+
+    targets = sorted({name.split('+', 1)[0] for name in merged_dirs})
+    return crate_bytes, targets, features, skipped
+
+
+def store_merged_crate(package_version, crate_bytes, targets, features=None):
+    """Save a merged crate on the package version and record its checksum."""
+    crate_name = crate_name_for_package(package_version.package.name)
+    package_version.rust_crate_file.save(
+        f"{crate_name}-{cargo_version_for(package_version)}.crate",
+        ContentFile(crate_bytes),
+        save=False
+    )
+    package_version.rust_crate_sha256 = hashlib.sha256(crate_bytes).hexdigest()
+    package_version.rust_crate_targets = targets
+    package_version.rust_crate_features = features or {}
+    package_version.save()
@@ -0,0 +1,51 @@
+"""
+Build the rustdoc of crate versions that have no docs yet.
+
+Usage:
+    python manage.py build_rust_docs                # every crate version without docs
+    python manage.py build_rust_docs zlib openssl   # only these packages
+    python manage.py build_rust_docs --rebuild      # also those that have docs
+    python manage.py build_rust_docs --dry-run      # list them
+"""
+from django.core.management.base import BaseCommand
+from django.db.models import Q
+
+from packages.crate_docs import DocsError, build_docs
+from packages.models import PackageVersion
+
+
+class Command(BaseCommand):
+    help = 'Build the rustdoc served at /docs/<crate>/<version>/ for every package version with a Rust crate'
+
+    def add_arguments(self, parser):
+        parser.add_argument('packages', nargs='*', help='Only build docs for these package names')
+        parser.add_argument('--rebuild', action='store_true', help='Rebuild docs that were already built')
+        parser.add_argument('--dry-run', action='store_true', help='List the versions without building docs')
+
+    def handle(self, *args, **options):
+        versions = PackageVersion.objects.filter(
+            Q(binaries__rust_crate_file__gt='') | Q(rust_crate_file__gt='')
+        ).distinct().select_related('package').order_by('created_at', 'id')
+        if not options['rebuild']:
+            versions = versions.filter(rust_docs_path='')
+        if options['packages']:
+            versions = versions.filter(package__name__in=options['packages'])
+
+        built = 0
+        failed = 0
+        for package_version in versions:
+            label = str(package_version)
+            if options['dry_run']:
+                self.stdout.write(label)
+                continue
+            try:
+                build_docs(package_version)
+            except DocsError as e:
+                failed += 1
+                self.stderr.write(self.style.ERROR(f"✗ {label}: {e}"))
+            else:
+                built += 1
+                self.stdout.write(self.style.SUCCESS(f"✓ {label}"))
+
+        if not options['dry_run']:
+            self.stdout.write(f"Built docs for {built} version{'s' if built != 1 else ''}, {failed} failed")
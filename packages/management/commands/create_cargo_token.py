@@ -0,0 +1,31 @@
+"""
+Create a token for publishing crates with cargo.
+
+Usage:
+    python manage.py create_cargo_token alice            # prints the token
+    python manage.py create_cargo_token alice --name CI
+
+Then, on the publishing machine:
+    cargo login --registry conancrates <token>
+"""
+from django.contrib.auth.models import User
+from django.core.management.base import BaseCommand, CommandError
+
+from packages.cargo_registry import create_cargo_token
+
+
+class Command(BaseCommand):
+    help = 'Create a token cargo publish authenticates with (cargo login --registry conancrates)'
+
+    def add_arguments(self, parser):
+        parser.add_argument('username', help='User publishing with the token')
+        parser.add_argument('--name', default='', help='What the token is for (e.g., CI)')
+
+    def handle(self, *args, **options):
+        user = User.objects.filter(username=options['username']).first()
+        if user is None:
+            raise CommandError(f"No user named {options['username']}")
+
+        token = create_cargo_token(user, options['name'])
+        self.stdout.write(token)
+        self.stderr.write("Only its SHA256 is stored: it can't be shown again")
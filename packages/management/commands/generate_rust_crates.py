@@ -0,0 +1,49 @@
+"""
+Generate Rust crates for binaries uploaded without one.
+
+Usage:
+    python manage.py generate_rust_crates                # every binary missing a crate
+    python manage.py generate_rust_crates zlib openssl   # only these packages
+    python manage.py generate_rust_crates --dry-run      # list them
+"""
+from django.core.management.base import BaseCommand
+from django.db.models import Q
+
+from packages.crate_generator import GenerationError, generate_crate
+from packages.models import BinaryPackage
+
+
+class Command(BaseCommand):
+    help = 'Generate the Rust -sys crate of every binary package that has no rust_crate_file'
+
+    def add_arguments(self, parser):
+        parser.add_argument('packages', nargs='*', help='Only generate crates for these package names')
+        parser.add_argument('--dry-run', action='store_true', help='List the binaries without generating crates')
+
+    def handle(self, *args, **options):
+        binaries = BinaryPackage.objects.filter(
+            Q(rust_crate_file='') | Q(rust_crate_file__isnull=True)
+        ).exclude(
+            Q(binary_file='') | Q(binary_file__isnull=True)
+        ).select_related('package_version__package').order_by('created_at', 'id')
+        if options['packages']:
+            binaries = binaries.filter(package_version__package__name__in=options['packages'])
+
+        generated = 0
+        failed = 0
+        for binary in binaries:
+            label = f"{binary.package_version}:{binary.package_id}"
+            if options['dry_run']:
+                self.stdout.write(label)
+                continue
+            try:
+                generate_crate(binary)
+            except GenerationError as e:
+                failed += 1
+                self.stderr.write(self.style.ERROR(f"✗ {label}: {e}"))
+            else:
+                generated += 1
+                self.stdout.write(self.style.SUCCESS(f"✓ {label}"))
+
+        if not options['dry_run']:
+            self.stdout.write(f"Generated {generated} crate{'s' if generated != 1 else ''}, {failed} failed")
@@ -0,0 +1,51 @@
+"""
+Index the symbols and headers of binaries uploaded before symbol indexing.
+
+Usage:
+    python manage.py index_symbols                # every binary without indexed symbols
+    python manage.py index_symbols zlib openssl   # only these packages
+    python manage.py index_symbols --reindex      # also those already indexed
+    python manage.py index_symbols --dry-run      # list them
+"""
+from django.core.management.base import BaseCommand
+from django.db.models import Q
+
+from packages.models import BinaryPackage
+from packages.symbol_index import IndexingError, index_binary
+
+
+class Command(BaseCommand):
+    help = 'Index the exported symbols and headers searched at /symbols/ for every binary package'
+
+    def add_arguments(self, parser):
+        parser.add_argument('packages', nargs='*', help='Only index binaries of these package names')
+        parser.add_argument('--reindex', action='store_true', help='Reindex binaries that already have symbols')
+        parser.add_argument('--dry-run', action='store_true', help='List the binaries without indexing them')
+
+    def handle(self, *args, **options):
+        binaries = BinaryPackage.objects.exclude(
+            Q(binary_file='') | Q(binary_file__isnull=True)
+        ).select_related('package_version__package').order_by('created_at', 'id')
+        if not options['reindex']:
+            binaries = binaries.filter(symbols__isnull=True)
+        if options['packages']:
+            binaries = binaries.filter(package_version__package__name__in=options['packages'])
+
+        indexed = 0
+        failed = 0
+        for binary in binaries:
+            label = f"{binary.package_version}:{binary.package_id}"
+            if options['dry_run']:
+                self.stdout.write(label)
+                continue
+            try:
+                symbols, headers = index_binary(binary)
+            except IndexingError as e:
+                failed += 1
+                self.stderr.write(self.style.ERROR(f"✗ {label}: {e}"))
+            else:
+                indexed += 1
+                self.stdout.write(self.style.SUCCESS(f"✓ {label}: {symbols} symbols, {headers} headers"))
+
+        if not options['dry_run']:
+            self.stdout.write(f"Indexed {indexed} binar{'ies' if indexed != 1 else 'y'}, {failed} failed")
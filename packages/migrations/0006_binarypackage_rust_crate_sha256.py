@@ -0,0 +1,18 @@
+# Generated by Django 5.2.7 on 2025-11-10 18:12
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0005_binarypackage_rust_crate_file'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='binarypackage',
+            name='rust_crate_sha256',
+            field=models.CharField(blank=True, help_text='SHA256 of the .crate archive (Cargo index cksum)', max_length=64),
+        ),
+    ]
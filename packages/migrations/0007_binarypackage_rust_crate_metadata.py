@@ -0,0 +1,18 @@
+# Generated by Django 5.2.7 on 2025-11-12 09:41
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0006_binarypackage_rust_crate_sha256'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='binarypackage',
+            name='rust_crate_metadata',
+            field=models.JSONField(blank=True, default=dict, help_text='Crate metadata sent by cargo publish (name, deps, features, links)'),
+        ),
+    ]
@@ -0,0 +1,28 @@
+# Generated by Django 5.2.7 on 2025-11-13 14:02
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0007_binarypackage_rust_crate_metadata'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='packageversion',
+            name='rust_crate_file',
+            field=models.FileField(blank=True, help_text='Merged multi-target Rust -sys crate archive', null=True, upload_to='rust_crates/'),
+        ),
+        migrations.AddField(
+            model_name='packageversion',
+            name='rust_crate_sha256',
+            field=models.CharField(blank=True, help_text='SHA256 of the merged .crate archive (Cargo index cksum)', max_length=64),
+        ),
+        migrations.AddField(
+            model_name='packageversion',
+            name='rust_crate_targets',
+            field=models.JSONField(blank=True, default=list, help_text='Rust target triples included in the merged crate'),
+        ),
+    ]
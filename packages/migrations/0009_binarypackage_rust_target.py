@@ -0,0 +1,71 @@
+# Generated by Django 5.2.7 on 2025-11-14 10:27
+
+from django.db import migrations, models
+
+
+# packages.rust_targets.RUST_TARGETS when this migration was written
+RUST_TARGETS = {
+    ('Linux', 'x86_64'): 'x86_64-unknown-linux-gnu',
+    ('Linux', 'x86'): 'i686-unknown-linux-gnu',
+    ('Linux', 'armv8'): 'aarch64-unknown-linux-gnu',
+    ('Linux', 'armv7'): 'armv7-unknown-linux-gnueabi',
+    ('Linux', 'armv7hf'): 'armv7-unknown-linux-gnueabihf',
+    ('Linux', 'armv6'): 'arm-unknown-linux-gnueabi',
+    ('Linux', 'ppc64le'): 'powerpc64le-unknown-linux-gnu',
+    ('Linux', 'ppc64'): 'powerpc64-unknown-linux-gnu',
+    ('Linux', 's390x'): 's390x-unknown-linux-gnu',
+    ('Linux', 'riscv64'): 'riscv64gc-unknown-linux-gnu',
+    ('Macos', 'x86_64'): 'x86_64-apple-darwin',
+    ('Macos', 'armv8'): 'aarch64-apple-darwin',
+    ('iOS', 'armv8'): 'aarch64-apple-ios',
+    ('iOS', 'x86_64'): 'x86_64-apple-ios',
+    ('Android', 'armv8'): 'aarch64-linux-android',
+    ('Android', 'armv7'): 'armv7-linux-androideabi',
+    ('Android', 'x86'): 'i686-linux-android',
+    ('Android', 'x86_64'): 'x86_64-linux-android',
+    ('FreeBSD', 'x86_64'): 'x86_64-unknown-freebsd',
+    ('FreeBSD', 'armv8'): 'aarch64-unknown-freebsd',
+    ('Emscripten', 'wasm'): 'wasm32-unknown-emscripten',
+}
+
+# packages.rust_targets.RUST_WINDOWS_TARGETS when this migration was written
+RUST_WINDOWS_TARGETS = {
+    ('x86_64', 'msvc'): 'x86_64-pc-windows-msvc',
+    ('x86', 'msvc'): 'i686-pc-windows-msvc',
+    ('armv8', 'msvc'): 'aarch64-pc-windows-msvc',
+    ('x86_64', 'gnu'): 'x86_64-pc-windows-gnu',
+    ('x86', 'gnu'): 'i686-pc-windows-gnu',
+}
+
+
+def rust_target_for_settings(os_name, arch, compiler):
+    """packages.rust_targets.rust_target_for_settings when this migration was written."""
+    if os_name == 'Windows':
+        return RUST_WINDOWS_TARGETS.get((arch, 'gnu' if compiler == 'gcc' else 'msvc'))
+    return RUST_TARGETS.get((os_name, arch))
+
+
+def set_rust_targets(apps, schema_editor):
+    """Fill in rust_target for binaries uploaded before it was recorded."""
+    BinaryPackage = apps.get_model('packages', 'BinaryPackage')
+    for binary in BinaryPackage.objects.filter(rust_target=''):
+        rust_target = rust_target_for_settings(binary.os, binary.arch, binary.compiler)
+        if rust_target:
+            binary.rust_target = rust_target
+            binary.save(update_fields=['rust_target'])
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0008_packageversion_rust_crate_file'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='binarypackage',
+            name='rust_target',
+            field=models.CharField(blank=True, db_index=True, help_text='Rust target triple for the settings (e.g., x86_64-unknown-linux-gnu)', max_length=100),
+        ),
+        migrations.RunPython(set_rust_targets, migrations.RunPython.noop),
+    ]
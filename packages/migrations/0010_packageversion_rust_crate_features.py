@@ -0,0 +1,18 @@
+# Generated by Django 5.2.7 on 2025-11-14 15:42
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0009_binarypackage_rust_target'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='packageversion',
+            name='rust_crate_features',
+            field=models.JSONField(blank=True, default=dict, help_text='Cargo features of the merged crate and the Conan option values they select'),
+        ),
+    ]
@@ -0,0 +1,82 @@
+# Generated by Django 5.2.7 on 2025-11-15 09:12
+
+import re
+
+from django.db import migrations, models
+
+
+# packages.cargo_versions.SEMVER_RE when this migration was written
+SEMVER_RE = re.compile(
+    r'^(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)'
+    r'(?:-((?:0|[1-9]\d*|\d*[A-Za-z-][0-9A-Za-z-]*)(?:\.(?:0|[1-9]\d*|\d*[A-Za-z-][0-9A-Za-z-]*))*))?'
+    r'(?:\+([0-9A-Za-z-]+(?:\.[0-9A-Za-z-]+)*))?$'
+)
+
+
+def semver_identifiers(text):
+    """packages.cargo_versions.semver_identifiers when this migration was written."""
+    identifiers = []
+    for part in text.split('.'):
+        part = re.sub(r'[^0-9A-Za-z-]', '-', part)
+        if part:
+            identifiers.append(part)
+    return identifiers
+
+
+def cargo_version(version):
+    """packages.cargo_versions.cargo_version when this migration was written."""
+    version = version.strip()
+    if SEMVER_RE.match(version):
+        return version
+
+    version, _, build = version.partition('+')
+    version, _, prerelease = version.partition('-')
+    if re.match(r'^[vV]\d', version):
+        version = version[1:]
+
+    numbers = []
+    extra = []
+    for part in version.split('.'):
+        match = re.match(r'^(\d+)(.*)$', part)
+        if match and len(numbers) < 3 and not (numbers and extra):
+            numbers.append(str(int(match.group(1))))
+            extra.extend(semver_identifiers(match.group(2)))
+        else:
+            extra.extend(semver_identifiers(part))
+    numbers += ['0'] * (3 - len(numbers))
+
+    result = '.'.join(numbers)
+    prerelease = [
+        str(int(identifier)) if identifier.isdigit() else identifier
+        for identifier in semver_identifiers(prerelease)
+    ]
+    if prerelease:
+        result += '-' + '.'.join(prerelease)
+    metadata = extra + semver_identifiers(build)
+    if metadata:
+        result += '+' + '.'.join(metadata)
+    return result
+
+
+def set_cargo_versions(apps, schema_editor):
+    """Fill in cargo_version for package versions uploaded before it was recorded."""
+    PackageVersion = apps.get_model('packages', 'PackageVersion')
+    for package_version in PackageVersion.objects.filter(cargo_version=''):
+        package_version.cargo_version = cargo_version(package_version.version)
+        package_version.save(update_fields=['cargo_version'])
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0010_packageversion_rust_crate_features'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='packageversion',
+            name='cargo_version',
+            field=models.CharField(blank=True, db_index=True, help_text='Semver version used for the Rust crate, see packages/cargo_versions.py', max_length=100),
+        ),
+        migrations.RunPython(set_cargo_versions, migrations.RunPython.noop),
+    ]
@@ -0,0 +1,23 @@
+# Generated by Django 5.2.7 on 2025-11-17 14:05
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0011_packageversion_cargo_version'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='binarypackage',
+            name='compiler_libcxx',
+            field=models.CharField(blank=True, help_text='C++ standard library (compiler.libcxx, e.g. libstdc++11, libc++)', max_length=50),
+        ),
+        migrations.AddField(
+            model_name='binarypackage',
+            name='compiler_runtime',
+            field=models.CharField(blank=True, help_text='MSVC runtime (compiler.runtime, e.g. dynamic, static)', max_length=50),
+        ),
+    ]
@@ -0,0 +1,23 @@
+# Generated by Django 5.2.7 on 2025-11-19 10:42
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0012_binarypackage_compiler_libcxx_runtime'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='packageversion',
+            name='rust_docs_path',
+            field=models.CharField(blank=True, help_text='Entry page of the generated rustdoc, relative to /docs/<crate>/<version>/', max_length=200),
+        ),
+        migrations.AddField(
+            model_name='packageversion',
+            name='rust_docs_error',
+            field=models.TextField(blank=True, help_text='Why the last rustdoc build failed'),
+        ),
+    ]
@@ -0,0 +1,28 @@
+# Generated by Django 5.2.7 on 2025-11-20 09:15
+
+import django.db.models.deletion
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0013_packageversion_rust_docs'),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name='BinarySymbol',
+            fields=[
+                ('id', models.BigAutoField(auto_created=True, primary_key=True, serialize=False, verbose_name='ID')),
+                ('kind', models.CharField(choices=[('symbol', 'Symbol'), ('header', 'Header')], max_length=10)),
+                ('name', models.CharField(help_text='Symbol as the linker sees it, or header path relative to include/', max_length=1024)),
+                ('library', models.CharField(blank=True, help_text='Library file exporting the symbol (e.g., libssl.a)', max_length=255)),
+                ('binary', models.ForeignKey(on_delete=django.db.models.deletion.CASCADE, related_name='symbols', to='packages.binarypackage')),
+            ],
+            options={
+                'ordering': ['kind', 'name'],
+                'indexes': [models.Index(fields=['kind', 'name'], name='packages_symbol_kind_name_idx')],
+            },
+        ),
+    ]
@@ -0,0 +1,18 @@
+# Generated by Django 5.2.7 on 2025-11-21 08:30
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0014_binarysymbol'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='packageversion',
+            name='rust_crate_indexed_at',
+            field=models.DateTimeField(blank=True, help_text="When Cargo first got a crate of this version (index or download): its checksum can't change after", null=True),
+        ),
+    ]
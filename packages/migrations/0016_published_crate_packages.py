@@ -0,0 +1,40 @@
+# Generated by Django 5.2.7 on 2025-11-24 09:15
+
+from django.db import migrations
+
+
+# packages.cargo_registry.PUBLISHED_PACKAGE_PREFIX when this migration was written
+PUBLISHED_PACKAGE_PREFIX = 'cargo:'
+
+
+def move_published_crates(apps, schema_editor):
+    """
+    Rename the packages cargo publish created for crates that aren't the -sys
+    crate of a Conan package (every binary a source-only published crate) into
+    the published crates' namespace.
+    """
+    Package = apps.get_model('packages', 'Package')
+    BinaryPackage = apps.get_model('packages', 'BinaryPackage')
+    for package in Package.objects.exclude(name__startswith=PUBLISHED_PACKAGE_PREFIX):
+        binaries = list(BinaryPackage.objects.filter(package_version__package=package))
+        generated_name = f"{package.name.replace('_', '-')}-sys".lower()
+        published_names = [(binary.rust_crate_metadata or {}).get('name') or '' for binary in binaries]
+        if not binaries or any(binary.binary_file for binary in binaries):
+            continue
+        if any(not name or name.lower() == generated_name for name in published_names):
+            continue
+        new_name = f"{PUBLISHED_PACKAGE_PREFIX}{package.name}"
+        if not Package.objects.filter(name=new_name).exists():
+            package.name = new_name
+            package.save(update_fields=['name'])
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0015_packageversion_rust_crate_indexed_at'),
+    ]
+
+    operations = [
+        migrations.RunPython(move_published_crates, migrations.RunPython.noop),
+    ]
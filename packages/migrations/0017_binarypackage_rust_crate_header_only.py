@@ -0,0 +1,18 @@
+# Generated by Django 5.2.7 on 2025-11-25 10:04
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0016_published_crate_packages'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='binarypackage',
+            name='rust_crate_header_only',
+            field=models.BooleanField(blank=True, help_text='Whether the .crate is header-only (its Cargo.toml has no links key)', null=True),
+        ),
+    ]
@@ -0,0 +1,37 @@
+# Generated by Django 5.2.7 on 2025-11-26 11:20
+
+from django.db import migrations, models
+
+
+def set_basenames(apps, schema_editor):
+    """Fill in basename for headers indexed before it was recorded."""
+    BinarySymbol = apps.get_model('packages', 'BinarySymbol')
+    headers = BinarySymbol.objects.filter(kind='header', basename='').only('id', 'name')
+    batch = []
+    for header in headers.iterator(chunk_size=1000):
+        header.basename = header.name.rsplit('/', 1)[-1]
+        batch.append(header)
+        if len(batch) == 1000:
+            BinarySymbol.objects.bulk_update(batch, ['basename'])
+            batch = []
+    BinarySymbol.objects.bulk_update(batch, ['basename'])
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('packages', '0017_binarypackage_rust_crate_header_only'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='binarysymbol',
+            name='basename',
+            field=models.CharField(blank=True, help_text='Last component of a header path (e.g., ssl.h), for suffix searches', max_length=1024),
+        ),
+        migrations.RunPython(set_basenames, migrations.RunPython.noop),
+        migrations.AddIndex(
+            model_name='binarysymbol',
+            index=models.Index(fields=['kind', 'basename'], name='packages_symbol_kind_base_idx'),
+        ),
+    ]
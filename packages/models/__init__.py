@@ -4,6 +4,8 @@ Package models for ConanCrates
 from .package import Package
 from .package_version import PackageVersion
 from .binary_package import BinaryPackage
+from .binary_symbol import BinarySymbol
+from .cargo_token import CargoToken
 from .dependency import Dependency
 from .topic import Topic
 
@@ -11,6 +13,8 @@ __all__ = [
     'Package',
     'PackageVersion',
     'BinaryPackage',
+    'BinarySymbol',
+    'CargoToken',
     'Dependency',
     'Topic',
 ]
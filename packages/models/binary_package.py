@@ -15,7 +15,13 @@ class BinaryPackage(models.Model):
     arch = models.CharField(max_length=50, blank=True)
     compiler = models.CharField(max_length=50, blank=True)
     compiler_version = models.CharField(max_length=50, blank=True)
+    compiler_libcxx = models.CharField(max_length=50, blank=True,
+                                       help_text="C++ standard library (compiler.libcxx, e.g. libstdc++11, libc++)")
+    compiler_runtime = models.CharField(max_length=50, blank=True,
+                                        help_text="MSVC runtime (compiler.runtime, e.g. dynamic, static)")
     build_type = models.CharField(max_length=50, blank=True)  # Debug, Release, etc.
+    rust_target = models.CharField(max_length=100, blank=True, db_index=True,
+                                   help_text="Rust target triple for the settings (e.g., x86_64-unknown-linux-gnu)")
 
     # Additional options (JSON field for flexibility)
     options = models.JSONField(default=dict, blank=True)
@@ -31,6 +37,12 @@ class BinaryPackage(models.Model):
     # Rust crate file (.crate archive)
     rust_crate_file = models.FileField(upload_to='rust_crates/', blank=True, null=True,
                                        help_text="Generated Rust -sys crate archive")
+    rust_crate_sha256 = models.CharField(max_length=64, blank=True,
+                                         help_text="SHA256 of the .crate archive (Cargo index cksum)")
+    rust_crate_header_only = models.BooleanField(null=True, blank=True,
+                                                 help_text="Whether the .crate is header-only (its Cargo.toml has no links key)")
+    rust_crate_metadata = models.JSONField(default=dict, blank=True,
+                                           help_text="Crate metadata sent by cargo publish (name, deps, features, links)")
 
     # Checksums
     sha256 = models.CharField(max_length=64, blank=True)
@@ -53,7 +65,10 @@ class BinaryPackage(models.Model):
         if self.arch:
             parts.append(f"Arch: {self.arch}")
         if self.compiler:
-            parts.append(f"Compiler: {self.compiler} {self.compiler_version}")
+            compiler = f"Compiler: {self.compiler} {self.compiler_version}"
+            if self.compiler_libcxx or self.compiler_runtime:
+                compiler += f" ({self.compiler_libcxx or self.compiler_runtime})"
+            parts.append(compiler)
         if self.build_type:
             parts.append(f"Build: {self.build_type}")
         return ", ".join(parts)
@@ -0,0 +1,31 @@
+from django.db import models
+
+
+class BinarySymbol(models.Model):
+    """
+    A symbol exported by a library of a binary package, or a header it ships.
+    Answers "which package provides SSL_CTX_new / <zlib.h>?" (see packages/symbol_index.py).
+    """
+    binary = models.ForeignKey('BinaryPackage', on_delete=models.CASCADE, related_name='symbols')
+
+    KINDS = [
+        ('symbol', 'Symbol'),
+        ('header', 'Header'),
+    ]
+    kind = models.CharField(max_length=10, choices=KINDS)
+    name = models.CharField(max_length=1024,
+                            help_text="Symbol as the linker sees it, or header path relative to include/")
+    basename = models.CharField(max_length=1024, blank=True,
+                                help_text="Last component of a header path (e.g., ssl.h), for suffix searches")
+    library = models.CharField(max_length=255, blank=True,
+                               help_text="Library file exporting the symbol (e.g., libssl.a)")
+
+    class Meta:
+        ordering = ['kind', 'name']
+        indexes = [
+            models.Index(fields=['kind', 'name'], name='packages_symbol_kind_name_idx'),
+            models.Index(fields=['kind', 'basename'], name='packages_symbol_kind_base_idx'),
+        ]
+
+    def __str__(self):
+        return f"{self.name} ({self.binary})"
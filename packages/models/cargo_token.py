@@ -0,0 +1,22 @@
+from django.db import models
+from django.contrib.auth.models import User
+
+
+class CargoToken(models.Model):
+    """
+    A token cargo sends to the registry (cargo login --registry conancrates).
+    Identifies the user publishing crates (see packages/cargo_registry.py).
+    Only the SHA256 of the token is stored.
+    """
+    user = models.ForeignKey(User, on_delete=models.CASCADE, related_name='cargo_tokens')
+    name = models.CharField(max_length=100, blank=True, help_text="What the token is for (e.g., CI)")
+    token_sha256 = models.CharField(max_length=64, unique=True, help_text="SHA256 of the token")
+
+    created_at = models.DateTimeField(auto_now_add=True)
+    last_used_at = models.DateTimeField(null=True, blank=True)
+
+    class Meta:
+        ordering = ['-created_at']
+
+    def __str__(self):
+        return f"{self.user} ({self.name or 'cargo token'})"
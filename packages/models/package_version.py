@@ -9,6 +9,8 @@ class PackageVersion(models.Model):
     """
     package = models.ForeignKey('Package', on_delete=models.CASCADE, related_name='versions')
     version = models.CharField(max_length=100, db_index=True)
+    cargo_version = models.CharField(max_length=100, blank=True, db_index=True,
+                                     help_text="Semver version used for the Rust crate, see packages/cargo_versions.py")
 
     # Recipe information
     recipe_revision = models.CharField(max_length=64, blank=True, help_text="Git/hash of recipe")
@@ -16,6 +18,23 @@ class PackageVersion(models.Model):
     recipe_content = models.TextField(blank=True, help_text="Content of conanfile.py")
     conan_version = models.CharField(max_length=50, blank=True, help_text="Version of Conan used to create this package")
 
+    # Multi-target Rust crate (native/<target-triple>/ for every binary), see packages/crate_merge.py
+    rust_crate_file = models.FileField(upload_to='rust_crates/', blank=True, null=True,
+                                       help_text="Merged multi-target Rust -sys crate archive")
+    rust_crate_sha256 = models.CharField(max_length=64, blank=True,
+                                         help_text="SHA256 of the merged .crate archive (Cargo index cksum)")
+    rust_crate_targets = models.JSONField(default=list, blank=True,
+                                          help_text="Rust target triples included in the merged crate")
+    rust_crate_features = models.JSONField(default=dict, blank=True,
+                                           help_text="Cargo features of the merged crate and the Conan option values they select")
+    rust_crate_indexed_at = models.DateTimeField(null=True, blank=True,
+                                                 help_text="When Cargo first got a crate of this version (index or download): its checksum can't change after")
+
+    # Rustdoc of the crate served for this version (/docs/<crate>/<version>/), see packages/crate_docs.py
+    rust_docs_path = models.CharField(max_length=200, blank=True,
+                                      help_text="Entry page of the generated rustdoc, relative to /docs/<crate>/<version>/")
+    rust_docs_error = models.TextField(blank=True, help_text="Why the last rustdoc build failed")
+
     # Settings that affect this version
     description = models.TextField(blank=True)
 
@@ -0,0 +1,112 @@
+"""
+Mapping between Conan settings and Rust target triples
+
+Cargo and build scripts identify platforms by target triple (TARGET,
+e.g. "x86_64-unknown-linux-gnu"), Conan by os/arch/compiler settings. Every
+BinaryPackage stores the triple it was built for (rust_target), generated
+-sys crates keep each platform's prebuilt libraries under
+native/<target-triple>/, and the APIs accept ?target=<triple>.
+
+The CLI has its own copy (conan_settings_to_rust_target() in
+conancrates/conancrates.py), which names the native/ directories;
+packages/tests/test_rust_targets.py checks that both agree.
+"""
+
+
+# (Conan os, Conan arch) -> Rust target triple
+RUST_TARGETS = {
+    ('Linux', 'x86_64'): 'x86_64-unknown-linux-gnu',
+    ('Linux', 'x86'): 'i686-unknown-linux-gnu',
+    ('Linux', 'armv8'): 'aarch64-unknown-linux-gnu',
+    ('Linux', 'armv7'): 'armv7-unknown-linux-gnueabi',
+    ('Linux', 'armv7hf'): 'armv7-unknown-linux-gnueabihf',
+    ('Linux', 'armv6'): 'arm-unknown-linux-gnueabi',
+    ('Linux', 'ppc64le'): 'powerpc64le-unknown-linux-gnu',
+    ('Linux', 'ppc64'): 'powerpc64-unknown-linux-gnu',
+    ('Linux', 's390x'): 's390x-unknown-linux-gnu',
+    ('Linux', 'riscv64'): 'riscv64gc-unknown-linux-gnu',
+    ('Macos', 'x86_64'): 'x86_64-apple-darwin',
+    ('Macos', 'armv8'): 'aarch64-apple-darwin',
+    ('iOS', 'armv8'): 'aarch64-apple-ios',
+    ('iOS', 'x86_64'): 'x86_64-apple-ios',
+    ('Android', 'armv8'): 'aarch64-linux-android',
+    ('Android', 'armv7'): 'armv7-linux-androideabi',
+    ('Android', 'x86'): 'i686-linux-android',
+    ('Android', 'x86_64'): 'x86_64-linux-android',
+    ('FreeBSD', 'x86_64'): 'x86_64-unknown-freebsd',
+    ('FreeBSD', 'armv8'): 'aarch64-unknown-freebsd',
+    ('Emscripten', 'wasm'): 'wasm32-unknown-emscripten',
+}
+
+# (Conan arch, Windows ABI) -> Rust target triple
+RUST_WINDOWS_TARGETS = {
+    ('x86_64', 'msvc'): 'x86_64-pc-windows-msvc',
+    ('x86', 'msvc'): 'i686-pc-windows-msvc',
+    ('armv8', 'msvc'): 'aarch64-pc-windows-msvc',
+    ('x86_64', 'gnu'): 'x86_64-pc-windows-gnu',
+    ('x86', 'gnu'): 'i686-pc-windows-gnu',
+}
+
+# Compilers producing MinGW (-gnu) binaries on Windows; everything else
+# (msvc, clang-cl, Intel) uses the MSVC ABI
+WINDOWS_GNU_COMPILERS = ('gcc',)
+
+
+def windows_abi(compiler):
+    """Get the Rust Windows ABI ("msvc" or "gnu") for a Conan compiler."""
+    return 'gnu' if compiler in WINDOWS_GNU_COMPILERS else 'msvc'
+
+
+def rust_target_for_settings(os_name, arch, compiler=None):
+    """
+    Get the Rust target triple for a Conan configuration.
+
+    Examples:
+        ("Linux", "x86_64")             -> "x86_64-unknown-linux-gnu"
+        ("Windows", "x86_64", "msvc")   -> "x86_64-pc-windows-msvc"
+        ("Windows", "x86_64", "gcc")    -> "x86_64-pc-windows-gnu"
+        ("Macos", "armv8")              -> "aarch64-apple-darwin"
+
+    Returns:
+        Target triple, or None if the configuration has no Rust equivalent
+    """
+    if os_name == 'Windows':
+        return RUST_WINDOWS_TARGETS.get((arch, windows_abi(compiler)))
+    return RUST_TARGETS.get((os_name, arch))
+
+
+def conan_settings_for_rust_target(target):
+    """
+    Get the Conan settings for a Rust target triple (the reverse mapping).
+
+    Examples:
+        "x86_64-unknown-linux-gnu" -> {'os': 'Linux', 'arch': 'x86_64'}
+        "x86_64-pc-windows-msvc"   -> {'os': 'Windows', 'arch': 'x86_64', 'compiler': 'msvc'}
+
+    Returns:
+        Dict with 'os', 'arch' (and 'compiler' for Windows), or None if unknown
+    """
+    for (os_name, arch), rust_target in RUST_TARGETS.items():
+        if rust_target == target:
+            return {'os': os_name, 'arch': arch}
+    for (arch, abi), rust_target in RUST_WINDOWS_TARGETS.items():
+        if rust_target == target:
+            return {'os': 'Windows', 'arch': arch, 'compiler': 'gcc' if abi == 'gnu' else 'msvc'}
+    return None
+
+
+def is_known_rust_target(target):
+    """Check if a target triple is one ConanCrates can map to Conan settings."""
+    return conan_settings_for_rust_target(target) is not None
+
+
+def rust_target_for_binary(binary):
+    """
+    Get the Rust target triple of a BinaryPackage (None if unknown).
+
+    Uses the triple recorded at upload, falling back to the mapping for
+    binaries without one.
+    """
+    if binary.rust_target:
+        return binary.rust_target
+    return rust_target_for_settings(binary.os, binary.arch, binary.compiler)
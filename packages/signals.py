@@ -33,7 +33,7 @@ def delete_binary_files(sender, instance, **kwargs):
 @receiver(pre_delete, sender=PackageVersion)
 def delete_package_version_files(sender, instance, **kwargs):
     """
-    Delete recipe_file from MinIO when PackageVersion is deleted.
+    Delete recipe_file and rust_crate_file from MinIO when PackageVersion is deleted.
     """
     if instance.recipe_file:
         filename = instance.recipe_file.name
@@ -42,3 +42,12 @@ def delete_package_version_files(sender, instance, **kwargs):
             print(f"✓ Deleted recipe file from MinIO: {filename}")
         except Exception as e:
             print(f"✗ Error deleting recipe file {filename}: {e}")
+
+    # Delete merged rust crate file if it exists
+    if instance.rust_crate_file:
+        filename = instance.rust_crate_file.name
+        try:
+            instance.rust_crate_file.delete(save=False)
+            print(f"✓ Deleted rust crate file from MinIO: {filename}")
+        except Exception as e:
+            print(f"✗ Error deleting rust crate file {filename}: {e}")
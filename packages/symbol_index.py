@@ -0,0 +1,242 @@
+"""
+Symbol and header index of the uploaded binaries
+
+When a link fails with "undefined reference to `SSL_CTX_new`", or a build
+with "zlib.h: No such file", the question is which package provides it.
+After upload, the binary tarball is unpacked and every symbol its libraries
+export, and every header of its include/ folder, is recorded as a
+BinarySymbol; /symbols/ and /api/symbols search them.
+
+Libraries are read with the CLI's readers (library_symbols() in
+conancrates/conancrates.py): the index of static and import libraries, and
+the exports of ELF and Mach-O shared libraries. Symbols are stored as the
+linker sees them: Mach-O names keep their leading underscore and C++ names
+stay mangled.
+
+Indexing runs in a background thread (SYMBOL_INDEXING setting, like crate
+generation) and from the index_symbols management command.
+"""
+import tarfile
+import tempfile
+import threading
+from pathlib import Path
+
+from django.conf import settings
+from django.db import connection, transaction
+from django.db.models import Q
+
+from packages.crate_generator import GenerationError, find_package_folder
+from packages.models import BinaryPackage, BinarySymbol
+
+
+# Longest name stored: longer ones are mangled C++ templates nobody searches for
+MAX_NAME_LENGTH = 1024
+
+
+class IndexingError(Exception):
+    """Exception raised when the symbols of a binary can't be indexed"""
+    pass
+
+
+def is_import_stub(symbol):
+    """
+    Check if a symbol is one of the entries a Windows import library adds
+    for each DLL export (__imp_<name>) or for the DLL itself.
+    """
+    return (
+        symbol.startswith(('__imp_', '__IMPORT_DESCRIPTOR_'))
+        or symbol == '__NULL_IMPORT_DESCRIPTOR'
+        or symbol.endswith('_NULL_THUNK_DATA')
+    )
+
+
+def package_symbols(package_folder):
+    """
+    Read the symbols and headers of an extracted package folder.
+
+    Returns:
+        Sorted list of (kind, name, library) tuples
+    """
+    # Imported here: the CLI module is only needed to read the binaries
+    from conancrates.conancrates import find_headers, find_libraries, library_symbols
+
+    package_folder = Path(package_folder)
+    entries = set()
+    libraries, _ = find_libraries(package_folder)
+    for _, lib_file, _ in libraries:
+        for symbol in library_symbols(lib_file, functions_only=False):
+            if symbol and not is_import_stub(symbol) and len(symbol) <= MAX_NAME_LENGTH:
+                entries.add(('symbol', symbol, lib_file.name))
+
+    include_dir = package_folder / 'include'
+    for header in find_headers(include_dir):
+        path = header.relative_to(include_dir).as_posix()
+        if len(path) <= MAX_NAME_LENGTH:
+            entries.add(('header', path, ''))
+    return sorted(entries)
+
+
+def header_basename(path):
+    """Last component of a header path ("openssl/ssl.h" -> "ssl.h")."""
+    return path.rsplit('/', 1)[-1]
+
+
+def index_binary(binary):
+    """
+    Replace the indexed symbols and headers of a binary with those of its tarball.
+
+    Returns:
+        Tuple of (symbol count, header count)
+
+    Raises:
+        IndexingError: If the binary has no tarball, or it can't be extracted
+    """
+    if not binary.binary_file:
+        raise IndexingError(f"Binary {binary.package_id} has no uploaded tarball")
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        extract_dir = Path(tmpdir) / 'package'
+        binary.binary_file.open('rb')
+        try:
+            with tarfile.open(fileobj=binary.binary_file, mode='r:*') as tar:
+                # Uploads are untrusted: refuse links and paths outside extract_dir
+                tar.extractall(extract_dir, filter='data')
+        except (tarfile.TarError, OSError) as e:
+            raise IndexingError(f"Could not extract the binary tarball: {e}")
+        finally:
+            binary.binary_file.close()
+
+        try:
+            package_folder, _ = find_package_folder(extract_dir)
+        except GenerationError as e:
+            raise IndexingError(str(e))
+        entries = package_symbols(package_folder)
+
+    with transaction.atomic():
+        binary.symbols.all().delete()
+        BinarySymbol.objects.bulk_create(
+            [
+                BinarySymbol(binary=binary, kind=kind, name=name, library=library,
+                             basename=header_basename(name) if kind == 'header' else '')
+                for kind, name, library in entries
+            ],
+            batch_size=1000,
+        )
+    headers = sum(1 for kind, _, _ in entries if kind == 'header')
+    return len(entries) - headers, headers
+
+
+def index_binary_by_id(binary_id):
+    """
+    Index a binary's symbols.
+
+    Errors are logged, not raised: this runs after the upload has been answered.
+    """
+    binary = BinaryPackage.objects.select_related('package_version__package').filter(pk=binary_id).first()
+    if binary is None:
+        return
+    try:
+        symbols, headers = index_binary(binary)
+        print(f"✓ Indexed {symbols} symbols and {headers} headers of {binary.package_version}:{binary.package_id}")
+    except IndexingError as e:
+        print(f"✗ Error indexing symbols of {binary.package_version}:{binary.package_id}: {e}")
+
+
+def run_in_background(binary_id):
+    """Thread target: index the binary, then release the thread's database connection."""
+    try:
+        index_binary_by_id(binary_id)
+    finally:
+        connection.close()
+
+
+def schedule_symbol_indexing(binary):
+    """
+    Index the symbols and headers of an uploaded binary.
+
+    The SYMBOL_INDEXING setting selects how:
+    - "background" (default): in a thread started once the upload is committed
+    - "inline": before returning (tests, single-process deployments)
+    - "off": not at all (use the index_symbols management command)
+    """
+    if not binary.binary_file:
+        return
+
+    mode = getattr(settings, 'SYMBOL_INDEXING', 'background')
+    if mode == 'inline':
+        index_binary_by_id(binary.pk)
+    elif mode == 'background':
+        binary_id = binary.pk
+        transaction.on_commit(
+            lambda: threading.Thread(target=run_in_background, args=(binary_id,), daemon=True).start()
+        )
+
+
+def header_path(query):
+    """
+    Get the header path of a search like "#include <zlib.h>", "<zlib.h>" or
+    "openssl/ssl.h", or None if the query doesn't look like a header.
+    """
+    # Imported here: the CLI module is only needed for its header extensions
+    from conancrates.conancrates import HEADER_EXTENSIONS
+
+    query = query.strip()
+    if query.startswith('#'):
+        query = query.lstrip('#').strip()
+        query = query[len('include'):].strip() if query.startswith('include') else query
+    quoted = query[:1] in ('<', '"')
+    path = query.strip('<>"').strip()
+    if quoted or '/' in path or Path(path).suffix.lower() in HEADER_EXTENSIONS:
+        return path
+    return None
+
+
+def symbol_variants(symbol):
+    """
+    Names a C symbol may have in a library: Mach-O and 32-bit Windows add an
+    underscore, so searching "SSL_CTX_new" also finds "_SSL_CTX_new" and the
+    other way round.
+    """
+    variants = {symbol, f"_{symbol}"}
+    if symbol.startswith('_'):
+        variants.add(symbol[1:])
+    return variants
+
+
+def search_symbols(query, kind=None, os='', arch=''):
+    """
+    Find the binaries providing a symbol or a header.
+
+    Symbols match exactly (with or without the leading underscore), headers by
+    path or path suffix: "ssl.h" finds openssl/ssl.h.
+
+    Args:
+        query: Symbol name or header (e.g. "SSL_CTX_new", "<zlib.h>")
+        kind: "symbol" or "header", guessed from the query when not given
+        os: Only binaries for this OS (e.g. "Linux")
+        arch: Only binaries for this architecture (e.g. "x86_64")
+
+    Returns:
+        Tuple of (kind, queryset of BinarySymbol)
+    """
+    path = header_path(query)
+    if kind is None:
+        kind = 'header' if path else 'symbol'
+
+    if kind == 'header':
+        path = path or query.strip().strip('<>"')
+        # The indexed basename narrows suffix matches down before the LIKE
+        suffix = Q(basename=header_basename(path), name__endswith=f"/{path}")
+        matches = BinarySymbol.objects.filter(Q(name=path) | suffix, kind='header')
+    else:
+        matches = BinarySymbol.objects.filter(kind='symbol', name__in=symbol_variants(query.strip()))
+
+    if os:
+        matches = matches.filter(binary__os__iexact=os)
+    if arch:
+        matches = matches.filter(binary__arch__iexact=arch)
+    matches = matches.select_related('binary__package_version__package').order_by(
+        'binary__package_version__package__name', '-binary__package_version__created_at',
+        'binary__os', 'binary__arch', 'binary__package_id', 'name', 'library',
+    )
+    return kind, matches
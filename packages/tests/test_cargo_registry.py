@@ -0,0 +1,691 @@
+"""
+Tests for the Cargo registry (sparse index, publish and download API, merged crates).
+"""
+from django.contrib.auth.models import User
+from django.test import TestCase, Client
+from django.urls import reverse
+from django.core.files.uploadedfile import SimpleUploadedFile
+from packages.models import Package, PackageVersion, BinaryPackage
+from packages.cargo_registry import (
+    sparse_index_path, crate_name_for_package, find_package_for_crate, build_crate_archive, create_cargo_token,
+)
+from packages.crate_merge import merge_rust_crates, option_feature
+import hashlib
+import io
+import json
+import struct
+import tarfile
+
+
+class SparseIndexPathTests(TestCase):
+    """Test crate name to index path mapping"""
+
+    def test_index_paths(self):
+        """Test the 1/2/3/xx directory layout"""
+        self.assertEqual(sparse_index_path('a'), '1/a')
+        self.assertEqual(sparse_index_path('ab'), '2/ab')
+        self.assertEqual(sparse_index_path('abc'), '3/a/abc')
+        self.assertEqual(sparse_index_path('zlib-sys'), 'zl/ib/zlib-sys')
+
+    def test_index_path_is_lowercase(self):
+        """Test that index paths are lowercased"""
+        self.assertEqual(sparse_index_path('OpenSSL-sys'), 'op/en/openssl-sys')
+
+    def test_crate_name_for_package(self):
+        """Test -sys crate naming"""
+        self.assertEqual(crate_name_for_package('zlib'), 'zlib-sys')
+        self.assertEqual(crate_name_for_package('my_lib'), 'my-lib-sys')
+
+
+class SparseIndexTests(TestCase):
+    """Test sparse index endpoints"""
+
+    def setUp(self):
+        self.client = Client()
+        self.crate_data = b'fake crate data'
+
+        self.package = Package.objects.create(name='test_lib')
+        self.version = PackageVersion.objects.create(package=self.package, version='1.0.0')
+        self.binary = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='abc123',
+            os='Linux',
+            arch='x86_64',
+            dependency_graph={
+                'graph': {
+                    'nodes': {
+                        '0': {'ref': 'test_lib/1.0.0'},
+                        '1': {'ref': 'dep_lib/2.0.0#hash', 'package_id': 'def456'}
+                    }
+                }
+            },
+            rust_crate_file=SimpleUploadedFile('test-lib-sys-1.0.0.crate', self.crate_data)
+        )
+
+    def test_config_json(self):
+        """Test config.json points dl and api at this server"""
+        response = self.client.get(reverse('packages:cargo_index_config'))
+        self.assertEqual(response.status_code, 200)
+
+        config = json.loads(response.content)
+        self.assertEqual(config['dl'], 'http://testserver/api/v1/crates')
+        self.assertEqual(config['api'], 'http://testserver')
+
+    def test_index_file(self):
+        """Test index file contains one entry per version with cksum and deps"""
+        url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        response = self.client.get(url)
+        self.assertEqual(response.status_code, 200)
+
+        lines = response.content.decode('utf-8').strip().split('\n')
+        self.assertEqual(len(lines), 1)
+
+        entry = json.loads(lines[0])
+        self.assertEqual(entry['name'], 'test-lib-sys')
+        self.assertEqual(entry['vers'], '1.0.0')
+        self.assertEqual(entry['cksum'], hashlib.sha256(self.crate_data).hexdigest())
+        self.assertEqual(entry['links'], 'test_lib')
+        self.assertFalse(entry['yanked'])
+        self.assertEqual(len(entry['deps']), 1)
+        self.assertEqual(entry['deps'][0]['name'], 'dep-lib-sys')
+        self.assertEqual(entry['deps'][0]['req'], '^2.0.0')
+        self.assertEqual(entry['deps'][0]['kind'], 'normal')
+
+    def test_index_records_checksum(self):
+        """Test that the computed checksum is stored on the binary"""
+        url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        self.client.get(url)
+
+        self.binary.refresh_from_db()
+        self.assertEqual(self.binary.rust_crate_sha256, hashlib.sha256(self.crate_data).hexdigest())
+
+    def test_index_skips_versions_without_crate(self):
+        """Test that versions without a Rust crate are not listed"""
+        version2 = PackageVersion.objects.create(package=self.package, version='2.0.0')
+        BinaryPackage.objects.create(package_version=version2, package_id='nocrate')
+
+        url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        response = self.client.get(url)
+
+        lines = response.content.decode('utf-8').strip().split('\n')
+        self.assertEqual(len(lines), 1)
+
+    def test_index_header_only(self):
+        """Test header-only crates have no links and are build dependencies of their dependents"""
+        self.binary.dependency_graph['graph']['nodes']['1']['package_type'] = 'header-library'
+        self.binary.save()
+        json_lib = Package.objects.create(name='json_lib')
+        crate_bytes = build_crate_archive('json-lib-sys-3.11.2', {
+            'Cargo.toml': b'[package]\nname = "json-lib-sys"\nversion = "3.11.2"\n',
+        })
+        header_only = BinaryPackage.objects.create(
+            package_version=PackageVersion.objects.create(package=json_lib, version='3.11.2'),
+            package_id='header',
+            rust_crate_file=SimpleUploadedFile('json-lib-sys-3.11.2.crate', crate_bytes)
+        )
+
+        url = reverse('packages:cargo_index_file', kwargs={'index_path': 'js/on/json-lib-sys'})
+        self.assertIsNone(json.loads(self.client.get(url).content)['links'])
+        header_only.refresh_from_db()
+        self.assertTrue(header_only.rust_crate_header_only)
+
+        url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        entry = json.loads(self.client.get(url).content)
+        self.assertEqual(entry['links'], 'test_lib')
+        self.assertEqual([(dep['name'], dep['kind']) for dep in entry['deps']],
+                         [('dep-lib-sys', 'normal'), ('dep-lib-sys', 'build')])
+
+    def test_index_file_unknown_crate(self):
+        """Test 404 for crates that don't exist"""
+        url = reverse('packages:cargo_index_file', kwargs={'index_path': 'no/ne/none-sys'})
+        response = self.client.get(url)
+        self.assertEqual(response.status_code, 404)
+
+    def test_index_file_wrong_prefix(self):
+        """Test 404 when the path doesn't match the index layout"""
+        url = reverse('packages:cargo_index_file', kwargs={'index_path': 'xx/yy/test-lib-sys'})
+        response = self.client.get(url)
+        self.assertEqual(response.status_code, 404)
+
+    def test_find_package_for_crate(self):
+        """Test crate names resolve back to the Conan package"""
+        self.assertEqual(find_package_for_crate('test-lib-sys'), self.package)
+        self.assertEqual(find_package_for_crate('Test-Lib-Sys'), self.package)
+        self.assertIsNone(find_package_for_crate('other-sys'))
+
+
+def make_crate(name, version, files=None):
+    """Build an in-memory .crate archive with a {name}-{version}/ root"""
+    files = files or {'Cargo.toml': f'[package]\nname = "{name}"\nversion = "{version}"\n'}
+    buffer = io.BytesIO()
+    with tarfile.open(fileobj=buffer, mode='w:gz') as tar:
+        for path, content in files.items():
+            data = content.encode('utf-8')
+            info = tarfile.TarInfo(f"{name}-{version}/{path}")
+            info.size = len(data)
+            tar.addfile(info, io.BytesIO(data))
+    return buffer.getvalue()
+
+
+def make_publish_body(metadata, crate_bytes):
+    """Build a cargo publish request body"""
+    metadata_bytes = json.dumps(metadata).encode('utf-8')
+    return (struct.pack('<I', len(metadata_bytes)) + metadata_bytes +
+            struct.pack('<I', len(crate_bytes)) + crate_bytes)
+
+
+class CargoPublishTests(TestCase):
+    """Test the cargo publish endpoint"""
+
+    def setUp(self):
+        self.client = Client()
+        self.url = reverse('packages:cargo_publish')
+
+        # Conan package uploaded with --no-rust (binary without crate)
+        self.uploader = User.objects.create_user('uploader')
+        self.package = Package.objects.create(name='zlib')
+        self.version = PackageVersion.objects.create(package=self.package, version='1.2.13', uploaded_by=self.uploader)
+        self.binary = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='zlib123',
+            os='Linux',
+            arch='x86_64'
+        )
+        self.uploader_token = create_cargo_token(self.uploader)
+        self.token = create_cargo_token(User.objects.create_user('publisher'), 'CI')
+
+    def publish(self, metadata, crate_bytes=None, token=None):
+        if crate_bytes is None:
+            crate_bytes = make_crate(metadata['name'], metadata['vers'])
+        return self.client.put(
+            self.url,
+            data=make_publish_body(metadata, crate_bytes),
+            content_type='application/octet-stream',
+            HTTP_AUTHORIZATION=token or self.token
+        )
+
+    def test_publish_wrapper_crate(self):
+        """Test publishing a wrapper crate creates a source-only binary"""
+        crate_bytes = make_crate('zlib-safe', '0.1.0', {
+            'Cargo.toml': '[package]\nname = "zlib-safe"\nversion = "0.1.0"\n\n'
+                          '[dependencies.zlib-sys]\nversion = "1.2.13"\n',
+        })
+        response = self.publish({
+            'name': 'zlib-safe',
+            'vers': '0.1.0',
+            'description': 'Safe zlib wrapper',
+            'deps': [{
+                'name': 'zlib-sys',
+                'version_req': '^1.2.13',
+                'features': [],
+                'optional': False,
+                'default_features': True,
+                'target': None,
+                'kind': 'normal'
+            }],
+            'features': {}
+        }, crate_bytes)
+
+        self.assertEqual(response.status_code, 200)
+        self.assertIn('warnings', json.loads(response.content))
+
+        package = Package.objects.get(name='cargo:zlib-safe')
+        binary = BinaryPackage.objects.get(package_version__package=package)
+        self.assertEqual(binary.rust_crate_sha256, hashlib.sha256(crate_bytes).hexdigest())
+        self.assertEqual(binary.os, '')
+
+        # The published crate is served by the index with its own deps
+        url = reverse('packages:cargo_index_file', kwargs={'index_path': 'zl/ib/zlib-safe'})
+        entry = json.loads(self.client.get(url).content)
+        self.assertEqual(entry['name'], 'zlib-safe')
+        self.assertEqual(entry['deps'][0]['name'], 'zlib-sys')
+        self.assertEqual(entry['deps'][0]['req'], '^1.2.13')
+
+    def test_publish_wrapper_named_like_conan_package(self):
+        """Test a crate named like a Conan package gets its own package"""
+        response = self.publish({'name': 'zlib', 'vers': '1.2.13', 'deps': [], 'features': {}})
+
+        self.assertEqual(response.status_code, 200)
+        self.assertFalse(self.version.binaries.exclude(rust_crate_file='').exists())
+        package = find_package_for_crate('zlib')
+        self.assertEqual(package.name, 'cargo:zlib')
+        self.assertEqual(BinaryPackage.objects.get(package_version__package=package).rust_crate_metadata['name'], 'zlib')
+        self.assertEqual(find_package_for_crate('zlib-sys'), self.package)
+
+    def test_publish_requires_token(self):
+        """Test publishing without a known token is refused"""
+        metadata = {'name': 'mycrate', 'vers': '1.0.0', 'deps': [], 'features': {}}
+        response = self.client.put(self.url, data=make_publish_body(metadata, make_crate('mycrate', '1.0.0')),
+                                   content_type='application/octet-stream')
+        self.assertEqual(response.status_code, 401)
+        self.assertIn('cargo login', json.loads(response.content)['errors'][0]['detail'])
+
+        response = self.publish(metadata, token='ccr_unknown')
+        self.assertEqual(response.status_code, 401)
+        self.assertFalse(Package.objects.filter(name='cargo:mycrate').exists())
+
+    def test_publish_sys_crate_requires_owner(self):
+        """Test the -sys crate of a Conan package is only published by its uploaders"""
+        metadata = {'name': 'zlib-sys', 'vers': '1.2.13', 'deps': [], 'features': {}}
+        response = self.publish(metadata)
+        self.assertEqual(response.status_code, 403)
+        self.binary.refresh_from_db()
+        self.assertFalse(self.binary.rust_crate_file)
+
+        staff = User.objects.create_user('staff', is_staff=True)
+        response = self.publish(metadata, token=create_cargo_token(staff))
+        self.assertEqual(response.status_code, 200)
+
+    def test_publish_wrapper_requires_first_publisher(self):
+        """Test new versions of a published crate are only published by its publishers"""
+        self.assertEqual(self.publish({'name': 'zlib-safe', 'vers': '0.1.0', 'deps': [], 'features': {}}).status_code, 200)
+
+        metadata = {'name': 'zlib-safe', 'vers': '0.2.0', 'deps': [], 'features': {}}
+        response = self.publish(metadata, token=self.uploader_token)
+        self.assertEqual(response.status_code, 403)
+        self.assertIn('publishers of `zlib-safe`', json.loads(response.content)['errors'][0]['detail'])
+
+        self.assertEqual(self.publish(metadata).status_code, 200)
+        package = Package.objects.get(name='cargo:zlib-safe')
+        self.assertEqual(set(package.versions.values_list('uploaded_by__username', flat=True)), {'publisher'})
+
+    def test_publish_sys_crate_attaches_to_binary(self):
+        """Test publishing a -sys crate attaches it to the Conan binary"""
+        response = self.publish({'name': 'zlib-sys', 'vers': '1.2.13', 'deps': [], 'features': {}},
+                                token=self.uploader_token)
+
+        self.assertEqual(response.status_code, 200)
+        self.binary.refresh_from_db()
+        self.assertTrue(self.binary.rust_crate_file)
+        self.assertEqual(self.version.binaries.count(), 1)
+
+    def test_publish_duplicate_version(self):
+        """Test that an already published version is rejected"""
+        metadata = {'name': 'zlib-sys', 'vers': '1.2.13', 'deps': [], 'features': {}}
+        self.publish(metadata, token=self.uploader_token)
+        response = self.publish(metadata, token=self.uploader_token)
+
+        self.assertEqual(response.status_code, 409)
+        self.assertIn('already uploaded', json.loads(response.content)['errors'][0]['detail'])
+
+    def test_publish_invalid_version(self):
+        """Test that non-semver versions are rejected"""
+        response = self.publish({'name': 'mycrate', 'vers': '1.0', 'deps': [], 'features': {}},
+                                make_crate('mycrate', '1.0'))
+        self.assertEqual(response.status_code, 400)
+        self.assertIn('errors', json.loads(response.content))
+
+    def test_publish_invalid_archive_root(self):
+        """Test that archives without {name}-{version}/Cargo.toml are rejected"""
+        response = self.publish({'name': 'mycrate', 'vers': '1.0.0', 'deps': [], 'features': {}},
+                                make_crate('other', '1.0.0'))
+        self.assertEqual(response.status_code, 400)
+
+    def test_publish_manifest_must_match_metadata(self):
+        """Test the archive's Cargo.toml must declare the metadata's version, links and deps"""
+        crate_bytes = make_crate('mycrate', '1.0.0', {
+            'Cargo.toml': '[package]\nname = "mycrate"\nversion = "1.0.0"\nlinks = "z"\n\n'
+                          '[target.\'cfg(unix)\'.dependencies]\nzlib-sys = "1.2"\n',
+        })
+        dep = {'name': 'zlib-sys', 'version_req': '^1.2', 'features': [], 'optional': False,
+               'default_features': True, 'target': 'cfg(unix)', 'kind': 'normal'}
+
+        response = self.publish({'name': 'mycrate', 'vers': '1.0.0', 'deps': [dep], 'features': {}}, crate_bytes)
+        self.assertEqual(response.status_code, 400)
+        self.assertIn('links', json.loads(response.content)['errors'][0]['detail'])
+
+        response = self.publish({'name': 'mycrate', 'vers': '1.0.0', 'links': 'z', 'deps': [], 'features': {}}, crate_bytes)
+        self.assertEqual(response.status_code, 400)
+        self.assertIn('only in Cargo.toml: zlib-sys ^1.2', json.loads(response.content)['errors'][0]['detail'])
+
+        response = self.publish({'name': 'mycrate', 'vers': '1.0.0', 'links': 'z', 'deps': [dep], 'features': {}}, crate_bytes)
+        self.assertEqual(response.status_code, 200)
+
+    def test_publish_truncated_payload(self):
+        """Test that truncated payloads are rejected"""
+        response = self.client.put(self.url, data=b'\x10\x00', content_type='application/octet-stream')
+        self.assertEqual(response.status_code, 400)
+
+    def test_publish_requires_put(self):
+        """Test that only PUT is allowed"""
+        response = self.client.post(self.url)
+        self.assertEqual(response.status_code, 405)
+
+
+class CargoDownloadTests(TestCase):
+    """Test the Cargo crate download endpoint"""
+
+    def setUp(self):
+        self.client = Client()
+        self.crate_data = b'linux crate data'
+
+        self.package = Package.objects.create(name='test_lib')
+        self.version = PackageVersion.objects.create(package=self.package, version='1.0.0')
+        self.binary = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='linux123',
+            os='Linux',
+            arch='x86_64',
+            rust_crate_file=SimpleUploadedFile('test-lib-sys-1.0.0.crate', self.crate_data)
+        )
+        # Uploaded later: must not replace the crate already in the index
+        BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='windows123',
+            os='Windows',
+            arch='x86_64',
+            rust_crate_file=SimpleUploadedFile('test-lib-sys-1.0.0.crate', b'windows crate data')
+        )
+
+    def download_url(self, crate_name='test-lib-sys', version='1.0.0'):
+        return reverse('packages:cargo_download', kwargs={'crate_name': crate_name, 'version': version})
+
+    def test_download_matches_index_checksum(self):
+        """Test downloaded bytes hash to the cksum published in the index"""
+        index_url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        entry = json.loads(self.client.get(index_url).content)
+
+        response = self.client.get(self.download_url())
+        self.assertEqual(response.status_code, 200)
+        self.assertEqual(response.content, self.crate_data)
+        self.assertEqual(hashlib.sha256(response.content).hexdigest(), entry['cksum'])
+
+    def test_download_increments_count(self):
+        """Test that downloads are counted"""
+        self.client.get(self.download_url())
+
+        self.binary.refresh_from_db()
+        self.package.refresh_from_db()
+        self.assertEqual(self.binary.download_count, 1)
+        self.assertEqual(self.package.download_count, 1)
+
+    def test_download_checksum_mismatch(self):
+        """Test that a stored file not matching the index checksum is refused"""
+        self.binary.rust_crate_sha256 = '0' * 64
+        self.binary.save()
+
+        response = self.client.get(self.download_url())
+        self.assertEqual(response.status_code, 500)
+
+    def test_download_unknown_version(self):
+        """Test 404 for versions that don't exist"""
+        response = self.client.get(self.download_url(version='9.9.9'))
+        self.assertEqual(response.status_code, 404)
+
+    def test_download_unknown_crate(self):
+        """Test 404 for crates that don't exist"""
+        response = self.client.get(self.download_url(crate_name='none-sys'))
+        self.assertEqual(response.status_code, 404)
+
+    def test_download_version_listed_in_index(self):
+        """Test the version served is the one the index lists when Conan versions collide"""
+        # "1.2" and "1.2.0" are both Cargo's 1.2.0: the older one has no crate, so isn't listed
+        PackageVersion.objects.create(package=self.package, version='1.2')
+        newer = PackageVersion.objects.create(package=self.package, version='1.2.0')
+        BinaryPackage.objects.create(
+            package_version=newer,
+            package_id='linux120',
+            rust_crate_file=SimpleUploadedFile('test-lib-sys-1.2.0.crate', b'1.2.0 crate data')
+        )
+
+        index_url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        entries = [json.loads(line) for line in self.client.get(index_url).content.decode().splitlines()]
+        self.assertEqual([entry['vers'] for entry in entries], ['1.0.0', '1.2.0'])
+
+        response = self.client.get(self.download_url(version='1.2.0'))
+        self.assertEqual(response.status_code, 200)
+        self.assertEqual(response.content, b'1.2.0 crate data')
+        self.assertEqual(hashlib.sha256(response.content).hexdigest(), entries[1]['cksum'])
+
+
+def make_generated_crate(crate_name, libs, build_rs='fn main() {}\n'):
+    """Build a .crate archive like the CLI generates: {crate_name}-1.0.0/ root, libraries in native/current/"""
+    files = {
+        'Cargo.toml': f'[package]\nname = "{crate_name}"\n',
+        'build.rs': build_rs,
+        'native/current/libs.txt': ''.join(f"static={lib}\n" for lib in libs),
+    }
+    for lib in libs:
+        files[f'native/current/lib{lib}.a'] = f'{lib} archive'
+    buffer = io.BytesIO()
+    with tarfile.open(fileobj=buffer, mode='w:gz') as tar:
+        for path, content in files.items():
+            data = content.encode('utf-8')
+            info = tarfile.TarInfo(f"{crate_name}-1.0.0/{path}")
+            info.size = len(data)
+            tar.addfile(info, io.BytesIO(data))
+    return buffer.getvalue()
+
+
+class MergeCratesTests(TestCase):
+    """Test merging per-binary crates into one multi-target crate"""
+
+    def setUp(self):
+        self.client = Client()
+        self.package = Package.objects.create(name='test_lib')
+        self.version = PackageVersion.objects.create(package=self.package, version='1.0.0')
+        self.url = reverse('packages:merge_rust_crates', kwargs={'package_name': 'test_lib', 'version': '1.0.0'})
+
+        self.linux = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='linux123',
+            os='Linux',
+            arch='x86_64',
+            compiler='gcc',
+            build_type='Release',
+            rust_crate_file=SimpleUploadedFile('test-lib-sys-1.0.0.crate', make_generated_crate('test-lib-sys', ['test_lib']))
+        )
+        self.windows = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='windows123',
+            os='Windows',
+            arch='x86_64',
+            compiler='msvc',
+            build_type='Release',
+            rust_crate_file=SimpleUploadedFile('test-lib-sys-1.0.0.crate', make_generated_crate('test-lib-sys', ['test_lib']))
+        )
+
+    def merged_names(self):
+        self.version.refresh_from_db()
+        self.version.rust_crate_file.open('rb')
+        try:
+            with tarfile.open(fileobj=io.BytesIO(self.version.rust_crate_file.read()), mode='r:gz') as tar:
+                return tar.getnames()
+        finally:
+            self.version.rust_crate_file.close()
+
+    def test_merge_creates_per_target_directories(self):
+        """Test native/current of each binary becomes native/<target-triple>"""
+        response = self.client.post(self.url)
+
+        self.assertEqual(response.status_code, 200)
+        data = json.loads(response.content)
+        self.assertEqual(data['targets'], ['x86_64-pc-windows-msvc', 'x86_64-unknown-linux-gnu'])
+
+        names = self.merged_names()
+        self.assertIn('test-lib-sys-1.0.0/native/x86_64-unknown-linux-gnu/libs.txt', names)
+        self.assertIn('test-lib-sys-1.0.0/native/x86_64-pc-windows-msvc/libtest_lib.a', names)
+        self.assertIn('test-lib-sys-1.0.0/build.rs', names)
+        self.assertNotIn('test-lib-sys-1.0.0/native/current/libs.txt', names)
+
+    def test_merge_prefers_release_for_same_target(self):
+        """Test that a Debug build for an already merged target is skipped"""
+        BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='linuxdebug123',
+            os='Linux',
+            arch='x86_64',
+            compiler='gcc',
+            build_type='Debug',
+            rust_crate_file=SimpleUploadedFile('test-lib-sys-1.0.0.crate', make_generated_crate('test-lib-sys', ['test_libd']))
+        )
+
+        data = json.loads(self.client.post(self.url).content)
+
+        self.assertEqual(data['skipped_binaries'], ['linuxdebug123'])
+        self.assertNotIn('test-lib-sys-1.0.0/native/x86_64-unknown-linux-gnu/libtest_libd.a', self.merged_names())
+
+    def test_merge_is_reproducible(self):
+        """Test that merging the same crates produces the same archive"""
+        first = merge_rust_crates(self.version)[0]
+        second = merge_rust_crates(self.version)[0]
+        self.assertEqual(first, second)
+
+    def test_merge_accepts_legacy_crate_root(self):
+        """Test that crates with a {crate_name}/ root are merged under {crate_name}-{version}/"""
+        legacy_bytes = io.BytesIO()
+        with tarfile.open(fileobj=legacy_bytes, mode='w:gz') as tar:
+            for path, content in {'Cargo.toml': '', 'native/current/libs.txt': 'static=test_lib\n'}.items():
+                info = tarfile.TarInfo(f"test-lib-sys/{path}")
+                info.size = len(content)
+                tar.addfile(info, io.BytesIO(content.encode('utf-8')))
+        self.linux.rust_crate_file = SimpleUploadedFile('old.crate', legacy_bytes.getvalue())
+        self.linux.save()
+
+        self.assertEqual(self.client.post(self.url).status_code, 200)
+        self.assertIn('test-lib-sys-1.0.0/native/x86_64-unknown-linux-gnu/libs.txt', self.merged_names())
+
+    def test_merged_crate_is_served_by_registry(self):
+        """Test the index checksum and download switch to the merged crate"""
+        data = json.loads(self.client.post(self.url).content)
+
+        index_url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        entry = json.loads(self.client.get(index_url).content)
+        self.assertEqual(entry['cksum'], data['sha256'])
+
+        download_url = reverse('packages:cargo_download', kwargs={'crate_name': 'test-lib-sys', 'version': '1.0.0'})
+        response = self.client.get(download_url)
+        self.assertEqual(hashlib.sha256(response.content).hexdigest(), data['sha256'])
+
+    def test_registry_targets(self):
+        """Test the info API tells which targets the crate served by the index covers"""
+        def registry_targets(package_id):
+            url = reverse('packages:package_info_api', args=['test_lib', '1.0.0', package_id])
+            return json.loads(self.client.get(url).content)['rust_crate']['registry_targets']
+
+        # The oldest upload's crate is served, also for the Windows binary
+        self.assertEqual(registry_targets('windows123'), ['x86_64-unknown-linux-gnu'])
+
+        self.client.post(self.url)
+        self.assertEqual(
+            sorted(registry_targets('windows123')), ['x86_64-pc-windows-msvc', 'x86_64-unknown-linux-gnu']
+        )
+
+    def test_merge_refused_once_indexed(self):
+        """Test the checksum Cargo got from the index never changes"""
+        index_url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        cksum = json.loads(self.client.get(index_url).content)['cksum']
+
+        response = self.client.post(self.url)
+
+        self.assertEqual(response.status_code, 409)
+        self.assertIn('Cargo.lock', json.loads(response.content)['error'])
+        self.assertEqual(json.loads(self.client.get(index_url).content)['cksum'], cksum)
+        self.version.refresh_from_db()
+        self.assertFalse(self.version.rust_crate_file)
+
+    def test_merge_refused_once_downloaded(self):
+        """Test a per-binary crate downloaded by Cargo can't be replaced by a merge"""
+        download_url = reverse('packages:cargo_download', kwargs={'crate_name': 'test-lib-sys', 'version': '1.0.0'})
+        self.assertEqual(self.client.get(download_url).status_code, 200)
+
+        self.assertEqual(self.client.post(self.url).status_code, 409)
+
+    def test_merge_twice_is_refused(self):
+        """Test that a merged crate version is immutable"""
+        self.client.post(self.url)
+        response = self.client.post(self.url)
+        self.assertEqual(response.status_code, 409)
+
+    def test_merge_requires_link_manifest(self):
+        """Test that crates generated without libs.txt can't be merged"""
+        crate_bytes = make_crate('test-lib-sys', '1.0.0', {'Cargo.toml': '', 'native/current/libtest_lib.a': 'x'})
+        self.linux.rust_crate_file = SimpleUploadedFile('old.crate', crate_bytes)
+        self.linux.save()
+
+        response = self.client.post(self.url)
+
+        self.assertEqual(response.status_code, 400)
+        self.assertIn('libs.txt', json.loads(response.content)['error'])
+
+    def test_merge_requires_same_headers(self):
+        """Test that crates with per-platform headers aren't merged"""
+        for binary, config in ((self.linux, '#define HAVE_UNISTD_H 1\n'), (self.windows, '#define HAVE_IO_H 1\n')):
+            binary.rust_crate_file = SimpleUploadedFile('test-lib-sys-1.0.0.crate', make_crate('test-lib-sys', '1.0.0', {
+                'Cargo.toml': '[package]\nname = "test-lib-sys"\n',
+                'native/current/libs.txt': 'static=test_lib\n',
+                'include/test_lib.h': '#include "config.h"\n',
+                'include/config.h': config,
+            }))
+            binary.save()
+
+        response = self.client.post(self.url)
+
+        self.assertEqual(response.status_code, 400)
+        self.assertIn('(include/config.h)', json.loads(response.content)['error'])
+        self.version.refresh_from_db()
+        self.assertFalse(self.version.rust_crate_file)
+
+    def add_shared_variant(self, build_rs='// CARGO_FEATURE_<NAME> selects the variant\nfn main() {}\n'):
+        """Add a Linux binary built with shared=True (the existing binaries have no options)"""
+        for binary in (self.linux, self.windows):
+            binary.rust_crate_file = SimpleUploadedFile(
+                'test-lib-sys-1.0.0.crate', make_generated_crate('test-lib-sys', ['test_lib'], build_rs)
+            )
+            binary.save()
+        return BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='linuxshared123',
+            os='Linux',
+            arch='x86_64',
+            compiler='gcc',
+            build_type='Release',
+            options={'shared': 'True'},
+            rust_crate_file=SimpleUploadedFile(
+                'test-lib-sys-1.0.0.crate', make_generated_crate('test-lib-sys', ['test_lib_shared'], build_rs)
+            )
+        )
+
+    def test_option_variants_become_features(self):
+        """Test that binaries differing in options get a feature and a native/<triple>+<feature> directory"""
+        self.add_shared_variant()
+
+        response = self.client.post(self.url)
+
+        self.assertEqual(response.status_code, 200)
+        data = json.loads(response.content)
+        self.assertEqual(data['features'], {'shared': {'shared': 'True'}})
+        self.assertEqual(data['skipped_binaries'], [])
+        self.assertEqual(data['targets'], ['x86_64-pc-windows-msvc', 'x86_64-unknown-linux-gnu'])
+
+        names = self.merged_names()
+        self.assertIn('test-lib-sys-1.0.0/native/x86_64-unknown-linux-gnu/libtest_lib.a', names)
+        self.assertIn('test-lib-sys-1.0.0/native/x86_64-unknown-linux-gnu+shared/libtest_lib_shared.a', names)
+
+        self.version.rust_crate_file.open('rb')
+        try:
+            with tarfile.open(fileobj=io.BytesIO(self.version.rust_crate_file.read()), mode='r:gz') as tar:
+                cargo_toml = tar.extractfile('test-lib-sys-1.0.0/Cargo.toml').read().decode()
+        finally:
+            self.version.rust_crate_file.close()
+        self.assertIn('[features]\nshared = []\n', cargo_toml)
+
+        index_url = reverse('packages:cargo_index_file', kwargs={'index_path': 'te/st/test-lib-sys'})
+        entry = json.loads(self.client.get(index_url).content)
+        self.assertEqual(entry['features'], {'shared': []})
+
+    def test_option_variants_require_feature_selection(self):
+        """Test that crates whose build.rs can't select option variants aren't merged"""
+        self.add_shared_variant(build_rs='fn main() {}\n')
+
+        response = self.client.post(self.url)
+
+        self.assertEqual(response.status_code, 400)
+        self.assertIn('options', json.loads(response.content)['error'])
+
+    def test_option_feature_names(self):
+        """Test Cargo feature names for Conan option values"""
+        self.assertEqual(option_feature('shared', 'True'), 'shared')
+        self.assertEqual(option_feature('shared', 'False'), 'shared-false')
+        self.assertEqual(option_feature('with_ssl', 'openssl'), 'with_ssl-openssl')
+        self.assertEqual(option_feature('cxx_std', 'C++17'), 'cxx_std-c-17')
@@ -0,0 +1,222 @@
+"""
+Tests for translating Conan versions into Cargo versions.
+"""
+from django.test import TestCase, Client
+from django.urls import reverse
+from django.core.files.uploadedfile import SimpleUploadedFile
+from packages.models import Package, PackageVersion, BinaryPackage, Dependency
+from packages.cargo_versions import (
+    SEMVER_RE,
+    cargo_version,
+    version_requirement,
+    cargo_version_for,
+    cargo_requirement,
+)
+from packages.views.simple_upload import parse_conanfile
+import conancrates.conancrates as cli
+import json
+
+
+class CargoVersionTests(TestCase):
+    """Test the Conan to Cargo version translation"""
+
+    def test_semver_is_unchanged(self):
+        """Test versions that are already semver"""
+        self.assertEqual(cargo_version('1.2.3'), '1.2.3')
+        self.assertEqual(cargo_version('1.0.0-rc.1'), '1.0.0-rc.1')
+        self.assertEqual(cargo_version('1.81.0+build'), '1.81.0+build')
+
+    def test_short_versions_are_padded(self):
+        """Test missing minor and patch components become zero"""
+        self.assertEqual(cargo_version('3.0'), '3.0.0')
+        self.assertEqual(cargo_version('7'), '7.0.0')
+        self.assertEqual(cargo_version('2023.01.05'), '2023.1.5')
+
+    def test_extra_components_become_build_metadata(self):
+        """Test components past major.minor.patch are kept as build metadata"""
+        self.assertEqual(cargo_version('1.2.13.1'), '1.2.13+1')
+        self.assertEqual(cargo_version('1.1.1w'), '1.1.1+w')
+        self.assertEqual(cargo_version('cci.20230101'), '20230101.0.0+cci')
+        self.assertEqual(cargo_version('1.81+b_x'), '1.81.0+b-x')
+        self.assertEqual(cargo_version('system'), '0.0.0+system')
+
+    def test_prerelease(self):
+        """Test Conan pre-releases stay pre-releases"""
+        self.assertEqual(cargo_version('2.0-rc1'), '2.0.0-rc1')
+        self.assertEqual(cargo_version('1.0-rc.01'), '1.0.0-rc.1')
+
+    def test_results_are_semver(self):
+        """Test every translation is accepted by Cargo"""
+        for version in ['1.2.13.1', '3.0', 'cci.20230101', '1.81.0+build', 'v2.1', '1.2.3.4.5', '1.0-beta_2']:
+            self.assertRegex(cargo_version(version), SEMVER_RE)
+
+    def test_version_requirement(self):
+        """Test build metadata is dropped from requirements"""
+        self.assertEqual(version_requirement('1.2.13+1'), '1.2.13')
+        self.assertEqual(version_requirement('3.0.0'), '3.0.0')
+
+    def test_cargo_version_for_falls_back_to_translation(self):
+        """Test package versions without a recorded translation"""
+        package = Package.objects.create(name='zlib')
+        recorded = PackageVersion.objects.create(package=package, version='1.3', cargo_version='1.3.0')
+        unrecorded = PackageVersion.objects.create(package=package, version='1.2.13.1')
+        self.assertEqual(cargo_version_for(recorded), '1.3.0')
+        self.assertEqual(cargo_version_for(unrecorded), '1.2.13+1')
+
+
+class CargoRequirementTests(TestCase):
+    """Test Conan requirements are translated into Cargo requirements"""
+
+    def test_plain_versions_are_caret_requirements(self):
+        """Test exact Conan versions become Cargo's default caret requirement"""
+        self.assertEqual(cargo_requirement('1.2.13'), '^1.2.13')
+        self.assertEqual(cargo_requirement('3.0@user/channel#rev'), '^3.0.0')
+        self.assertEqual(cargo_requirement('1.2.13.1'), '^1.2.13')
+
+    def test_ranges(self):
+        """Test range conditions become comma separated comparators"""
+        self.assertEqual(cargo_requirement('[>=1.2 <2]'), '>=1.2.0, <2.0.0')
+        self.assertEqual(cargo_requirement('[>1.2 <=1.4]'), '>1.2.0, <=1.4.0')
+        self.assertEqual(cargo_requirement('[1.2.13]'), '=1.2.13')
+        self.assertEqual(cargo_requirement('[*]'), '*')
+
+    def test_tilde_and_caret_keep_partial_versions(self):
+        """Test ~ and ^ aren't padded, since Cargo reads ~1 and ~1.0.0 differently"""
+        self.assertEqual(cargo_requirement('[~1]'), '~1')
+        self.assertEqual(cargo_requirement('[~1.2]'), '~1.2')
+        self.assertEqual(cargo_requirement('[^0.2]'), '^0.2')
+
+    def test_range_options_are_ignored(self):
+        """Test options after the comma (include_prerelease, loose) are dropped"""
+        self.assertEqual(cargo_requirement('[~3.0, include_prerelease]'), '~3.0')
+
+    def test_alternatives_use_resolved_version(self):
+        """Test the || alternative containing the resolved version is used"""
+        self.assertEqual(cargo_requirement('[>=1 <2 || >=3 <4]', '1.5'), '>=1.0.0, <2.0.0')
+        self.assertEqual(cargo_requirement('[>=1 <2 || >=3 <4]', '3.1'), '>=3.0.0, <4.0.0')
+
+    def test_unmatched_range_falls_back_to_resolved_version(self):
+        """Test a range that can't match the resolved version still resolves"""
+        self.assertEqual(cargo_requirement('[^0.2]', '0.3'), '^0.3.0')
+
+
+class CliParityTests(TestCase):
+    """Test the server serves the versions the CLI writes into generated crates"""
+
+    VERSIONS = [
+        '1.2.3', '1.0.0-rc.1', '1.81.0+build', '3.0', '7', '2023.01.05', '1.2.13.1', '1.1.1w',
+        'cci.20230101', '1.81+b_x', 'system', '2.0-rc1', '1.0-rc.01', 'v2.1', '1.2.3.4.5', '1.0-beta_2',
+    ]
+    REQUIREMENTS = [
+        ('1.2.13', None), ('3.0@user/channel#rev', None), ('1.2.13.1', None), ('', '1.2'), ('', None),
+        ('[>=1.2 <2]', None), ('[>1.2 <=1.4]', None), ('[1.2.13]', None), ('[*]', None),
+        ('[~1]', None), ('[~1.2]', None), ('[^0.2]', None), ('[~3.0, include_prerelease]', None),
+        ('[>=1 <2 || >=3 <4]', '1.5'), ('[>=1 <2 || >=3 <4]', '3.1'), ('[^0.2]', '0.3'),
+        ('[>=1.2 <2]', 'cci.20230101'), ('[~1.2.13.1]', '1.2.13.1'),
+    ]
+
+    def test_cargo_version_matches_cli(self):
+        """Test cargo_version() and version_requirement() agree with the CLI's copies"""
+        for version in self.VERSIONS:
+            with self.subTest(version=version):
+                self.assertEqual(cargo_version(version), cli.cargo_version(version))
+                self.assertEqual(version_requirement(cargo_version(version)),
+                                 cli.version_requirement(cli.cargo_version(version)))
+
+    def test_cargo_requirement_matches_cli(self):
+        """Test cargo_requirement() agrees with the CLI's copy"""
+        for requirement, resolved in self.REQUIREMENTS:
+            with self.subTest(requirement=requirement, resolved=resolved):
+                self.assertEqual(cargo_requirement(requirement, resolved), cli.cargo_requirement(requirement, resolved))
+
+class ParseConanfileRequiresTests(TestCase):
+    """Test requirements are read from recipes"""
+
+    def test_requires_attribute_and_method(self):
+        """Test ranges in requires = [...] and self.requires() calls"""
+        recipe = (
+            'class App(ConanFile):\n'
+            '    requires = ["zlib/[>=1.2 <2]",\n'
+            '                "fmt/10.0.0"]\n'
+            '    tool_requires = ["cmake/3.27"]\n'
+            '\n'
+            '    def requirements(self):\n'
+            '        self.requires("openssl/[~3.0]@user/channel")\n'
+        )
+        self.assertEqual(
+            parse_conanfile(recipe)['dependencies'],
+            ['zlib/[>=1.2 <2]', 'fmt/10.0.0', 'openssl/[~3.0]@user/channel']
+        )
+
+
+class CargoVersionRegistryTests(TestCase):
+    """Test the registry serves translated versions"""
+
+    def setUp(self):
+        self.client = Client()
+        self.package = Package.objects.create(name='zlib')
+        self.version = PackageVersion.objects.create(
+            package=self.package, version='1.2.13.1', cargo_version='1.2.13+1'
+        )
+        BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='abc123',
+            dependency_graph={
+                'graph': {
+                    'nodes': {
+                        '0': {'ref': 'zlib/1.2.13.1', 'dependencies': {
+                            '2': {'ref': 'bzip2/1.0.8', 'require': 'bzip2/[>=1.0 <1.1]'}
+                        }},
+                        '1': {'ref': 'minizip/3.0#hash', 'package_id': 'def456'},
+                        '2': {'ref': 'bzip2/1.0.8#hash', 'package_id': 'aaa111'}
+                    }
+                }
+            },
+            rust_crate_file=SimpleUploadedFile('zlib-sys-1.2.13+1.crate', b'zlib crate')
+        )
+        self.index_url = reverse('packages:cargo_index_file', kwargs={'index_path': 'zl/ib/zlib-sys'})
+
+    def index_entries(self):
+        response = self.client.get(self.index_url)
+        self.assertEqual(response.status_code, 200)
+        return [json.loads(line) for line in response.content.decode('utf-8').strip().split('\n')]
+
+    def test_index_uses_cargo_versions(self):
+        """Test vers and dependency requirements are semver"""
+        entry = self.index_entries()[0]
+        self.assertEqual(entry['vers'], '1.2.13+1')
+        self.assertEqual(entry['deps'][0]['req'], '^3.0.0')
+
+    def test_index_keeps_version_ranges(self):
+        """Test requirements come from the recipe's Dependency records, then the graph's requires"""
+        Dependency.objects.create(
+            package_version=self.version,
+            requires_package=Package.objects.create(name='minizip'),
+            version_requirement='[~3]',
+        )
+
+        deps = {dep['name']: dep['req'] for dep in self.index_entries()[0]['deps']}
+        self.assertEqual(deps['minizip-sys'], '~3')
+        self.assertEqual(deps['bzip2-sys'], '>=1.0.0, <1.1.0')
+
+    def test_index_lists_oldest_of_equal_versions(self):
+        """Test Conan versions only differing in build metadata are listed once"""
+        version2 = PackageVersion.objects.create(
+            package=self.package, version='1.2.13.2', cargo_version='1.2.13+2'
+        )
+        BinaryPackage.objects.create(
+            package_version=version2,
+            package_id='abc123',
+            rust_crate_file=SimpleUploadedFile('zlib-sys-1.2.13+2.crate', b'newer crate')
+        )
+
+        entries = self.index_entries()
+        self.assertEqual([entry['vers'] for entry in entries], ['1.2.13+1'])
+
+    def test_download_by_cargo_version(self):
+        """Test the download endpoint finds the package version by its Cargo version"""
+        url = reverse('packages:cargo_download', kwargs={'crate_name': 'zlib-sys', 'version': '1.2.13+1'})
+        response = self.client.get(url)
+        self.assertEqual(response.status_code, 200)
+        self.assertEqual(response.content, b'zlib crate')
+        self.assertIn('zlib-sys-1.2.13+1.crate', response['Content-Disposition'])
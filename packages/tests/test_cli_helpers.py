@@ -22,6 +22,47 @@ check_package_in_cache = cli.check_package_in_cache
 get_binary_package_path = cli.get_binary_package_path
 is_release_version = cli.is_release_version
 parse_conan_profile = cli.parse_conan_profile
+conan_settings_to_rust_target = cli.conan_settings_to_rust_target
+read_conaninfo_settings = cli.read_conaninfo_settings
+load_rust_config = cli.load_rust_config
+find_bindgen_header = cli.find_bindgen_header
+bindgen_command = cli.bindgen_command
+run_bindgen = cli.run_bindgen
+find_libraries = cli.find_libraries
+is_import_library = cli.is_import_library
+find_package_node = cli.find_package_node
+extract_link_info = cli.extract_link_info
+link_manifest = cli.link_manifest
+cxx_runtime_libs = cli.cxx_runtime_libs
+links_env_var = cli.links_env_var
+sys_env_prefix = cli.sys_env_prefix
+pkg_config_name = cli.pkg_config_name
+normalize_cargo_toml = cli.normalize_cargo_toml
+write_crate_archive = cli.write_crate_archive
+cargo_version = cli.cargo_version
+version_requirement = cli.version_requirement
+cargo_requirement = cli.cargo_requirement
+graph_requirements = cli.graph_requirements
+load_cxx_manifest = cli.load_cxx_manifest
+generate_cxx_bridge = cli.generate_cxx_bridge
+archive_symbols = cli.archive_symbols
+elf_symbols = cli.elf_symbols
+smoke_test_functions = cli.smoke_test_functions
+generate_link_smoke_test = cli.generate_link_smoke_test
+find_headers = cli.find_headers
+is_header_only_node = cli.is_header_only_node
+generate_rust_crate = cli.generate_rust_crate
+unpack_crate = cli.unpack_crate
+cargo_config_entries = cli.cargo_config_entries
+update_cargo_config = cli.update_cargo_config
+rpath_config_entries = cli.rpath_config_entries
+registry_coverage_problems = cli.registry_coverage_problems
+add_cargo_dependency = cli.add_cargo_dependency
+write_lockfile = cli.write_lockfile
+read_lockfile = cli.read_lockfile
+lock_drift = cli.lock_drift
+fetch_locked_crate = cli.fetch_locked_crate
+LockfileError = cli.LockfileError
 
 
 class TestGetBinaryPackagePath(unittest.TestCase):
@@ -212,5 +253,984 @@ build_type=Release
         self.assertIsNone(settings)
 
 
+class TestConanSettingsToRustTarget(unittest.TestCase):
+    """Test conan_settings_to_rust_target maps settings to target triples."""
+
+    def test_known_platforms(self):
+        """Should map common configurations to their Rust triples."""
+        cases = [
+            ({'os': 'Linux', 'arch': 'x86_64', 'compiler': 'gcc'}, 'x86_64-unknown-linux-gnu'),
+            ({'os': 'Linux', 'arch': 'armv8', 'compiler': 'gcc'}, 'aarch64-unknown-linux-gnu'),
+            ({'os': 'Windows', 'arch': 'x86_64', 'compiler': 'msvc'}, 'x86_64-pc-windows-msvc'),
+            ({'os': 'Windows', 'arch': 'x86_64', 'compiler': 'gcc'}, 'x86_64-pc-windows-gnu'),
+            ({'os': 'Windows', 'arch': 'x86_64', 'compiler': 'clang'}, 'x86_64-pc-windows-msvc'),
+            ({'os': 'Macos', 'arch': 'armv8', 'compiler': 'apple-clang'}, 'aarch64-apple-darwin'),
+            ({'os': 'Linux', 'arch': 'armv7hf', 'compiler': 'gcc'}, 'armv7-unknown-linux-gnueabihf'),
+            ({'os': 'Android', 'arch': 'armv8', 'compiler': 'clang'}, 'aarch64-linux-android'),
+        ]
+        for settings, expected in cases:
+            with self.subTest(settings=settings):
+                self.assertEqual(conan_settings_to_rust_target(settings), expected)
+
+    def test_unknown_platforms(self):
+        """Should return None when there is no Rust equivalent."""
+        self.assertIsNone(conan_settings_to_rust_target({'os': 'Linux', 'arch': 'sparc'}))
+        self.assertIsNone(conan_settings_to_rust_target({'os': 'Plan9', 'arch': 'x86_64'}))
+        self.assertIsNone(conan_settings_to_rust_target({}))
+
+
+class TestReadConaninfoSettings(unittest.TestCase):
+    """Test read_conaninfo_settings parses the [settings] section."""
+
+    def test_reads_settings_section_only(self):
+        """Should return settings and ignore other sections."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            Path(tmpdir, 'conaninfo.txt').write_text(
+                "[settings]\narch=armv8\nos=Macos\ncompiler=apple-clang\n"
+                "[options]\nshared=False\n"
+            )
+
+            settings = read_conaninfo_settings(tmpdir)
+
+        self.assertEqual(settings, {'arch': 'armv8', 'os': 'Macos', 'compiler': 'apple-clang'})
+
+    def test_missing_conaninfo(self):
+        """Should return an empty dict when conaninfo.txt doesn't exist."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            self.assertEqual(read_conaninfo_settings(tmpdir), {})
+
+
+class TestLoadRustConfig(unittest.TestCase):
+    """Test load_rust_config reads conancrates.ini next to the recipe."""
+
+    def test_reads_rust_section(self):
+        """Should return the [rust] section as a dict."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            Path(tmpdir, 'conanfile.py').write_text("")
+            Path(tmpdir, 'conancrates.ini').write_text(
+                "[rust]\nbindgen = true\nbindgen_header = mylib/mylib.h\n"
+            )
+
+            config = load_rust_config(Path(tmpdir, 'conanfile.py'))
+
+        self.assertEqual(config, {'bindgen': 'true', 'bindgen_header': 'mylib/mylib.h'})
+
+    def test_missing_file_or_section(self):
+        """Should return an empty dict without the file or the section."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            recipe_path = Path(tmpdir, 'conanfile.py')
+            self.assertEqual(load_rust_config(recipe_path), {})
+
+            Path(tmpdir, 'conancrates.ini').write_text("[other]\nkey = value\n")
+            self.assertEqual(load_rust_config(recipe_path), {})
+
+        self.assertEqual(load_rust_config(None), {})
+
+
+class TestBindgenHelpers(unittest.TestCase):
+    """Test bindgen header selection and command line."""
+
+    def test_find_header_by_package_name(self):
+        """Should prefer include/<name>/<name>.h over other headers."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            Path(tmpdir, 'mylib').mkdir()
+            headers = [Path(tmpdir, 'mylib', 'mylib.h'), Path(tmpdir, 'mylib', 'detail.h')]
+            for header in headers:
+                header.write_text("")
+
+            self.assertEqual(find_bindgen_header(tmpdir, 'mylib', headers), 'mylib/mylib.h')
+
+    def test_find_lone_header(self):
+        """Should use the only header when names don't match."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            header = Path(tmpdir, 'api.h')
+            header.write_text("")
+
+            self.assertEqual(find_bindgen_header(tmpdir, 'mylib', [header]), 'api.h')
+            self.assertIsNone(find_bindgen_header(tmpdir, 'mylib', [header, Path(tmpdir, 'other.h')]))
+
+    def test_bindgen_command(self):
+        """Should apply allowlist patterns to functions, types and variables."""
+        command = bindgen_command('inc/mylib.h', 'inc', 'src/bindings.rs', ['mylib_.*'])
+
+        self.assertEqual(command, [
+            'bindgen', 'inc/mylib.h', '-o', 'src/bindings.rs',
+            '--allowlist-function', 'mylib_.*', '--allowlist-type', 'mylib_.*', '--allowlist-var', 'mylib_.*',
+            '--', '-Iinc'
+        ])
+
+    def test_bindgen_command_cpp_header(self):
+        """Should parse .hpp headers as C++."""
+        command = bindgen_command('inc/mylib.hpp', 'inc', 'out.rs')
+        self.assertEqual(command[-2:], ['-x', 'c++'])
+
+    @patch('conancrates.conancrates.subprocess.run')
+    def test_run_bindgen_not_installed(self, mock_run):
+        """Should return False when bindgen isn't installed."""
+        mock_run.side_effect = FileNotFoundError()
+
+        self.assertFalse(run_bindgen('inc/mylib.h', 'inc', 'out.rs'))
+
+
+def make_ar_archive(members):
+    """Build an ar archive from (name, data) pairs."""
+    data = b'!<arch>\n'
+    for name, content in members:
+        header = f"{name:<16}{0:<12}{0:<6}{0:<6}{644:<8}{len(content):<10}`\n".encode('ascii')
+        data += header + content
+        if len(content) % 2:
+            data += b'\n'
+    return data
+
+
+class TestFindLibraries(unittest.TestCase):
+    """Test find_libraries detects static and shared libraries."""
+
+    def setUp(self):
+        import tempfile
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.binary_path = Path(self.tmpdir.name)
+        (self.binary_path / 'lib').mkdir()
+        (self.binary_path / 'bin').mkdir()
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def test_unix_static_and_shared(self):
+        """Should link .a statically, .so dynamically and bundle versioned objects."""
+        for name in ['libstatic.a', 'libshared.so', 'libshared.so.1', 'libother.1.dylib']:
+            (self.binary_path / 'lib' / name).write_bytes(b'')
+
+        libraries, runtime_files = find_libraries(self.binary_path)
+
+        self.assertEqual([(name, kind) for name, _, kind in libraries],
+                         [('shared', 'dylib'), ('static', 'static')])
+        self.assertEqual(sorted(f.name for f in runtime_files), ['libother.1.dylib', 'libshared.so.1'])
+
+    def test_windows_import_library_with_dll(self):
+        """Should treat a .lib with a matching DLL in bin/ as an import library."""
+        (self.binary_path / 'lib' / 'foo.lib').write_bytes(b'')
+        (self.binary_path / 'lib' / 'libbar.lib').write_bytes(make_ar_archive([('bar.obj/', b'\x64\x86')]))
+        (self.binary_path / 'bin' / 'foo.dll').write_bytes(b'')
+
+        libraries, runtime_files = find_libraries(self.binary_path)
+
+        self.assertEqual([(name, kind) for name, _, kind in libraries],
+                         [('foo', 'dylib'), ('libbar', 'static')])
+        self.assertEqual([f.name for f in runtime_files], ['foo.dll'])
+
+    def test_static_preferred_when_both_exist(self):
+        """Should link statically when a library ships as .a and .so."""
+        for name in ['libfoo.a', 'libfoo.so']:
+            (self.binary_path / 'lib' / name).write_bytes(b'')
+
+        libraries, _ = find_libraries(self.binary_path)
+
+        self.assertEqual([(name, kind) for name, _, kind in libraries], [('foo', 'static')])
+
+
+class TestIsImportLibrary(unittest.TestCase):
+    """Test is_import_library tells import libraries from static libraries."""
+
+    def check(self, data):
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            lib_path = Path(tmpdir, 'foo.lib')
+            lib_path.write_bytes(data)
+            return is_import_library(lib_path)
+
+    def test_short_import_objects(self):
+        """Should detect short import objects after the symbol tables."""
+        data = make_ar_archive([
+            ('/', b'\x00\x00\x00\x00'),
+            ('//', b'foo.dll/\n'),
+            ('foo.dll/', b'\x00\x00\xff\xff\x00\x00\x64\x86'),
+        ])
+        self.assertTrue(self.check(data))
+
+    def test_static_library(self):
+        """Should return False for archives of COFF objects."""
+        data = make_ar_archive([('/', b'\x00\x00\x00\x00'), ('foo.obj/', b'\x64\x86\x03\x00')])
+        self.assertFalse(self.check(data))
+
+    def test_not_an_archive(self):
+        """Should return False for files that aren't ar archives."""
+        self.assertFalse(self.check(b'not an archive'))
+
+
+class TestCppInfoLinking(unittest.TestCase):
+    """Test link order and system libraries from cpp_info."""
+
+    def test_find_package_node(self):
+        """Should find the node of a package by reference name."""
+        graph = {'graph': {'nodes': {
+            '0': {'ref': 'conanfile'},
+            '1': {'ref': 'openssl/3.1.0#abc', 'cpp_info': {}},
+        }}}
+        self.assertEqual(find_package_node(graph, 'openssl')['ref'], 'openssl/3.1.0#abc')
+        self.assertIsNone(find_package_node(graph, 'zlib'))
+        self.assertIsNone(find_package_node(None, 'zlib'))
+
+    def test_components_in_dependency_order(self):
+        """Should link a component before the components it requires."""
+        cpp_info = {
+            'root': {'libs': []},
+            'crypto': {'libs': ['crypto'], 'system_libs': ['pthread', 'dl'], 'requires': ['zlib::zlib']},
+            'ssl': {'libs': ['ssl'], 'system_libs': ['pthread'], 'requires': ['crypto'], 'defines': ['OPENSSL_API']},
+        }
+
+        link_info = extract_link_info(cpp_info)
+
+        self.assertEqual(link_info['libs'], ['ssl', 'crypto'])
+        self.assertEqual(link_info['system_libs'], ['pthread', 'dl'])
+        self.assertEqual(link_info['defines'], ['OPENSSL_API'])
+
+    def test_root_cpp_info(self):
+        """Should keep the declared order of a package without components."""
+        cpp_info = {'root': {'libs': ['curl', 'curl_util'], 'frameworks': ['Security'], 'system_libs': ['ws2_32']}}
+
+        link_info = extract_link_info(cpp_info)
+
+        self.assertEqual(link_info['libs'], ['curl', 'curl_util'])
+        self.assertEqual(link_info['frameworks'], ['Security'])
+
+    def test_nothing_to_link(self):
+        """Should return None when cpp_info wasn't computed."""
+        self.assertIsNone(extract_link_info(None))
+        self.assertIsNone(extract_link_info({'root': {'libs': [], 'includedirs': ['include']}}))
+
+    def test_link_manifest_follows_cpp_info(self):
+        """Should link in cpp_info order with system libraries and frameworks last."""
+        libraries = [('crypto', Path('lib/libcrypto.a'), 'static'), ('ssl', Path('lib/libssl.so'), 'dylib')]
+        link_info = {'libs': ['ssl', 'crypto', 'extra'], 'system_libs': ['pthread'],
+                     'frameworks': ['Security'], 'defines': []}
+
+        self.assertEqual(link_manifest(libraries, link_info),
+                         ['dylib=ssl', 'static=crypto', 'extra', 'dylib=pthread', 'framework=Security'])
+
+    def test_link_manifest_without_cpp_info(self):
+        """Should link every library found when there is no cpp_info."""
+        libraries = [('a', Path('lib/liba.a'), 'static'), ('b', Path('lib/libb.so'), 'dylib')]
+        self.assertEqual(link_manifest(libraries), ['static=a', 'dylib=b'])
+
+    def test_cxx_runtime_libs(self):
+        """Should link the C++ standard library the binary was built against."""
+        self.assertEqual(cxx_runtime_libs({'compiler': 'gcc', 'compiler.libcxx': 'libstdc++11'}), ['stdc++'])
+        self.assertEqual(cxx_runtime_libs({'compiler': 'apple-clang', 'compiler.libcxx': 'libc++'}), ['c++'])
+        self.assertEqual(cxx_runtime_libs({'compiler': 'clang', 'compiler.libcxx': 'c++_static'}), ['c++_static', 'c++abi'])
+        self.assertEqual(cxx_runtime_libs({'compiler': 'msvc', 'compiler.runtime': 'dynamic'}), ['msvcprt'])
+        self.assertEqual(
+            cxx_runtime_libs({'compiler': 'msvc', 'compiler.runtime': 'static', 'compiler.runtime_type': 'Debug'}),
+            ['libcpmtd']
+        )
+        self.assertEqual(cxx_runtime_libs({'compiler': 'Visual Studio', 'compiler.runtime': 'MDd'}), ['msvcprtd'])
+        # C libraries remove compiler.libcxx
+        self.assertEqual(cxx_runtime_libs({'compiler': 'gcc'}), [])
+
+    def test_links_env_var(self):
+        """Should name DEP_ variables like cargo does for a links value."""
+        self.assertEqual(links_env_var('zlib', 'include'), 'DEP_ZLIB_INCLUDE')
+        self.assertEqual(links_env_var('libjpeg-turbo', 'root'), 'DEP_LIBJPEG_TURBO_ROOT')
+
+    def test_sys_env_prefix(self):
+        """Should derive the override variable prefix from the package name."""
+        self.assertEqual(sys_env_prefix('zlib'), 'ZLIB_SYS')
+        self.assertEqual(sys_env_prefix('libjpeg-turbo'), 'LIBJPEG_TURBO_SYS')
+        self.assertEqual(sys_env_prefix('gtest.core'), 'GTEST_CORE_SYS')
+
+    def test_pkg_config_name(self):
+        """Should use the pkg_config_name property, falling back to the package name."""
+        cpp_info = {'root': {'libs': ['curl'], 'properties': {'pkg_config_name': 'libcurl'}}}
+        self.assertEqual(pkg_config_name(cpp_info, 'libcurl-conan'), 'libcurl')
+        self.assertEqual(pkg_config_name({'root': {'libs': ['z']}}, 'zlib'), 'zlib')
+        self.assertEqual(pkg_config_name(None, 'zlib'), 'zlib')
+
+    def test_bindgen_command_defines(self):
+        """Should pass cpp_info defines to clang."""
+        command = bindgen_command('inc/mylib.h', 'inc', 'out.rs', defines=['MYLIB_STATIC', 'MYLIB_API=1'])
+        self.assertEqual(command[-3:], ['-Iinc', '-DMYLIB_STATIC', '-DMYLIB_API=1'])
+
+
+class TestCrateArchive(unittest.TestCase):
+    """Test .crate archives match cargo package output."""
+
+    CARGO_TOML = (
+        '[package]\nname = "mylib-sys"\nversion = "1.0.0"\nedition = "2021"\nlinks = "mylib"\n\n'
+        '[lib]\nname = "mylib_sys"\npath = "src/lib.rs"\n\n'
+        '[dependencies]\ndeplib-sys = { version = "2.0.0", path = "../deplib-sys" }\n'
+    )
+
+    def make_crate_dir(self, tmpdir):
+        crate_dir = Path(tmpdir, 'mylib-sys')
+        (crate_dir / 'src').mkdir(parents=True)
+        (crate_dir / 'native' / 'current').mkdir(parents=True)
+        (crate_dir / 'target' / 'debug').mkdir(parents=True)
+        (crate_dir / 'Cargo.toml').write_text(self.CARGO_TOML)
+        (crate_dir / 'build.rs').write_text('fn main() {}\n')
+        (crate_dir / 'src' / 'lib.rs').write_text('')
+        (crate_dir / 'native' / 'current' / 'libs.txt').write_text('static=mylib\n')
+        (crate_dir / 'target' / 'debug' / 'junk').write_text('build output')
+        return crate_dir
+
+    def test_normalize_cargo_toml(self):
+        """Should rewrite path dependencies and declare the build script like cargo."""
+        normalized = normalize_cargo_toml(self.CARGO_TOML, ['Cargo.toml', 'build.rs', 'src/lib.rs'])
+
+        self.assertTrue(normalized.startswith('# THIS FILE IS AUTOMATICALLY GENERATED BY CARGO'))
+        self.assertIn(
+            '[package]\nedition = "2021"\nname = "mylib-sys"\nversion = "1.0.0"\n'
+            'build = "build.rs"\nlinks = "mylib"\nautolib = false\nautobins = false\n'
+            'autoexamples = false\nautotests = false\nautobenches = false\nreadme = false\n',
+            normalized
+        )
+        self.assertIn('[dependencies.deplib-sys]\nversion = "2.0.0"\n', normalized)
+        self.assertNotIn('path = "../deplib-sys"', normalized)
+        self.assertLess(normalized.index('[lib]'), normalized.index('[dependencies.deplib-sys]'))
+
+    def test_normalize_lists_targets(self):
+        """Should list the targets cargo would discover, since discovery is turned off."""
+        import tomllib
+        files = [
+            'Cargo.toml', 'build.rs', 'src/lib.rs', 'src/main.rs', 'src/bin/tool.rs', 'src/bin/multi/main.rs',
+            'src/bin/multi/helper.rs', 'examples/demo.rs', 'tests/link_smoke.rs', 'tests/common/mod.rs',
+            'benches/speed.rs',
+        ]
+        normalized = normalize_cargo_toml('[package]\nname = "gz-sys"\nversion = "1.0.0"\n', files)
+        manifest = tomllib.loads(normalized)
+
+        self.assertEqual(manifest['lib'], {'name': 'gz_sys', 'path': 'src/lib.rs'})
+        self.assertEqual(manifest['bin'], [
+            {'name': 'gz-sys', 'path': 'src/main.rs'},
+            {'name': 'multi', 'path': 'src/bin/multi/main.rs'},
+            {'name': 'tool', 'path': 'src/bin/tool.rs'},
+        ])
+        self.assertEqual(manifest['example'], [{'name': 'demo', 'path': 'examples/demo.rs'}])
+        self.assertEqual(manifest['test'], [{'name': 'link_smoke', 'path': 'tests/link_smoke.rs'}])
+        self.assertEqual(manifest['bench'], [{'name': 'speed', 'path': 'benches/speed.rs'}])
+        self.assertLess(normalized.index('[lib]'), normalized.index('[[bin]]'))
+        self.assertLess(normalized.index('[[test]]'), normalized.index('[[bench]]'))
+
+        # Declared targets are kept, and auto* = false in the original still turns discovery off
+        normalized = normalize_cargo_toml(
+            '[package]\nname = "gz-sys"\nversion = "1.0.0"\nautotests = false\n\n'
+            '[[test]]\nname = "custom"\nharness = false\n',
+            files
+        )
+        self.assertEqual(
+            tomllib.loads(normalized)['test'], [{'name': 'custom', 'path': 'tests/custom.rs', 'harness': False}]
+        )
+
+    def test_archive_layout(self):
+        """Should put files under {name}-{version}/ with Cargo.toml.orig and without build output."""
+        import tarfile
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            crate_dir = self.make_crate_dir(tmpdir)
+            archive = Path(tmpdir, 'mylib-sys-1.0.0.crate')
+            write_crate_archive(crate_dir, 'mylib-sys', '1.0.0', archive)
+
+            with tarfile.open(archive, 'r:gz') as tar:
+                members = tar.getmembers()
+                orig = tar.extractfile('mylib-sys-1.0.0/Cargo.toml.orig').read().decode()
+
+        self.assertEqual([member.name for member in members], [
+            'mylib-sys-1.0.0/Cargo.toml',
+            'mylib-sys-1.0.0/Cargo.toml.orig',
+            'mylib-sys-1.0.0/build.rs',
+            'mylib-sys-1.0.0/native/current/libs.txt',
+            'mylib-sys-1.0.0/src/lib.rs',
+        ])
+        self.assertEqual(orig, self.CARGO_TOML)
+        for member in members:
+            self.assertEqual(member.mtime, cli.CRATE_MTIME)
+            self.assertEqual(member.mode, 0o644)
+            self.assertEqual((member.uid, member.gid), (0, 0))
+
+    def test_archive_is_reproducible(self):
+        """Should produce identical bytes for the same input, whatever the file times."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            crate_dir = self.make_crate_dir(tmpdir)
+            first = Path(tmpdir, 'first', 'mylib-sys-1.0.0.crate')
+            second = Path(tmpdir, 'second', 'mylib-sys-1.0.0.crate')
+            first.parent.mkdir()
+            second.parent.mkdir()
+
+            write_crate_archive(crate_dir, 'mylib-sys', '1.0.0', first)
+            os.utime(crate_dir / 'build.rs', (0, 0))
+            write_crate_archive(crate_dir, 'mylib-sys', '1.0.0', second)
+
+            self.assertEqual(first.read_bytes(), second.read_bytes())
+
+
+class TestCargoVersion(unittest.TestCase):
+    """Test Conan versions are translated into semver for Cargo."""
+
+    def test_translations(self):
+        """Should pad short versions and keep extra components as build metadata."""
+        cases = {
+            "1.2.3": "1.2.3",
+            "3.0": "3.0.0",
+            "1.2.13.1": "1.2.13+1",
+            "1.1.1w": "1.1.1+w",
+            "cci.20230101": "20230101.0.0+cci",
+            "2.0-rc1": "2.0.0-rc1",
+            "1.81.0+build": "1.81.0+build",
+        }
+        for version, expected in cases.items():
+            with self.subTest(version=version):
+                self.assertEqual(cargo_version(version), expected)
+                self.assertRegex(expected, cli.SEMVER_RE)
+
+    def test_version_requirement(self):
+        """Should drop build metadata, which Cargo ignores in requirements."""
+        self.assertEqual(version_requirement("1.2.13+1"), "1.2.13")
+        self.assertEqual(version_requirement("3.0.0"), "3.0.0")
+
+
+class TestCargoRequirement(unittest.TestCase):
+    """Test Conan version ranges are translated into Cargo requirements."""
+
+    def test_translations(self):
+        """Should keep range bounds and leave ~/^ versions partial."""
+        cases = {
+            "1.2.13": "^1.2.13",
+            "[>=1.2 <2]": ">=1.2.0, <2.0.0",
+            "[~1]": "~1",
+            "[^0.2, include_prerelease]": "^0.2",
+            "[1.2.13.1]": "=1.2.13",
+        }
+        for requirement, expected in cases.items():
+            with self.subTest(requirement=requirement):
+                self.assertEqual(cargo_requirement(requirement), expected)
+
+    def test_alternatives(self):
+        """Should pick the alternative containing the resolved version."""
+        self.assertEqual(cargo_requirement("[>=1 <2 || >=3 <4]", "1.5"), ">=1.0.0, <2.0.0")
+        self.assertEqual(cargo_requirement("[>=5]", "1.5"), "^1.5.0")
+
+    def test_graph_requirements(self):
+        """Should read requires from graph edges, preferring the root node's."""
+        nodes = {
+            "0": {"ref": "app/1.0", "dependencies": {"1": {"ref": "zlib/1.3", "require": "zlib/[>=1.2 <2]"}}},
+            "1": {"ref": "zlib/1.3"},
+            "2": {"ref": "png/1.6", "dependencies": {"1": {"ref": "zlib/1.3", "require": "zlib/1.3"}}},
+        }
+        self.assertEqual(graph_requirements(nodes), {"1": "[>=1.2 <2]"})
+
+
+
+class TestCxxBridge(unittest.TestCase):
+    """Test cxx bridge scaffolding generated from a C++ API manifest."""
+
+    MANIFEST = """
+namespace = "mylib"
+headers = ["mylib/widget.hpp"]
+
+[[classes]]
+name = "Widget"
+constructors = [{ name = "new", args = ["name: str"] }]
+methods = [
+    { name = "size", returns = "usize", const = true },
+    { name = "set_name", args = ["name: str"], cpp_name = "setName" },
+]
+
+[[functions]]
+name = "version"
+returns = "String"
+"""
+
+    def load(self, text):
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = Path(tmpdir) / "cxx.toml"
+            path.write_text(text)
+            return load_cxx_manifest(path)
+
+    def test_rejects_invalid_manifests(self):
+        """Should reject manifests without headers, API or with unsupported types."""
+        with self.assertRaises(cli.CxxManifestError):
+            self.load('namespace = "mylib"\n[[functions]]\nname = "f"\n')
+        with self.assertRaises(cli.CxxManifestError):
+            self.load('headers = ["a.h"]\n')
+        with self.assertRaisesRegex(cli.CxxManifestError, "unsupported argument type"):
+            self.load('headers = ["a.h"]\n[[functions]]\nname = "f"\nargs = ["v: std::vector<int>"]\n')
+
+    def test_bridge_signatures(self):
+        """Should declare shim functions in the bridge and wrap them as methods."""
+        bridge_rs, shim_h, shim_cc = generate_cxx_bridge(self.load(self.MANIFEST), "mylib-sys", "mylib_shim")
+
+        self.assertIn('#[cxx::bridge(namespace = "mylib")]', bridge_rs)
+        self.assertIn('include!("mylib-sys/shim/mylib_shim.h");', bridge_rs)
+        self.assertIn('fn widget_new(name: &str) -> UniquePtr<Widget>;', bridge_rs)
+        self.assertIn('fn widget_set_name(this: Pin<&mut Widget>, name: &str);', bridge_rs)
+        self.assertIn('pub fn size(&self) -> usize {', bridge_rs)
+        self.assertIn('pub fn set_name(self: Pin<&mut Self>, name: &str) {', bridge_rs)
+
+        self.assertIn('#include "mylib/widget.hpp"', shim_h)
+        self.assertIn('std::unique_ptr<Widget> widget_new(rust::Str name);', shim_h)
+        self.assertIn('self.setName(std::string(name));', shim_cc)
+        self.assertIn('return rust::String(::mylib::version());', shim_cc)
+
+
+
+def ar_member(name, data):
+    """Build an ar archive member."""
+    header = f"{name:<16}{0:<12}{0:<6}{0:<6}{644:<8}{len(data):<10}`\n".encode('ascii')
+    return header + data + (b'\n' if len(data) % 2 else b'')
+
+
+def elf_shared_library(symbols):
+    """Minimal 64-bit ELF with a dynamic symbol table of (name, type) symbols."""
+    import struct
+    strings = b'\0' + b''.join(name.encode('ascii') + b'\0' for name, _ in symbols)
+    table = b'\0' * 24
+    offset = 1
+    for name, symbol_type in symbols:
+        # Global binding, default visibility, defined in section 1
+        table += struct.pack('<IBBHQQ', offset, 0x10 | symbol_type, 0, 1, 0, 0)
+        offset += len(name) + 1
+    strings_offset = 64
+    table_offset = strings_offset + len(strings)
+    sections_offset = table_offset + len(table)
+    header = b'\x7fELF\x02\x01\x01' + b'\0' * 9 + struct.pack('<HHIQQQIHHHHHH', 3, 62, 1, 0, 0, sections_offset, 0, 64, 0, 0, 64, 3, 0)
+    sections = b'\0' * 64
+    sections += struct.pack('<IIQQQQIIQQ', 0, 3, 0, 0, strings_offset, len(strings), 0, 0, 1, 0)  # .dynstr
+    sections += struct.pack('<IIQQQQIIQQ', 0, 11, 0, 0, table_offset, len(table), 1, 1, 8, 24)  # .dynsym
+    return header + strings + table + sections
+
+
+class TestLinkSmokeTest(unittest.TestCase):
+    """Test the link smoke test generated from exported symbols."""
+
+    def test_elf_exports(self):
+        """Should read exported functions, and variables only when asked for."""
+        data = elf_shared_library([('gz_add', 2), ('gz_counter', 1), ('gz_tls', 6)])
+        self.assertEqual(elf_symbols(data), {'gz_add'})
+        self.assertEqual(elf_symbols(data, functions_only=False), {'gz_add', 'gz_counter', 'gz_tls'})
+
+    def test_gnu_archive_index(self):
+        """Should read the symbols of a GNU/MSVC ("/") archive index."""
+        names = [b'gz_add', b'gz_version']
+        index = len(names).to_bytes(4, 'big') + b'\0' * 4 * len(names) + b''.join(name + b'\0' for name in names)
+        data = b'!<arch>\n' + ar_member('/', index) + ar_member('gz.o/', b'\0' * 8)
+        self.assertEqual(archive_symbols(data), {'gz_add', 'gz_version'})
+
+    def test_bsd_archive_index(self):
+        """Should read the symbols of a BSD (__.SYMDEF) index with a long member name."""
+        strings = b'_gz_add\0_gz_version\0'
+        ranlib = (0).to_bytes(4, 'little') + (0).to_bytes(4, 'little') + (8).to_bytes(4, 'little') + (0).to_bytes(4, 'little')
+        name = b'__.SYMDEF SORTED\0\0\0\0'
+        index = name + len(ranlib).to_bytes(4, 'little') + ranlib + len(strings).to_bytes(4, 'little') + strings
+        data = b'!<arch>\n' + ar_member(f'#1/{len(name)}', index)
+        self.assertEqual(archive_symbols(data), {'_gz_add', '_gz_version'})
+
+    def test_picks_functions_declared_in_headers(self):
+        """Should keep exported symbols the headers declare, without Mach-O's underscore."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            header = Path(tmpdir) / 'gz.h'
+            header.write_text('int gz_add(int a, int b);\nconst char *gz_version (void);\n')
+            lib_file = Path(tmpdir) / 'libgz.a'
+            lib_file.write_bytes(b'')
+            symbols = {'_gz_add', 'gz_version', 'gz_internal', '_ZN2gz6detailEv', '__imp_gz_add'}
+            with patch('conancrates.conancrates.library_symbols', return_value=symbols):
+                self.assertEqual(
+                    smoke_test_functions([('gz', lib_file, 'static')], [header]),
+                    ['gz_add', 'gz_version']
+                )
+                self.assertEqual(len(smoke_test_functions([('gz', lib_file, 'static')], [header], limit=1)), 1)
+
+    def test_generated_test(self):
+        """Should link the crate and its dependencies and take each function's address."""
+        test_rs = generate_link_smoke_test('gz-sys', ['gz_add'], ['zlib-sys'])
+        self.assertIn('extern crate gz_sys;\nextern crate zlib_sys;\n', test_rs)
+        self.assertIn('    fn gz_add();\n', test_rs)
+        self.assertIn('("gz_add", gz_add as *const ()),', test_rs)
+
+    def test_archive_runs_smoke_test(self):
+        """Should declare the smoke test in the archive's Cargo.toml, where cargo no longer looks for tests."""
+        import tarfile
+        import tempfile
+        import tomllib
+        with tempfile.TemporaryDirectory() as tmpdir:
+            package = Path(tmpdir) / 'package'
+            (package / 'include').mkdir(parents=True)
+            (package / 'include' / 'gz.h').write_text('int gz_add(int a, int b);\n')
+            (package / 'lib').mkdir()
+            (package / 'lib' / 'libgz.a').write_bytes(b'!<arch>\n')
+
+            with patch('builtins.print'), patch('conancrates.conancrates.library_symbols', return_value={'gz_add'}):
+                status, crate_file = generate_rust_crate('gz/1.0.0', package, Path(tmpdir) / 'out')
+
+            self.assertEqual(status, 0)
+            with tarfile.open(crate_file, 'r:gz') as tar:
+                names = tar.getnames()
+                manifest = tomllib.loads(tar.extractfile('gz-sys-1.0.0/Cargo.toml').read().decode())
+
+        self.assertIn('gz-sys-1.0.0/tests/link_smoke.rs', names)
+        self.assertFalse(manifest['package']['autotests'])
+        self.assertEqual(manifest['test'], [{'name': 'link_smoke', 'path': 'tests/link_smoke.rs'}])
+
+
+class TestHeaderOnly(unittest.TestCase):
+    """Test header-only package detection and crate generation."""
+
+    def test_find_headers(self):
+        """Should find C and C++ headers of every common extension."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            include = Path(tmpdir)
+            for name in ['a.h', 'b.hh', 'c.hpp', 'd.hxx', 'e.inl', 'f.ipp', 'g.tcc', 'vector', 'notes.txt', 'src.cpp']:
+                (include / name).write_text('')
+            self.assertEqual(
+                sorted(path.name for path in find_headers(include)),
+                ['a.h', 'b.hh', 'c.hpp', 'd.hxx', 'e.inl', 'f.ipp', 'g.tcc', 'vector']
+            )
+            self.assertEqual(find_headers(include / 'missing'), [])
+
+    def test_is_header_only_node(self):
+        """Should use the package_type, then a cpp_info with nothing to link."""
+        self.assertTrue(is_header_only_node({'package_type': 'header-library'}))
+        self.assertTrue(is_header_only_node({'cpp_info': {'root': {'libs': [], 'includedirs': ['include']}}}))
+        self.assertFalse(is_header_only_node({'cpp_info': {'root': {'system_libs': ['pthread']}}}))
+        self.assertFalse(is_header_only_node({'package_type': 'static-library'}))
+        self.assertFalse(is_header_only_node({}))
+
+    def test_header_only_crate(self):
+        """Should generate a crate without links that exports its include paths."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            package = Path(tmpdir) / 'package'
+            (package / 'include' / 'json').mkdir(parents=True)
+            (package / 'include' / 'json' / 'json.hh').write_text('#pragma once\n')
+            (package / 'lib').mkdir()
+            graph = {'graph': {'nodes': {
+                '0': {'ref': 'json/3.11.2', 'cpp_info': {'root': {'defines': ['JSON_NOEXCEPTION']}}},
+            }}}
+
+            with patch('builtins.print'):
+                result = generate_rust_crate('json/3.11.2', package, Path(tmpdir) / 'out', graph)
+
+            self.assertEqual(result[0], 0)
+            crate_dir = Path(tmpdir) / 'out' / 'json-sys'
+            self.assertNotIn('links', (crate_dir / 'Cargo.toml').read_text())
+            self.assertFalse((crate_dir / 'native').exists())
+            self.assertIn('CONANCRATES_INCLUDE_PATHS', (crate_dir / 'build.rs').read_text())
+            lib_rs = (crate_dir / 'src' / 'lib.rs').read_text()
+            self.assertIn('pub fn include_paths() -> Vec<PathBuf>', lib_rs)
+            self.assertIn('pub const DEFINES: &[&str] = &["JSON_NOEXCEPTION"];', lib_rs)
+
+    def test_header_only_recipe_with_libraries(self):
+        """Should follow the graph node like dependent crates do, even with files in lib/."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            package = Path(tmpdir) / 'package'
+            (package / 'lib').mkdir(parents=True)
+            (package / 'lib' / 'libjson.a').write_bytes(b'!<arch>\n')
+            graph = {'graph': {'nodes': {'0': {'ref': 'json/3.11.2', 'package_type': 'header-library'}}}}
+
+            with patch('builtins.print'):
+                generate_rust_crate('json/3.11.2', package, Path(tmpdir) / 'out', graph)
+
+            crate_dir = Path(tmpdir) / 'out' / 'json-sys'
+            self.assertNotIn('links', (crate_dir / 'Cargo.toml').read_text())
+            self.assertFalse((crate_dir / 'native').exists())
+
+    def test_header_only_dependency_is_build_dependency(self):
+        """Should read header-only dependencies' include paths from build.rs."""
+        import tempfile
+        with tempfile.TemporaryDirectory() as tmpdir:
+            package = Path(tmpdir) / 'package'
+            (package / 'lib').mkdir(parents=True)
+            (package / 'lib' / 'libapp.a').write_bytes(b'!<arch>\n')
+            graph = {'graph': {'nodes': {
+                '0': {'ref': 'app/1.0'},
+                '1': {'ref': 'json/3.11.2#abc', 'package_type': 'header-library'},
+            }}}
+
+            with patch('builtins.print'):
+                generate_rust_crate('app/1.0', package, Path(tmpdir) / 'out', graph)
+
+            crate_dir = Path(tmpdir) / 'out' / 'app-sys'
+            cargo_toml = (crate_dir / 'Cargo.toml').read_text()
+            self.assertIn('links = "app"', cargo_toml)
+            self.assertIn('[build-dependencies]\njson-sys = ', cargo_toml)
+            build_rs = (crate_dir / 'build.rs').read_text()
+            self.assertIn('json_sys::include_paths()', build_rs)
+            self.assertNotIn('DEP_JSON_INCLUDE', build_rs)
+
+
+class TestAbiChecks(unittest.TestCase):
+    """Test the binary's settings are recorded for build.rs to check."""
+
+    def generate(self, conaninfo):
+        import shutil
+        import tempfile
+        tmpdir = tempfile.mkdtemp()
+        self.addCleanup(shutil.rmtree, tmpdir)
+        package = Path(tmpdir) / 'package'
+        (package / 'lib').mkdir(parents=True)
+        (package / 'lib' / 'libcx.a').write_bytes(b'!<arch>\n')
+        (package / 'conaninfo.txt').write_text(conaninfo)
+        with patch('builtins.print'):
+            generate_rust_crate('cx/1.0', package, Path(tmpdir) / 'out')
+        return Path(tmpdir) / 'out' / 'cx-sys'
+
+    def test_settings_recorded(self):
+        """Should write the settings next to the libraries and check them in build.rs."""
+        crate_dir = self.generate('[settings]\nos=Windows\narch=x86_64\ncompiler=msvc\ncompiler.runtime=static\n')
+        self.assertEqual(
+            (crate_dir / 'native' / 'current' / 'settings.txt').read_text(),
+            'arch=x86_64\ncompiler=msvc\ncompiler.runtime=static\nos=Windows\n'
+        )
+        build_rs = (crate_dir / 'build.rs').read_text()
+        self.assertIn('check_abi(&lib_path);', build_rs)
+        self.assertIn('"STRICT"', build_rs)
+
+    def test_errors_are_raised(self):
+        """Should raise generation errors instead of only printing them."""
+        import tempfile
+        from types import SimpleNamespace
+        with tempfile.TemporaryDirectory() as tmpdir:
+            package = Path(tmpdir) / 'package'
+            (package / 'lib').mkdir(parents=True)
+            (package / 'lib' / 'libcx.a').write_bytes(b'!<arch>\n')
+            (package / 'conaninfo.txt').write_text('[settings]\nos=Plan9\narch=x86_64\n')
+
+            with patch('builtins.print'):
+                with self.assertRaisesRegex(cli.CrateGenerationError, 'Rust target triple'):
+                    generate_rust_crate('cx/1.0', package, Path(tmpdir) / 'out', args=SimpleNamespace(multi_target=True))
+                with self.assertRaisesRegex(cli.CrateGenerationError, 'name/version'):
+                    generate_rust_crate('cx', package, Path(tmpdir) / 'out')
+
+    def test_old_libstdcxx_abi(self):
+        """Should select libstdc++'s pre-C++11 ABI for code compiled against the headers."""
+        crate_dir = self.generate('[settings]\nos=Linux\narch=x86_64\ncompiler=gcc\ncompiler.libcxx=libstdc++\n')
+        native_dir = crate_dir / 'native' / 'current'
+        self.assertEqual((native_dir / 'defines.txt').read_text(), '_GLIBCXX_USE_CXX11_ABI=0\n')
+        self.assertEqual((native_dir / 'libs.txt').read_text(), 'static=cx\nstdc++\n')
+
+
+class TestCargoSetup(unittest.TestCase):
+    """Test vendoring crates and writing the cargo configuration."""
+
+    def setUp(self):
+        import shutil
+        import tempfile
+        self.tmpdir = Path(tempfile.mkdtemp())
+        self.addCleanup(shutil.rmtree, self.tmpdir)
+
+    def test_unpack_crate(self):
+        """Should unpack into <crate>-<version>/ with the checksums of a directory source."""
+        import hashlib
+        import json
+        crate_dir = self.tmpdir / 'src'
+        (crate_dir / 'src').mkdir(parents=True)
+        (crate_dir / 'Cargo.toml').write_text('[package]\nname = "gz-sys"\nversion = "1.0.0"\n')
+        (crate_dir / 'src' / 'lib.rs').write_text('')
+        crate_file = self.tmpdir / 'gz-sys-1.0.0.crate'
+        write_crate_archive(crate_dir, 'gz-sys', '1.0.0', crate_file)
+
+        unpacked = unpack_crate(crate_file, self.tmpdir / 'vendor')
+
+        self.assertEqual(unpacked, self.tmpdir / 'vendor' / 'gz-sys-1.0.0')
+        checksum = json.loads((unpacked / '.cargo-checksum.json').read_text())
+        self.assertEqual(checksum['package'], hashlib.sha256(crate_file.read_bytes()).hexdigest())
+        self.assertEqual(sorted(checksum['files']), ['Cargo.toml', 'Cargo.toml.orig', 'src/lib.rs'])
+
+    def test_config_entries(self):
+        """Should patch crates-io, replace it, or define the registry."""
+        crate_dirs = {'gz-sys': Path('vendor/gz-sys-1.0.0')}
+        self.assertEqual(
+            cargo_config_entries('patch', crate_dirs, Path('vendor'), 'http://cc:8000'),
+            {'patch': {'crates-io': {'gz-sys': {'path': 'vendor/gz-sys-1.0.0'}}}}
+        )
+        self.assertEqual(
+            cargo_config_entries('source', crate_dirs, Path('vendor'), 'http://cc:8000')['source']['crates-io'],
+            {'replace-with': 'conancrates-vendor'}
+        )
+        self.assertEqual(
+            cargo_config_entries('registry', {}, Path('vendor'), 'http://cc:8000/'),
+            {'registries': {'conancrates': {'index': 'sparse+http://cc:8000/cargo/index/'}}}
+        )
+
+    def test_rpath_config_entries(self):
+        """Should give executables an rpath to their directory, keeping the target's rustflags."""
+        import tomllib
+        config_path = self.tmpdir / '.cargo' / 'config.toml'
+        config_path.parent.mkdir()
+        config_path.write_text('[target.\'cfg(target_os = "linux")\']\nrustflags = ["-C", "target-cpu=native"]\n')
+
+        update_cargo_config(config_path, rpath_config_entries(config_path, 'x86_64-unknown-linux-gnu'))
+        update_cargo_config(config_path, rpath_config_entries(config_path, 'x86_64-unknown-linux-gnu'))
+
+        config = tomllib.loads(config_path.read_text())
+        self.assertEqual(
+            config['target']['cfg(target_os = "linux")']['rustflags'],
+            ['-C', 'target-cpu=native', '-C', 'link-arg=-Wl,-rpath,$ORIGIN']
+        )
+        self.assertEqual(
+            rpath_config_entries(config_path, 'aarch64-apple-darwin'),
+            {'target': {'cfg(target_os = "macos")': {'rustflags': ['-C', 'link-arg=-Wl,-rpath,@loader_path']}}}
+        )
+        self.assertEqual(rpath_config_entries(config_path, 'x86_64-pc-windows-msvc'), {})
+        self.assertEqual(rpath_config_entries(config_path, None), {})
+
+    def test_registry_coverage(self):
+        """Should report crates the index serves for other targets than the profile's."""
+        info = {
+            'package': {'name': 'app', 'version': '1.0'},
+            'rust_crate': {'crate_name': 'app-sys', 'registry_targets': ['x86_64-unknown-linux-gnu', 'aarch64-apple-darwin']},
+            'dependencies': [{'name': 'gz', 'version': '1.0', 'package_id': 'gz123'}],
+        }
+        dep_info = {'rust_crate': {'crate_name': 'gz-sys', 'registry_targets': ['x86_64-pc-windows-msvc']}}
+        with patch.object(cli, 'fetch_binary_info', return_value=dep_info) as fetch:
+            self.assertEqual(
+                registry_coverage_problems('http://cc', info, 'x86_64-unknown-linux-gnu'),
+                ['gz-sys 1.0: the index serves x86_64-pc-windows-msvc']
+            )
+            fetch.assert_called_once_with('http://cc', 'gz', '1.0', 'gz123')
+
+            dep_info['rust_crate']['registry_targets'].append('aarch64-apple-darwin')
+            self.assertEqual(registry_coverage_problems('http://cc', info, 'aarch64-apple-darwin'), [])
+            self.assertEqual(len(registry_coverage_problems('http://cc', info, None)), 2)
+
+    def test_update_cargo_config_keeps_settings(self):
+        """Should merge into an existing config."""
+        import tomllib
+        config_path = self.tmpdir / '.cargo' / 'config.toml'
+        config_path.parent.mkdir()
+        config_path.write_text('[build]\njobs = 4\n\n[patch.crates-io]\nother-sys = { path = "other" }\n')
+
+        update_cargo_config(config_path, {'patch': {'crates-io': {'gz-sys': {'path': 'vendor/gz-sys-1.0.0'}}}})
+
+        config = tomllib.loads(config_path.read_text())
+        self.assertEqual(config['build'], {'jobs': 4})
+        self.assertEqual(sorted(config['patch']['crates-io']), ['gz-sys', 'other-sys'])
+
+    def test_add_cargo_dependency(self):
+        """Should insert into [dependencies] once, leaving the rest of the manifest alone."""
+        cargo_toml = self.tmpdir / 'Cargo.toml'
+        cargo_toml.write_text('[package]\nname = "app"\n\n[dependencies]\nlog = "0.4"\n\n[features]\n# keep\n')
+
+        self.assertTrue(add_cargo_dependency(cargo_toml, 'gz-sys', '1.0.0'))
+        self.assertFalse(add_cargo_dependency(cargo_toml, 'gz-sys', '1.0.0'))
+
+        self.assertEqual(
+            cargo_toml.read_text(),
+            '[package]\nname = "app"\n\n[dependencies]\ngz-sys = "1.0.0"\nlog = "0.4"\n\n[features]\n# keep\n'
+        )
+
+    def test_add_cargo_dependency_without_table(self):
+        """Should append a [dependencies] table when there is none."""
+        cargo_toml = self.tmpdir / 'Cargo.toml'
+        cargo_toml.write_text('[package]\nname = "app"\n')
+
+        add_cargo_dependency(cargo_toml, 'gz-sys', {'version': '1.0.0', 'registry': 'conancrates'})
+
+        self.assertTrue(cargo_toml.read_text().endswith(
+            '\n[dependencies]\ngz-sys = { version = "1.0.0", registry = "conancrates" }\n'
+        ))
+
+
+class TestLockfile(unittest.TestCase):
+    """Test conancrates.lock and the checks of install --locked."""
+
+    CRATE = {
+        'name': 'gz-sys',
+        'version': '1.0.0',
+        'package': 'gz/1.0',
+        'package_id': 'abcd1234',
+        'recipe_revision': 'rev1',
+        'sha256': '',
+        'target': 'x86_64-unknown-linux-gnu',
+    }
+
+    def setUp(self):
+        import hashlib
+        import shutil
+        import tempfile
+        self.tmpdir = Path(tempfile.mkdtemp())
+        self.addCleanup(shutil.rmtree, self.tmpdir)
+        self.crate_bytes = b'crate data'
+        self.crate = dict(self.CRATE, sha256=hashlib.sha256(self.crate_bytes).hexdigest())
+
+    def server(self, info=None, crate_bytes=None):
+        """Mock requests.get answering the info API and crate downloads."""
+        def get(url, params=None):
+            response = Mock(status_code=200)
+            if url.endswith('/info'):
+                response.json.return_value = info or {
+                    'package': {'recipe_revision': 'rev1'},
+                    'rust_crate': {
+                        'available': True, 'crate_name': 'gz-sys', 'cargo_version': '1.0.0',
+                        'sha256': self.crate['sha256'], 'rust_target': 'x86_64-unknown-linux-gnu',
+                    },
+                }
+            else:
+                response.content = self.crate_bytes if crate_bytes is None else crate_bytes
+            return response
+        return patch.object(cli.requests, 'get', side_effect=get)
+
+    def test_round_trip(self):
+        """Should read back the requested packages and every locked field."""
+        lockfile = self.tmpdir / 'conancrates.lock'
+        write_lockfile(lockfile, ['gz/1.0'], [self.crate])
+
+        self.assertEqual(read_lockfile(lockfile), (['gz/1.0'], [self.crate]))
+
+    def test_read_rejects_unknown_version(self):
+        """Should refuse lockfiles of another format version and incomplete entries."""
+        lockfile = self.tmpdir / 'conancrates.lock'
+        lockfile.write_text('version = 99\n')
+        with self.assertRaises(LockfileError):
+            read_lockfile(lockfile)
+
+        lockfile.write_text('version = 1\nrequires = []\n\n[[crate]]\nname = "gz-sys"\n')
+        with self.assertRaisesRegex(LockfileError, 'no version, package, package_id, sha256'):
+            read_lockfile(lockfile)
+
+    def test_lock_drift(self):
+        """Should report every locked field the server no longer agrees with."""
+        current = dict(self.crate, recipe_revision='rev2', target='aarch64-unknown-linux-gnu')
+
+        self.assertEqual(lock_drift(self.crate, dict(self.crate)), [])
+        self.assertEqual(
+            [difference.split(':')[0] for difference in lock_drift(self.crate, current)],
+            ['recipe_revision', 'target']
+        )
+
+    def test_fetch_locked_crate(self):
+        """Should return the crate when the server and the download match the lock."""
+        with self.server():
+            self.assertEqual(fetch_locked_crate('http://cc', self.crate), self.crate_bytes)
+
+    def test_fetch_refuses_checksum_mismatch(self):
+        """Should refuse a download whose sha256 isn't the locked one."""
+        with self.server(crate_bytes=b'tampered'):
+            with self.assertRaisesRegex(LockfileError, 'checksum mismatch'):
+                fetch_locked_crate('http://cc', self.crate)
+
+    def test_fetch_refuses_recipe_revision_drift(self):
+        """Should refuse a binary whose recipe revision changed since it was locked."""
+        info = {
+            'package': {'recipe_revision': 'rev2'},
+            'rust_crate': {
+                'available': True, 'crate_name': 'gz-sys', 'cargo_version': '1.0.0',
+                'sha256': self.crate['sha256'], 'rust_target': 'x86_64-unknown-linux-gnu',
+            },
+        }
+        with self.server(info=info):
+            with self.assertRaisesRegex(LockfileError, "recipe_revision: locked 'rev1', server has 'rev2'"):
+                fetch_locked_crate('http://cc', self.crate)
+
+
 if __name__ == '__main__':
     unittest.main()
@@ -0,0 +1,157 @@
+"""
+Tests for rustdoc hosting of the registry's crates.
+"""
+from django.test import TestCase, override_settings
+from django.urls import reverse
+from django.core.files.uploadedfile import SimpleUploadedFile
+from packages.models import Package, PackageVersion, BinaryPackage
+from packages.crate_docs import DocsError, build_docs, requirement_matches, resolve_crate
+from packages.tests.test_rust_crates import make_sys_crate
+from pathlib import Path
+from unittest.mock import patch
+import subprocess
+import tomllib
+
+
+def create_crate_version(package, version, package_id, dependencies=(), dependency_graph=None):
+    """Package version with one binary carrying a -sys crate."""
+    package_version = PackageVersion.objects.create(package=package, version=version)
+    crate_name = f"{package.name.replace('_', '-')}-sys"
+    BinaryPackage.objects.create(
+        package_version=package_version,
+        package_id=package_id,
+        dependency_graph=dependency_graph,
+        rust_crate_file=SimpleUploadedFile(
+            f'{crate_name}-{version}.crate', make_sys_crate(crate_name, version, dependencies)
+        )
+    )
+    return package_version
+
+
+class CrateDocsTests(TestCase):
+    """Test building, storing and serving the docs of a crate with a dependency"""
+
+    def setUp(self):
+        pkg_b = Package.objects.create(name='pkg_b')
+        for version, package_id in (('1.0.0', 'b100'), ('1.2.0', 'b120'), ('2.0.0', 'b200')):
+            create_crate_version(pkg_b, version, package_id)
+
+        pkg_a = Package.objects.create(name='pkg_a')
+        self.version = create_crate_version(pkg_a, '1.0.0', 'a123', ['pkg-b-sys'], {
+            'graph': {
+                'nodes': {
+                    '0': {'ref': 'pkg_a/1.0.0'},
+                    '1': {'ref': 'pkg_b/1.0.0#hash', 'package_id': 'b100'}
+                }
+            }
+        })
+        self.workspaces = []
+
+    def fake_cargo_doc(self, command, cwd, env, **kwargs):
+        """Write what cargo doc writes into --target-dir, and remember the workspace."""
+        workspace = Path(cwd)
+        self.workspaces.append({
+            'members': tomllib.loads((workspace / 'Cargo.toml').read_text())['workspace']['members'],
+            'pkg-a-sys': tomllib.loads((workspace / 'crates/pkg-a-sys/Cargo.toml').read_text()),
+            'pkg-b-sys': tomllib.loads((workspace / 'crates/pkg-b-sys/Cargo.toml').read_text()),
+        })
+        doc_dir = Path(command[command.index('--target-dir') + 1]) / 'doc'
+        (doc_dir / 'pkg_a_sys').mkdir(parents=True)
+        (doc_dir / 'pkg_a_sys' / 'index.html').write_text('<h1>pkg_a_sys</h1>')
+        (doc_dir / 'search-index.js').write_text('var searchIndex = {};')
+        return subprocess.CompletedProcess(command, 0, '', '')
+
+    def test_requirement_matches(self):
+        """Test requirements of the index (and bare published ones) against versions"""
+        self.assertTrue(requirement_matches('^1.0.0', '1.2.0'))
+        self.assertTrue(requirement_matches('>=1.0.0, <2.0.0', '1.9.9'))
+        self.assertTrue(requirement_matches('1.0', '1.5.0'))
+        self.assertFalse(requirement_matches('^1.0.0', '2.0.0'))
+        self.assertFalse(requirement_matches('*', 'not-semver'))
+
+    def test_resolve_newest_matching_version(self):
+        """Test dependencies resolve like Cargo would: the newest version matching"""
+        package_version, binary = resolve_crate('pkg-b-sys', '^1.0.0')
+        self.assertEqual(package_version.version, '1.2.0')
+        self.assertEqual(binary.package_id, 'b120')
+        self.assertIsNone(resolve_crate('pkg-b-sys', '^3.0.0'))
+        self.assertIsNone(resolve_crate('missing-sys', '*'))
+
+    def test_build_and_serve_docs(self):
+        """Test cargo doc runs offline on a workspace of the crate and its dependency, and the HTML is served"""
+        with patch('packages.crate_docs.subprocess.run', side_effect=self.fake_cargo_doc) as run:
+            build_docs(self.version)
+
+        command = run.call_args.args[0]
+        self.assertEqual(command[:2], ['bwrap', '--unshare-all'])
+        cargo = command.index('cargo')
+        self.assertEqual(command[cargo:cargo + 4], ['cargo', 'doc', '--offline', '--no-deps'])
+        self.assertNotIn('/etc', command[:cargo])
+        self.assertEqual(run.call_args.kwargs['env']['DOCS_RS'], '1')
+        workspace = self.workspaces[0]
+        self.assertEqual(workspace['members'], ['crates/pkg-a-sys', 'crates/pkg-b-sys'])
+        self.assertEqual(workspace['pkg-a-sys']['dependencies']['pkg-b-sys']['path'], '../pkg-b-sys')
+        self.assertEqual(workspace['pkg-b-sys']['package']['version'], '1.2.0')
+
+        self.version.refresh_from_db()
+        self.assertEqual(self.version.rust_docs_path, 'pkg_a_sys/index.html')
+        self.assertEqual(self.version.rust_docs_error, '')
+
+        response = self.client.get(reverse('packages:rust_docs', args=['pkg-a-sys', '1.0.0']))
+        self.assertRedirects(
+            response, reverse('packages:rust_docs_file', args=['pkg-a-sys', '1.0.0', 'pkg_a_sys/index.html'])
+        )
+        response = self.client.get(response['Location'])
+        self.assertEqual(response.status_code, 200)
+        self.assertEqual(response.content, b'<h1>pkg_a_sys</h1>')
+        self.assertTrue(response['Content-Type'].startswith('text/html'))
+        self.assertIn('sandbox', response['Content-Security-Policy'])
+
+        response = self.client.get(reverse('packages:package_detail', args=['pkg_a']))
+        self.assertContains(response, reverse('packages:rust_docs', args=['pkg-a-sys', '1.0.0']))
+
+    def test_docs_paths_are_confined(self):
+        """Test paths outside the version's docs are refused"""
+        with patch('packages.crate_docs.subprocess.run', side_effect=self.fake_cargo_doc):
+            build_docs(self.version)
+
+        url = reverse('packages:rust_docs_file', args=['pkg-a-sys', '1.0.0', 'pkg_a_sys/../../../1.0.0/x'])
+        self.assertEqual(self.client.get(url).status_code, 404)
+        url = reverse('packages:rust_docs_file', args=['pkg-b-sys', '1.0.0', 'search-index.js'])
+        self.assertEqual(self.client.get(url).status_code, 404)
+
+    @override_settings(RUST_DOCS_SANDBOX=[])
+    def test_no_sandbox_runs_nothing(self):
+        """Test crates' build scripts never run outside a sandbox"""
+        with patch('packages.crate_docs.subprocess.run') as run:
+            with self.assertRaisesRegex(DocsError, 'No sandbox configured'):
+                build_docs(self.version)
+        run.assert_not_called()
+
+        self.version.refresh_from_db()
+        self.assertEqual(self.version.rust_docs_path, '')
+        self.assertIn('RUST_DOCS_SANDBOX', self.version.rust_docs_error)
+
+    @override_settings(RUST_DOCS_SANDBOX=['nsjail', '--bindmount', '{build_dir}', '--'])
+    def test_custom_sandbox(self):
+        """Test a configured sandbox command wraps cargo doc"""
+        with patch('packages.crate_docs.subprocess.run', side_effect=self.fake_cargo_doc) as run:
+            build_docs(self.version)
+
+        command = run.call_args.args[0]
+        self.assertEqual(command[:2], ['nsjail', '--bindmount'])
+        self.assertEqual(command[2], str(Path(run.call_args.kwargs['cwd']).parent))
+        self.assertEqual(command[3:5], ['--', 'cargo'])
+
+    def test_failed_build_is_recorded(self):
+        """Test a failing cargo doc raises and is recorded on the version"""
+        failure = subprocess.CompletedProcess([], 101, '', 'error: could not compile `pkg-a-sys`\n')
+        with patch('packages.crate_docs.subprocess.run', return_value=failure):
+            with self.assertRaisesRegex(DocsError, 'could not compile'):
+                build_docs(self.version)
+
+        self.version.refresh_from_db()
+        self.assertEqual(self.version.rust_docs_path, '')
+        self.assertIn('could not compile', self.version.rust_docs_error)
+        response = self.client.get(reverse('packages:rust_docs', args=['pkg-a-sys', '1.0.0']))
+        self.assertEqual(response.status_code, 404)
@@ -0,0 +1,127 @@
+"""
+Tests for server-side Rust crate generation.
+"""
+from django.test import TestCase, Client, override_settings
+from django.urls import reverse
+from django.core.files.uploadedfile import SimpleUploadedFile
+from django.core.management import call_command
+from packages.models import Package, PackageVersion, BinaryPackage
+from packages.crate_generator import find_package_folder
+from pathlib import Path
+import io
+import tarfile
+import tempfile
+
+
+def static_library(symbols):
+    """ar archive with a GNU symbol index listing the given symbols."""
+    names = b''.join(symbol.encode('ascii') + b'\0' for symbol in symbols)
+    index = len(symbols).to_bytes(4, 'big') + b'\0' * 4 * len(symbols) + names
+    header = f"{'/':<16}{0:<12}{0:<6}{0:<6}{644:<8}{len(index):<10}`\n".encode('ascii')
+    return b'!<arch>\n' + header + index + (b'\n' if len(index) % 2 else b'')
+
+
+def binary_tarball(files):
+    """Gzipped tarball of {path: bytes}."""
+    buffer = io.BytesIO()
+    with tarfile.open(fileobj=buffer, mode='w:gz') as tar:
+        for path, data in files.items():
+            info = tarfile.TarInfo(path)
+            info.size = len(data)
+            tar.addfile(info, io.BytesIO(data))
+    return buffer.getvalue()
+
+
+# `conan cache save` layout: export folder next to the package folder
+CACHE_SAVE_FILES = {
+    'pkglist.json': b'{}',
+    'p/gz1234/e/conanfile.py': b'from conan import ConanFile\n\nclass Gz(ConanFile):\n    name = "gz"\n',
+    'p/b/gzabcd/p/conaninfo.txt': b'[settings]\nos=Linux\narch=x86_64\nbuild_type=Release\n',
+    'p/b/gzabcd/p/include/gz.h': b'int gz_add(int a, int b);\n',
+    'p/b/gzabcd/p/lib/libgz.a': static_library(['gz_add']),
+}
+
+
+@override_settings(RUST_CRATE_GENERATION='inline')
+class CrateGenerationTests(TestCase):
+    """Test crates are generated for binaries uploaded without one"""
+
+    def setUp(self):
+        self.client = Client()
+
+    def crate_members(self, binary):
+        binary.rust_crate_file.open('rb')
+        try:
+            with tarfile.open(fileobj=io.BytesIO(binary.rust_crate_file.read()), mode='r:gz') as tar:
+                return tar.getnames()
+        finally:
+            binary.rust_crate_file.close()
+
+    def test_find_package_folder(self):
+        """Test the package folder and exported recipe are found in a cache save tarball"""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            with tarfile.open(fileobj=io.BytesIO(binary_tarball(CACHE_SAVE_FILES)), mode='r:gz') as tar:
+                tar.extractall(tmpdir, filter='data')
+            package_folder, recipe = find_package_folder(tmpdir)
+            self.assertEqual(package_folder, Path(tmpdir) / 'p/b/gzabcd/p')
+            self.assertEqual(recipe, Path(tmpdir) / 'p/gz1234/e/conanfile.py')
+
+    def test_simple_upload_without_crate(self):
+        """Test uploads with --no-rust get a generated crate"""
+        response = self.client.post(reverse('packages:simple_upload'), {
+            'package_name': 'gz',
+            'version': '1.0',
+            'package_id': 'abcd1234',
+            'recipe': SimpleUploadedFile('conanfile.py', CACHE_SAVE_FILES['p/gz1234/e/conanfile.py']),
+            'binary': SimpleUploadedFile('gz.tgz', binary_tarball(CACHE_SAVE_FILES)),
+        })
+        self.assertEqual(response.status_code, 200)
+
+        binary = BinaryPackage.objects.get(package_id='abcd1234')
+        self.assertTrue(binary.rust_crate_file.name.endswith('gz-sys-1.0.0.crate'))
+        self.assertEqual(len(binary.rust_crate_sha256), 64)
+        members = self.crate_members(binary)
+        self.assertIn('gz-sys-1.0.0/native/current/libgz.a', members)
+        self.assertIn('gz-sys-1.0.0/tests/link_smoke.rs', members)
+
+    def test_conan_upload(self):
+        """Test binaries uploaded by Conan clients (bare package folder) get a crate"""
+        package = Package.objects.create(name='gz')
+        PackageVersion.objects.create(package=package, version='1.0')
+        package_files = {
+            path.split('p/b/gzabcd/p/', 1)[1]: data
+            for path, data in CACHE_SAVE_FILES.items() if path.startswith('p/b/gzabcd/p/')
+        }
+
+        url = reverse('packages:api_upload_package', kwargs={
+            'package_name': 'gz', 'package_version': '1.0', 'package_id': 'abcd1234'
+        })
+        response = self.client.post(url, {'file': SimpleUploadedFile('conan_package.tgz', binary_tarball(package_files))})
+        self.assertEqual(response.status_code, 200)
+
+        binary = BinaryPackage.objects.get(package_id='abcd1234')
+        self.assertIn('gz-sys-1.0.0/include/gz.h', self.crate_members(binary))
+
+    @override_settings(RUST_CRATE_GENERATION='off')
+    def test_management_command_backfills(self):
+        """Test the command generates missing crates and reports failures"""
+        package = Package.objects.create(name='gz')
+        version = PackageVersion.objects.create(package=package, version='1.0')
+        good = BinaryPackage.objects.create(
+            package_version=version, package_id='good',
+            binary_file=SimpleUploadedFile('good.tgz', binary_tarball(CACHE_SAVE_FILES))
+        )
+        bad = BinaryPackage.objects.create(
+            package_version=version, package_id='bad',
+            binary_file=SimpleUploadedFile('bad.tgz', binary_tarball({'README': b'no package'}))
+        )
+
+        stdout, stderr = io.StringIO(), io.StringIO()
+        call_command('generate_rust_crates', stdout=stdout, stderr=stderr)
+
+        good.refresh_from_db()
+        bad.refresh_from_db()
+        self.assertTrue(good.rust_crate_file)
+        self.assertFalse(bad.rust_crate_file)
+        self.assertIn('no conaninfo.txt', stderr.getvalue())
+        self.assertIn('Generated 1 crate, 1 failed', stdout.getvalue())
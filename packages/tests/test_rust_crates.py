@@ -5,9 +5,14 @@ from django.test import TestCase, Client
 from django.urls import reverse
 from django.core.files.uploadedfile import SimpleUploadedFile
 from packages.models import Package, PackageVersion, BinaryPackage
+from packages.views.simple_upload import extract_conaninfo
+from packages.crate_bundle import add_path_dependencies
+import io
 import json
 import tarfile
 import tempfile
+import tomllib
+import zipfile
 import os
 
 
@@ -115,6 +120,25 @@ class RustCrateDownloadTests(TestCase):
         self.assertEqual(len(data['dependencies']), 1)
         self.assertEqual(data['dependencies'][0]['name'], 'deplib')
         self.assertEqual(data['dependencies'][0]['version'], '2.0.0')
+        self.assertEqual(data['dependencies'][0]['recipe_revision'], 'hash')
+
+    def test_package_info_api_lock_fields(self):
+        """Test the info API reports what conancrates.lock records"""
+        self.version.recipe_revision = 'rev1'
+        self.version.save()
+        self.binary.rust_crate_sha256 = 'a' * 64
+        self.binary.save()
+
+        url = reverse('packages:package_info_api', kwargs={
+            'package_name': 'testlib',
+            'version': '1.0.0',
+            'package_id': 'abc123'
+        })
+        data = json.loads(self.client.get(url).content)
+
+        self.assertEqual(data['package']['recipe_revision'], 'rev1')
+        self.assertEqual(data['rust_crate']['sha256'], 'a' * 64)
+        self.assertEqual(data['rust_crate']['rust_target'], 'x86_64-unknown-linux-gnu')
 
     def test_package_info_api_no_dependencies(self):
         """Test package info API with no dependencies"""
@@ -186,6 +210,102 @@ class RustCrateDownloadTests(TestCase):
         # Should find the Linux binary
         self.assertEqual(response.status_code, 200)
 
+    def test_rust_crate_by_target(self):
+        """Test selecting the binary by Rust target triple"""
+        windows = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='win123',
+            os='Windows',
+            arch='x86_64',
+            compiler='msvc',
+            compiler_version='193',
+            build_type='Release',
+            rust_target='x86_64-pc-windows-msvc',
+            rust_crate_file=SimpleUploadedFile('testlib-sys-1.0.0.crate', b'windows crate data')
+        )
+        url = reverse('packages:rust_crate_by_settings_api', kwargs={
+            'package_name': 'testlib',
+            'version': '1.0.0'
+        })
+
+        response = self.client.get(url, {'target': 'x86_64-pc-windows-msvc'})
+        self.assertEqual(response.status_code, 200)
+        data = json.loads(response.content)
+        self.assertEqual(data['package']['package_id'], windows.package_id)
+        self.assertEqual(data['settings']['rust_target'], 'x86_64-pc-windows-msvc')
+
+        # self.binary has no recorded rust_target: matched through its settings
+        response = self.client.get(url, {'target': 'x86_64-unknown-linux-gnu'})
+        self.assertEqual(response.status_code, 200)
+        self.assertEqual(json.loads(response.content)['package']['package_id'], 'abc123')
+
+    def test_rust_crate_by_target_no_match(self):
+        """Test 404 for a known target without binaries and 400 for unknown targets"""
+        url = reverse('packages:rust_crate_by_settings_api', kwargs={
+            'package_name': 'testlib',
+            'version': '1.0.0'
+        })
+
+        response = self.client.get(url, {'target': 'aarch64-apple-darwin'})
+        self.assertEqual(response.status_code, 404)
+
+        response = self.client.get(url, {'target': 'sparc-sun-solaris'})
+        self.assertEqual(response.status_code, 400)
+
+
+    def test_rust_crate_by_options(self):
+        """Test selecting between binaries that only differ in options"""
+        self.binary.options = {'shared': 'False'}
+        self.binary.save()
+        shared = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='shared123',
+            os='Linux',
+            arch='x86_64',
+            compiler='gcc',
+            compiler_version='11',
+            build_type='Release',
+            options={'shared': 'True'},
+            rust_crate_file=SimpleUploadedFile('testlib-sys-1.0.0.crate', b'shared crate data')
+        )
+        url = reverse('packages:rust_crate_by_settings_api', kwargs={
+            'package_name': 'testlib',
+            'version': '1.0.0'
+        })
+
+        response = self.client.get(url, {'target': 'x86_64-unknown-linux-gnu', 'option': 'shared=False'})
+        self.assertEqual(json.loads(response.content)['package']['package_id'], 'abc123')
+
+        response = self.client.get(url, {'target': 'x86_64-unknown-linux-gnu', 'option': 'shared=True'})
+        data = json.loads(response.content)
+        self.assertEqual(data['package']['package_id'], shared.package_id)
+        self.assertEqual(data['settings']['options'], {'shared': 'True'})
+
+        response = self.client.get(url, {'option': 'with_ssl=openssl'})
+        self.assertEqual(response.status_code, 404)
+
+    def test_settings_include_cxx_runtime(self):
+        """Test compiler.libcxx and compiler.runtime are read from conaninfo.txt and returned"""
+        conaninfo = (b'[settings]\nos=Windows\narch=x86_64\ncompiler=msvc\ncompiler.version=193\n'
+                     b'compiler.runtime=static\nbuild_type=Release\n')
+        buffer = io.BytesIO()
+        with tarfile.open(fileobj=buffer, mode='w:gz') as tar:
+            info = tarfile.TarInfo('conaninfo.txt')
+            info.size = len(conaninfo)
+            tar.addfile(info, io.BytesIO(conaninfo))
+        settings = extract_conaninfo(io.BytesIO(buffer.getvalue()))
+        self.assertEqual(settings['compiler_runtime'], 'static')
+        self.assertEqual(settings['compiler_libcxx'], '')
+
+        self.binary.compiler_libcxx = 'libstdc++11'
+        self.binary.save()
+        url = reverse('packages:rust_crate_by_settings_api', kwargs={
+            'package_name': 'testlib',
+            'version': '1.0.0'
+        })
+        response = self.client.get(url, {'os': 'Linux'})
+        self.assertEqual(json.loads(response.content)['settings']['compiler_libcxx'], 'libstdc++11')
+
 
 class RustCrateContentTests(TestCase):
     """Test Rust crate file structure and content"""
@@ -356,6 +476,114 @@ class RustCrateDependencyTests(TestCase):
             self.assertTrue(dep['rust_crate_url'].endswith('/rust-crate/'))
 
 
+def make_sys_crate(crate_name, version, dependencies=()):
+    """Build a .crate archive like the CLI: {crate_name}-{version}/ root, normalized Cargo.toml"""
+    cargo_toml = f'[package]\nedition = "2021"\nname = "{crate_name}"\nversion = "{version}"\n'
+    for dep in dependencies:
+        cargo_toml += f'\n[dependencies.{dep}]\nversion = "1.0.0"\n'
+    buffer = io.BytesIO()
+    with tarfile.open(fileobj=buffer, mode='w:gz') as tar:
+        for path, content in {'Cargo.toml': cargo_toml, 'build.rs': 'fn main() {}\n'}.items():
+            info = tarfile.TarInfo(f"{crate_name}-{version}/{path}")
+            info.size = len(content)
+            tar.addfile(info, io.BytesIO(content.encode('utf-8')))
+    return buffer.getvalue()
+
+
+class RustBundleTests(TestCase):
+    """Test the Rust bundle (crate with its dependencies as path dependencies)"""
+
+    def setUp(self):
+        """Create pkg_a -> pkg_b with crates in the CLI's archive format"""
+        pkg_b = Package.objects.create(name='pkg_b')
+        ver_b = PackageVersion.objects.create(package=pkg_b, version='1.0.0')
+        BinaryPackage.objects.create(
+            package_version=ver_b,
+            package_id='b123',
+            rust_crate_file=SimpleUploadedFile('pkg-b-sys-1.0.0.crate', make_sys_crate('pkg-b-sys', '1.0.0'))
+        )
+
+        pkg_a = Package.objects.create(name='pkg_a')
+        ver_a = PackageVersion.objects.create(package=pkg_a, version='1.0.0')
+        BinaryPackage.objects.create(
+            package_version=ver_a,
+            package_id='a123',
+            dependency_graph={
+                'graph': {
+                    'nodes': {
+                        '0': {'ref': 'pkg_a/1.0.0'},
+                        '1': {'ref': 'pkg_b/1.0.0#hash', 'package_id': 'b123'}
+                    }
+                }
+            },
+            rust_crate_file=SimpleUploadedFile(
+                'pkg-a-sys-1.0.0.crate', make_sys_crate('pkg-a-sys', '1.0.0', ['pkg-b-sys'])
+            )
+        )
+
+    def test_bundle_uses_crate_names_as_directories(self):
+        """Test that {name}-{version}/ roots are extracted to crates/<name>/ with path dependencies"""
+        url = reverse('packages:download_rust_bundle', kwargs={
+            'package_name': 'pkg_a',
+            'version': '1.0.0',
+            'package_id': 'a123'
+        })
+
+        response = self.client.get(url)
+
+        self.assertEqual(response.status_code, 200)
+        with zipfile.ZipFile(io.BytesIO(response.content)) as bundle:
+            names = bundle.namelist()
+            cargo_toml = bundle.read('crates/pkg-a-sys/Cargo.toml').decode('utf-8')
+
+        self.assertIn('crates/pkg-b-sys/build.rs', names)
+        self.assertFalse(any('-1.0.0' in name for name in names))
+        self.assertIn('[dependencies.pkg-b-sys]\npath = "../pkg-b-sys"\nversion = "1.0.0"\n', cargo_toml)
+
+    def test_bundle_is_cargo_workspace(self):
+        """Test that the bundle has a workspace Cargo.toml and a Cargo.lock of the members"""
+        url = reverse('packages:download_rust_bundle', kwargs={
+            'package_name': 'pkg_a',
+            'version': '1.0.0',
+            'package_id': 'a123'
+        })
+
+        response = self.client.get(url)
+
+        with zipfile.ZipFile(io.BytesIO(response.content)) as bundle:
+            workspace = tomllib.loads(bundle.read('Cargo.toml').decode('utf-8'))
+            cargo_lock = bundle.read('Cargo.lock').decode('utf-8')
+        self.assertEqual(workspace['workspace']['members'], ['crates/pkg-a-sys', 'crates/pkg-b-sys'])
+
+        lock = tomllib.loads(cargo_lock)
+        self.assertEqual(lock['package'], [
+            {'name': 'pkg-a-sys', 'version': '1.0.0', 'dependencies': ['pkg-b-sys']},
+            {'name': 'pkg-b-sys', 'version': '1.0.0'},
+        ])
+
+    def test_path_dependencies_for_every_dependency_form(self):
+        """Test inline, renamed, build and target-specific dependencies get paths, others are kept"""
+        manifest = tomllib.loads(
+            '[dependencies]\n'
+            'pkg-b-sys = "1.0.0"\n'
+            'b = { package = "pkg-b-sys", version = "1.0.0" }\n'
+            'libc = "0.2"\n'
+            '[build-dependencies.pkg-c-sys]\nversion = "2.0.0"\n'
+            '[target.\'cfg(unix)\'.dependencies]\npkg-c-sys = "2.0.0"\n'
+        )
+
+        used = add_path_dependencies(manifest, {'pkg-b-sys', 'pkg-c-sys'})
+
+        self.assertEqual(used, ['pkg-b-sys', 'pkg-c-sys'])
+        self.assertEqual(manifest['dependencies'], {
+            'pkg-b-sys': {'path': '../pkg-b-sys', 'version': '1.0.0'},
+            'b': {'path': '../pkg-b-sys', 'package': 'pkg-b-sys', 'version': '1.0.0'},
+            'libc': '0.2',
+        })
+        self.assertEqual(manifest['build-dependencies']['pkg-c-sys'], {'path': '../pkg-c-sys', 'version': '2.0.0'})
+        self.assertEqual(manifest['target']['cfg(unix)']['dependencies']['pkg-c-sys']['path'], '../pkg-c-sys')
+
+
 class RustCrateWebUITests(TestCase):
     """Test Rust crate integration in web UI"""
 
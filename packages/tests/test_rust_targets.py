@@ -0,0 +1,105 @@
+"""
+Tests for the Conan settings to Rust target triple mapping.
+"""
+from django.test import TestCase
+from packages.models import Package, PackageVersion, BinaryPackage
+from packages.rust_targets import (
+    RUST_TARGETS,
+    RUST_WINDOWS_TARGETS,
+    rust_target_for_settings,
+    conan_settings_for_rust_target,
+    rust_target_for_binary,
+)
+import conancrates.conancrates as cli
+
+
+class RustTargetMappingTests(TestCase):
+    """Test mapping Conan settings to target triples and back"""
+
+    def test_settings_to_target(self):
+        """Test common configurations"""
+        self.assertEqual(rust_target_for_settings('Linux', 'x86_64', 'gcc'), 'x86_64-unknown-linux-gnu')
+        self.assertEqual(rust_target_for_settings('Linux', 'armv8', 'clang'), 'aarch64-unknown-linux-gnu')
+        self.assertEqual(rust_target_for_settings('Linux', 'armv7hf', 'gcc'), 'armv7-unknown-linux-gnueabihf')
+        self.assertEqual(rust_target_for_settings('Macos', 'armv8', 'apple-clang'), 'aarch64-apple-darwin')
+        self.assertEqual(rust_target_for_settings('Android', 'armv7', 'clang'), 'armv7-linux-androideabi')
+
+    def test_windows_abi_follows_compiler(self):
+        """Test MinGW gcc maps to -gnu and MSVC-compatible compilers to -msvc"""
+        self.assertEqual(rust_target_for_settings('Windows', 'x86_64', 'msvc'), 'x86_64-pc-windows-msvc')
+        self.assertEqual(rust_target_for_settings('Windows', 'x86_64', 'clang'), 'x86_64-pc-windows-msvc')
+        self.assertEqual(rust_target_for_settings('Windows', 'x86_64', 'gcc'), 'x86_64-pc-windows-gnu')
+        self.assertEqual(rust_target_for_settings('Windows', 'x86', 'msvc'), 'i686-pc-windows-msvc')
+
+    def test_unknown_settings(self):
+        """Test configurations without a Rust equivalent"""
+        self.assertIsNone(rust_target_for_settings('Linux', 'sparc', 'gcc'))
+        self.assertIsNone(rust_target_for_settings('SunOS', 'x86_64', 'sun-cc'))
+        self.assertIsNone(rust_target_for_settings('', '', ''))
+
+    def test_target_to_settings(self):
+        """Test the reverse mapping"""
+        self.assertEqual(conan_settings_for_rust_target('x86_64-unknown-linux-gnu'),
+                         {'os': 'Linux', 'arch': 'x86_64'})
+        self.assertEqual(conan_settings_for_rust_target('aarch64-apple-darwin'),
+                         {'os': 'Macos', 'arch': 'armv8'})
+        self.assertEqual(conan_settings_for_rust_target('x86_64-pc-windows-gnu'),
+                         {'os': 'Windows', 'arch': 'x86_64', 'compiler': 'gcc'})
+        self.assertIsNone(conan_settings_for_rust_target('sparc-sun-solaris'))
+
+    def test_round_trip(self):
+        """Test that every known triple maps back to itself"""
+        for target in ['x86_64-unknown-linux-gnu', 'armv7-unknown-linux-gnueabihf',
+                       'x86_64-pc-windows-msvc', 'i686-pc-windows-gnu', 'aarch64-apple-ios',
+                       'wasm32-unknown-emscripten']:
+            with self.subTest(target=target):
+                settings = conan_settings_for_rust_target(target)
+                self.assertEqual(
+                    rust_target_for_settings(settings['os'], settings['arch'], settings.get('compiler')),
+                    target
+                )
+
+
+    def test_matches_cli(self):
+        """Test the server records the triple the CLI names native/<target-triple>/ after"""
+        self.assertEqual(RUST_TARGETS, cli.RUST_TARGETS)
+        self.assertEqual(RUST_WINDOWS_TARGETS, cli.RUST_WINDOWS_TARGETS)
+        settings = [(os_name, arch) for os_name, arch in RUST_TARGETS]
+        settings += [('Windows', arch) for arch, _ in RUST_WINDOWS_TARGETS]
+        for os_name, arch in settings + [('Linux', 'sparc'), ('Windows', 'ppc64')]:
+            for compiler in (None, 'gcc', 'clang', 'msvc', 'apple-clang'):
+                with self.subTest(os=os_name, arch=arch, compiler=compiler):
+                    self.assertEqual(
+                        rust_target_for_settings(os_name, arch, compiler),
+                        cli.conan_settings_to_rust_target({'os': os_name, 'arch': arch, 'compiler': compiler})
+                    )
+
+class BinaryRustTargetTests(TestCase):
+    """Test the rust_target recorded on BinaryPackage"""
+
+    def setUp(self):
+        package = Package.objects.create(name='targetlib')
+        self.version = PackageVersion.objects.create(package=package, version='1.0.0')
+
+    def test_recorded_target_is_used(self):
+        """Test that the stored triple takes precedence"""
+        binary = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='musl123',
+            os='Linux',
+            arch='x86_64',
+            compiler='gcc',
+            rust_target='x86_64-unknown-linux-musl'
+        )
+        self.assertEqual(rust_target_for_binary(binary), 'x86_64-unknown-linux-musl')
+
+    def test_missing_target_is_derived_from_settings(self):
+        """Test binaries uploaded before rust_target was recorded"""
+        binary = BinaryPackage.objects.create(
+            package_version=self.version,
+            package_id='old123',
+            os='Windows',
+            arch='x86_64',
+            compiler='msvc'
+        )
+        self.assertEqual(rust_target_for_binary(binary), 'x86_64-pc-windows-msvc')
@@ -0,0 +1,153 @@
+"""
+Tests for the symbol and header search.
+"""
+from django.test import TestCase, Client, override_settings
+from django.urls import reverse
+from django.core.files.uploadedfile import SimpleUploadedFile
+from django.core.management import call_command
+from packages.models import Package, PackageVersion, BinaryPackage, BinarySymbol
+from packages.symbol_index import header_path, index_binary, search_symbols
+from packages.tests.test_crate_generator import CACHE_SAVE_FILES, binary_tarball, static_library
+import io
+
+
+def ssl_tarball(symbols):
+    """Bare package folder of an OpenSSL-like binary."""
+    return binary_tarball({
+        'conaninfo.txt': b'[settings]\nos=Linux\narch=x86_64\n',
+        'include/openssl/ssl.h': b'SSL_CTX *SSL_CTX_new(const SSL_METHOD *method);\n',
+        'include/openssl/opensslv.h': b'',
+        'lib/libssl.a': static_library(symbols),
+        # Import library entries of a DLL export, which aren't what users search for
+        'lib/ssl.lib': static_library(['SSL_free', '__imp_SSL_free', '__NULL_IMPORT_DESCRIPTOR']),
+    })
+
+
+class SymbolIndexTests(TestCase):
+    """Test the symbols and headers of binaries are indexed and searched"""
+
+    def setUp(self):
+        self.client = Client()
+        package = Package.objects.create(name='openssl')
+        version = PackageVersion.objects.create(package=package, version='3.2.0')
+        self.linux = BinaryPackage.objects.create(
+            package_version=version, package_id='linux123', os='Linux', arch='x86_64',
+            binary_file=SimpleUploadedFile('linux.tgz', ssl_tarball(['SSL_CTX_new', 'SSL_free']))
+        )
+        self.macos = BinaryPackage.objects.create(
+            package_version=version, package_id='macos123', os='Macos', arch='armv8',
+            binary_file=SimpleUploadedFile('macos.tgz', ssl_tarball(['_SSL_CTX_new']))
+        )
+        index_binary(self.linux)
+        index_binary(self.macos)
+
+    def test_index_binary(self):
+        """Test library symbols and include/ headers are recorded, without import stubs"""
+        symbols = set(self.linux.symbols.filter(kind='symbol').values_list('name', 'library'))
+        self.assertEqual(symbols, {('SSL_CTX_new', 'libssl.a'), ('SSL_free', 'libssl.a'), ('SSL_free', 'ssl.lib')})
+        headers = set(self.linux.symbols.filter(kind='header').values_list('name', 'basename'))
+        self.assertEqual(headers, {('openssl/ssl.h', 'ssl.h'), ('openssl/opensslv.h', 'opensslv.h')})
+        self.assertFalse(self.linux.symbols.filter(kind='symbol').exclude(basename='').exists())
+
+        # Reindexing replaces the previous entries
+        self.assertEqual(index_binary(self.linux), (3, 2))
+        self.assertEqual(self.linux.symbols.count(), 5)
+
+    def test_header_queries(self):
+        """Test header searches are recognized in the forms users paste them"""
+        self.assertEqual(header_path('#include <zlib.h>'), 'zlib.h')
+        self.assertEqual(header_path('"openssl/ssl.h"'), 'openssl/ssl.h')
+        self.assertEqual(header_path('Eigen/Dense'), 'Eigen/Dense')
+        self.assertIsNone(header_path('SSL_CTX_new'))
+
+    def test_search_symbol(self):
+        """Test symbols match with or without Mach-O's leading underscore"""
+        kind, matches = search_symbols('SSL_CTX_new')
+        self.assertEqual(kind, 'symbol')
+        self.assertEqual({match.binary.package_id for match in matches}, {'linux123', 'macos123'})
+
+        _, matches = search_symbols('SSL_CTX_new', os='macos')
+        self.assertEqual([match.name for match in matches], ['_SSL_CTX_new'])
+        _, matches = search_symbols('SSL_CTX_new', arch='x86_64')
+        self.assertEqual([match.binary.package_id for match in matches], ['linux123'])
+        self.assertFalse(search_symbols('SSL_CTX')[1].exists())
+
+    def test_search_header(self):
+        """Test headers match by path or by path suffix"""
+        kind, matches = search_symbols('<openssl/ssl.h>')
+        self.assertEqual(kind, 'header')
+        self.assertEqual(matches.count(), 2)
+        self.assertEqual(search_symbols('ssl.h')[1].count(), 2)
+        self.assertFalse(search_symbols('l/ssl.h')[1].exists())
+
+    def test_search_api(self):
+        """Test the API lists the package, version and binary providing a symbol"""
+        response = self.client.get(reverse('packages:symbol_search_api'), {'q': 'SSL_CTX_new', 'os': 'Linux'})
+        self.assertEqual(response.status_code, 200)
+        data = response.json()
+        self.assertEqual(data['kind'], 'symbol')
+        self.assertFalse(data['truncated'])
+        self.assertEqual(len(data['results']), 1)
+        result = data['results'][0]
+        self.assertEqual(result['package'], 'openssl')
+        self.assertEqual(result['version'], '3.2.0')
+        self.assertEqual(result['package_id'], 'linux123')
+        self.assertEqual(result['library'], 'libssl.a')
+        self.assertEqual(result['url'], '/packages/openssl/?version=3.2.0')
+
+        response = self.client.get(reverse('packages:symbol_search_api'))
+        self.assertEqual(response.status_code, 400)
+
+    def test_search_page(self):
+        """Test the search page shows the binaries providing a header"""
+        response = self.client.get(reverse('packages:symbol_search'), {'q': '#include <openssl/ssl.h>'})
+        self.assertEqual(response.status_code, 200)
+        self.assertContains(response, 'openssl/3.2.0')
+        self.assertContains(response, 'linux123')
+        self.assertContains(response, 'macos123')
+
+        response = self.client.get(reverse('packages:symbol_search'), {'q': 'missing_symbol'})
+        self.assertContains(response, 'No package provides this symbol')
+
+
+@override_settings(SYMBOL_INDEXING='inline', RUST_CRATE_GENERATION='off')
+class SymbolIndexUploadTests(TestCase):
+    """Test binaries are indexed when uploaded"""
+
+    def test_simple_upload(self):
+        """Test uploaded binaries are indexed from their tarball"""
+        response = Client().post(reverse('packages:simple_upload'), {
+            'package_name': 'gz',
+            'version': '1.0',
+            'package_id': 'abcd1234',
+            'recipe': SimpleUploadedFile('conanfile.py', CACHE_SAVE_FILES['p/gz1234/e/conanfile.py']),
+            'binary': SimpleUploadedFile('gz.tgz', binary_tarball(CACHE_SAVE_FILES)),
+        })
+        self.assertEqual(response.status_code, 200)
+
+        binary = BinaryPackage.objects.get(package_id='abcd1234')
+        self.assertEqual(
+            set(binary.symbols.values_list('kind', 'name')),
+            {('symbol', 'gz_add'), ('header', 'gz.h')}
+        )
+
+    @override_settings(SYMBOL_INDEXING='off')
+    def test_management_command_backfills(self):
+        """Test the command indexes binaries without symbols"""
+        package = Package.objects.create(name='gz')
+        version = PackageVersion.objects.create(package=package, version='1.0')
+        BinaryPackage.objects.create(
+            package_version=version, package_id='gz1',
+            binary_file=SimpleUploadedFile('gz.tgz', binary_tarball(CACHE_SAVE_FILES))
+        )
+        BinaryPackage.objects.create(
+            package_version=version, package_id='gz2',
+            binary_file=SimpleUploadedFile('broken.tgz', b'not a tarball')
+        )
+
+        out, err = io.StringIO(), io.StringIO()
+        call_command('index_symbols', stdout=out, stderr=err)
+
+        self.assertEqual(BinarySymbol.objects.filter(binary__package_id='gz1').count(), 2)
+        self.assertIn('Indexed 1 binary, 1 failed', out.getvalue())
+        self.assertIn('gz2', err.getvalue())
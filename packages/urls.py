@@ -1,6 +1,6 @@
 from django.urls import path
 from . import views
-from .views import upload_views, simple_upload
+from .views import upload_views, simple_upload, cargo_views, docs_views, symbol_views
 
 app_name = 'packages'
 
@@ -11,6 +11,7 @@ urlpatterns = [
     path('packages/<str:package_name>/', views.package_detail, name='package_detail'),
     path('topics/', views.topic_list, name='topic_list'),
     path('topics/<slug:slug>/', views.topic_detail, name='topic_detail'),
+    path('symbols/', symbol_views.symbol_search, name='symbol_search'),
 
     # Download endpoints
     # Conan format downloads (for Conan users)
@@ -38,6 +39,22 @@ urlpatterns = [
          views.download_views.get_package_info_api, name='package_info_api'),
     path('api/packages/<str:package_name>/<str:version>/rust-crate',
          views.download_views.get_rust_crate_by_settings_api, name='rust_crate_by_settings_api'),
+    path('api/packages/<str:package_name>/<str:version>/rust-crate/merge',
+         cargo_views.merge_crates, name='merge_rust_crates'),
+    path('api/symbols', symbol_views.symbol_search_api, name='symbol_search_api'),
+
+    # Cargo sparse registry index
+    path('cargo/index/config.json', cargo_views.sparse_index_config, name='cargo_index_config'),
+    path('cargo/index/<path:index_path>', cargo_views.sparse_index_file, name='cargo_index_file'),
+
+    # Cargo Web API (cargo publish)
+    path('api/v1/crates/new', cargo_views.publish_crate, name='cargo_publish'),
+    path('api/v1/crates/<str:crate_name>/<str:version>/download',
+         cargo_views.download_crate, name='cargo_download'),
+
+    # Rustdoc of the registry's crates (see packages/crate_docs.py)
+    path('docs/<str:crate_name>/<str:version>/', docs_views.rust_docs, name='rust_docs'),
+    path('docs/<str:crate_name>/<str:version>/<path:path>', docs_views.rust_docs, name='rust_docs_file'),
 
     # Other downloads
     path('packages/<str:package_name>/<str:version>/manifest/',
@@ -0,0 +1,329 @@
+"""
+Cargo registry views for ConanCrates
+
+Serves a Cargo sparse index so generated -sys crates can be consumed with:
+
+    # .cargo/config.toml
+    [registries.conancrates]
+    index = "sparse+http://localhost:8000/cargo/index/"
+
+and implements the parts of the Cargo Web API needed by cargo publish.
+"""
+import json
+import hashlib
+from django.db import transaction
+from django.http import JsonResponse, HttpResponse, Http404
+from django.shortcuts import get_object_or_404
+from django.views.decorators.csrf import csrf_exempt
+from django.views.decorators.http import require_http_methods
+from django.core.files.base import ContentFile
+from packages.models import Package, PackageVersion, BinaryPackage
+from packages.cargo_registry import (
+    PublishError,
+    can_publish_to_package,
+    crate_name_for_package,
+    find_package_for_crate,
+    find_version_for_crate,
+    find_version_for_publish,
+    is_generated_crate,
+    is_published_package,
+    published_package_name,
+    sparse_index_path,
+    select_crate_binary,
+    has_merged_crate,
+    build_index_entries,
+    record_crates_indexed,
+    parse_publish_payload,
+    validate_publish_metadata,
+    published_crate_metadata,
+    user_for_cargo_token,
+)
+from packages.cargo_versions import cargo_version_for
+from packages.crate_merge import MergeError, merge_rust_crates, store_merged_crate
+from packages.crate_docs import schedule_docs_build
+
+
+def cargo_error(detail, status=400):
+    """Error response in the format cargo displays to the user"""
+    return JsonResponse({'errors': [{'detail': detail}]}, status=status)
+
+
+def get_registry_base_url(request):
+    """Absolute server URL without trailing slash (e.g., http://localhost:8000)"""
+    return request.build_absolute_uri('/').rstrip('/')
+
+
+def publishing_user(request):
+    """
+    User publishing a crate: the owner of the token cargo sends in the
+    Authorization header, or the signed-in user (e.g., curl with a session).
+
+    Returns:
+        User or None
+    """
+    if 'HTTP_AUTHORIZATION' in request.META:
+        return user_for_cargo_token(request.META['HTTP_AUTHORIZATION'])
+    return request.user if request.user.is_authenticated else None
+
+
+@require_http_methods(["GET", "HEAD"])
+def sparse_index_config(request):
+    """
+    Sparse index configuration (config.json).
+
+    - dl: Base URL for crate downloads ({dl}/{crate}/{version}/download)
+    - api: Base URL for the Web API (cargo publish)
+    """
+    base_url = get_registry_base_url(request)
+    return JsonResponse({
+        'dl': f"{base_url}/api/v1/crates",
+        'api': base_url,
+    })
+
+
+@require_http_methods(["GET", "HEAD"])
+def sparse_index_file(request, index_path):
+    """
+    Index file for a single crate: one JSON object per line, one line per version.
+
+    URL: /cargo/index/{1|2|3/a|ab/cd}/{crate_name}
+    """
+    crate_name = index_path.rsplit('/', 1)[-1]
+
+    # Reject paths that don't match the crate's place in the 1/2/3/xx layout
+    if index_path.lower() != sparse_index_path(crate_name):
+        return HttpResponse("Not found", status=404, content_type='text/plain')
+
+    package = find_package_for_crate(crate_name)
+    if not package:
+        return HttpResponse(f"Crate {crate_name} not found", status=404, content_type='text/plain')
+
+    entries = build_index_entries(package, crate_name)
+    if not entries:
+        return HttpResponse(f"No versions of {crate_name} have a Rust crate", status=404, content_type='text/plain')
+
+    record_crates_indexed(package_version for package_version, _ in entries)
+    content = ''.join(json.dumps(entry, separators=(',', ':')) + '\n' for _, entry in entries)
+    return HttpResponse(content, content_type='text/plain; charset=utf-8')
+
+
+@csrf_exempt
+@require_http_methods(["PUT"])
+def publish_crate(request):
+    """
+    Cargo Web API publish endpoint (cargo publish --registry conancrates).
+
+    URL: /api/v1/crates/new
+
+    The archive's Cargo.toml must match the metadata, which the index serves.
+    cargo sends the token of cargo login (create_cargo_token()) in the
+    Authorization header; it identifies the publisher (publishing_user()).
+    Versions of an existing crate can only be published by staff or one of
+    the package's uploaders (can_publish_to_package()). The -sys crate of a
+    Conan package is attached to the binaries of the matching PackageVersion
+    that don't have a crate yet. Other crates (e.g., safe wrappers around a
+    -sys crate) are stored on a source-only BinaryPackage of their own package
+    (published_package_name()), even when named like a Conan package, which
+    their first publisher owns.
+    """
+    user = publishing_user(request)
+    if user is None:
+        return cargo_error(
+            "authentication required: run `cargo login --registry conancrates` with a token "
+            "from `python manage.py create_cargo_token <username>`",
+            status=401
+        )
+
+    try:
+        metadata, crate_bytes = parse_publish_payload(request.body)
+        validate_publish_metadata(metadata, crate_bytes)
+    except PublishError as e:
+        return cargo_error(str(e))
+
+    crate_name = metadata['name']
+    crate_version = metadata['vers']
+    crate_sha256 = hashlib.sha256(crate_bytes).hexdigest()
+
+    with transaction.atomic():
+        package = find_package_for_crate(crate_name)
+        if package and not can_publish_to_package(user, package):
+            if is_published_package(package):
+                detail = f"only staff and the publishers of `{crate_name}` can publish new versions of it"
+            else:
+                detail = (f"`{crate_name}` is the crate of the Conan package {package.name}: "
+                          f"only staff and the package's uploaders can publish it")
+            return cargo_error(detail, status=403)
+        if not package:
+            package = Package.objects.create(
+                name=published_package_name(crate_name),
+                description=metadata.get('description') or '',
+                license=metadata.get('license') or '',
+                homepage=metadata.get('homepage') or '',
+            )
+
+        package_version = find_version_for_publish(package, crate_version)
+        if package_version is None:
+            package_version = PackageVersion.objects.create(
+                package=package,
+                version=crate_version,
+                cargo_version=crate_version,
+                description=metadata.get('description') or '',
+                uploaded_by=user
+            )
+
+        # Crate versions are immutable once published
+        if select_crate_binary(package_version, crate_name):
+            return cargo_error(f"crate version `{crate_name}@{crate_version}` is already uploaded", status=409)
+
+        targets = []
+        if is_generated_crate(package, crate_name):
+            targets = [binary for binary in package_version.binaries.all() if not binary.rust_crate_file]
+
+        if not targets:
+            # Source-only binary: no Conan settings, placeholder package_id
+            package_id = hashlib.md5(f"cargo:{crate_name}/{crate_version}".encode()).hexdigest()[:16]
+            binary, _ = BinaryPackage.objects.get_or_create(
+                package_version=package_version,
+                package_id=package_id
+            )
+            targets = [binary]
+
+        for binary in targets:
+            binary.rust_crate_file.save(
+                f"{crate_name}-{crate_version}.crate",
+                ContentFile(crate_bytes),
+                save=False
+            )
+            binary.rust_crate_sha256 = crate_sha256
+            binary.rust_crate_metadata = published_crate_metadata(metadata)
+            binary.save()
+
+    schedule_docs_build(package_version)
+
+    return JsonResponse({
+        'warnings': {
+            'invalid_categories': [],
+            'invalid_badges': [],
+            'other': []
+        }
+    })
+
+
+@require_http_methods(["GET", "HEAD"])
+def download_crate(request, crate_name, version):
+    """
+    Cargo download endpoint (the "dl" URL in config.json).
+
+    URL: /api/v1/crates/{crate_name}/{version}/download
+
+    Serves the merged multi-target crate if the version has one, otherwise
+    the archive of the binary selected by select_crate_binary() - always the
+    one whose checksum is published in the index. The bytes are hashed
+    before being returned so a stored file that no longer matches the index
+    fails here instead of in Cargo's checksum check.
+    """
+    package = find_package_for_crate(crate_name)
+    if not package:
+        raise Http404(f"Crate {crate_name} not found")
+
+    package_version = find_version_for_crate(package, version, crate_name)
+    binary = select_crate_binary(package_version, crate_name) if package_version else None
+    if not binary:
+        raise Http404(f"Crate {crate_name} version {version} not found")
+
+    if has_merged_crate(package_version, crate_name):
+        crate_owner = package_version
+    else:
+        crate_owner = binary
+
+    crate_owner.rust_crate_file.open('rb')
+    try:
+        crate_bytes = crate_owner.rust_crate_file.read()
+    finally:
+        crate_owner.rust_crate_file.close()
+
+    crate_sha256 = hashlib.sha256(crate_bytes).hexdigest()
+    if crate_owner.rust_crate_sha256 and crate_owner.rust_crate_sha256 != crate_sha256:
+        return HttpResponse(
+            f"Checksum mismatch for {crate_name} {version}: "
+            f"index has {crate_owner.rust_crate_sha256}, stored file has {crate_sha256}",
+            status=500,
+            content_type='text/plain'
+        )
+
+    # Record checksum for crates uploaded before it was tracked
+    if not crate_owner.rust_crate_sha256:
+        crate_owner.rust_crate_sha256 = crate_sha256
+        crate_owner.save(update_fields=['rust_crate_sha256'])
+
+    record_crates_indexed([package_version])
+
+    # Increment download count (merged crates aren't any single binary's download)
+    if crate_owner is binary:
+        binary.download_count += 1
+        binary.save(update_fields=['download_count'])
+
+    package.download_count += 1
+    package.save(update_fields=['download_count'])
+
+    response = HttpResponse(crate_bytes, content_type='application/gzip')
+    response['Content-Disposition'] = f'attachment; filename="{crate_name}-{version}.crate"'
+    return response
+
+
+@csrf_exempt
+@require_http_methods(["POST"])
+def merge_crates(request, package_name, version):
+    """
+    Merge the generated -sys crates of every binary of a package version
+    into one multi-target crate (conancrates merge-rust-crates).
+
+    URL: /api/packages/{package_name}/{version}/rust-crate/merge
+
+    Once merged, the index and download endpoints serve the merged crate.
+    It's immutable like any published crate version: merging again is
+    refused until the merged crate is removed (e.g., in the admin). Merging
+    is also refused once Cargo got a per-binary crate of the version (from
+    the index or the download endpoint): the merged crate's checksum would
+    break every Cargo.lock that recorded the per-binary one.
+    """
+    package = get_object_or_404(Package, name=package_name)
+    package_version = get_object_or_404(PackageVersion, package=package, version=version)
+
+    if package_version.rust_crate_file:
+        return JsonResponse({
+            'error': f"{package_version} already has a merged Rust crate",
+            'targets': package_version.rust_crate_targets,
+            'features': package_version.rust_crate_features,
+            'sha256': package_version.rust_crate_sha256
+        }, status=409)
+
+    if package_version.rust_crate_indexed_at:
+        return JsonResponse({
+            'error': (
+                f"{package_version} was already served to Cargo on "
+                f"{package_version.rust_crate_indexed_at:%Y-%m-%d %H:%M} UTC: merging would change the checksum "
+                f"Cargo.lock files recorded. Merge a new version before it's used"
+            ),
+            'indexed_at': package_version.rust_crate_indexed_at.isoformat(),
+        }, status=409)
+
+    try:
+        crate_bytes, targets, features, skipped = merge_rust_crates(package_version)
+    except MergeError as e:
+        return JsonResponse({'error': str(e)}, status=400)
+
+    store_merged_crate(package_version, crate_bytes, targets, features)
+    # The merged crate is now the one served: document it instead
+    schedule_docs_build(package_version, rebuild=True)
+
+    return JsonResponse({
+        'success': True,
+        'crate_name': crate_name_for_package(package.name),
+        'version': version,
+        'cargo_version': cargo_version_for(package_version),
+        'targets': targets,
+        'features': features,
+        'skipped_binaries': skipped,
+        'sha256': package_version.rust_crate_sha256
+    })
@@ -0,0 +1,51 @@
+"""
+Rustdoc hosting: the HTML built by packages/crate_docs.py, served at /docs/<crate>/<version>/
+"""
+import mimetypes
+from django.core.files.storage import default_storage
+from django.http import Http404, HttpResponse
+from django.shortcuts import redirect
+from django.views.decorators.http import require_http_methods
+from packages.cargo_registry import find_package_for_crate, find_version_for_crate
+from packages.cargo_versions import cargo_version_for
+from packages.crate_docs import docs_storage_dir, served_crate
+
+
+@require_http_methods(["GET", "HEAD"])
+def rust_docs(request, crate_name, version, path=''):
+    """
+    Serve a file of a crate version's rustdoc.
+
+    URL: /docs/{crate_name}/{version}/{path}
+
+    The version root redirects to the crate's entry page. Docs contain HTML
+    written by crate authors, so pages are sandboxed into their own origin:
+    their scripts (rustdoc's search) run, but can't reach the registry's
+    cookies or pages.
+    """
+    package = find_package_for_crate(crate_name)
+    package_version = find_version_for_crate(package, version, crate_name) if package else None
+    served = served_crate(package_version) if package_version and package_version.rust_docs_path else None
+    if not served or served[0].lower() != crate_name.lower():
+        raise Http404(f"No docs for {crate_name} {version}")
+    crate_name, crate_version = served[0], cargo_version_for(package_version)
+
+    if not path:
+        return redirect('packages:rust_docs_file', crate_name, crate_version, package_version.rust_docs_path)
+    if path.endswith('/'):
+        path += 'index.html'
+    if any(part in ('', '.', '..') for part in path.split('/')):
+        raise Http404(f"Invalid docs path: {path}")
+
+    storage_path = f"{docs_storage_dir(crate_name, crate_version)}/{path}"
+    try:
+        with default_storage.open(storage_path, 'rb') as f:
+            content = f.read()
+    except OSError:
+        raise Http404(f"{path} not found in the docs of {crate_name} {version}")
+
+    content_type, _ = mimetypes.guess_type(path)
+    response = HttpResponse(content, content_type=content_type or 'application/octet-stream')
+    response['Content-Security-Policy'] = 'sandbox allow-scripts allow-popups allow-forms'
+    response['X-Content-Type-Options'] = 'nosniff'
+    return response
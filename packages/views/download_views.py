@@ -1,6 +1,7 @@
 """
 Views for downloading packages and binaries
 """
+import io
 import os
 import json
 import zipfile
@@ -11,6 +12,10 @@ from pathlib import Path
 from django.shortcuts import get_object_or_404
 from django.http import FileResponse, JsonResponse, HttpResponse
 from packages.models import Package, PackageVersion, BinaryPackage
+from packages.rust_targets import rust_target_for_binary, conan_settings_for_rust_target
+from packages.cargo_versions import cargo_version, version_requirement, cargo_version_for
+from packages.cargo_registry import registry_targets
+from packages.crate_bundle import build_bundle
 from packages.conan_wrapper import (
     resolve_dependencies,
     check_conan_available,
@@ -755,7 +760,11 @@ def list_available_binaries(request, package_name, version):
             'arch': binary.arch,
             'compiler': binary.compiler,
             'compiler_version': binary.compiler_version,
+            'compiler_libcxx': binary.compiler_libcxx,
+            'compiler_runtime': binary.compiler_runtime,
             'build_type': binary.build_type,
+            'rust_target': rust_target_for_binary(binary),
+            'options': binary.options or {},
             'package_id': binary.package_id,
             'file_size': binary.file_size,
             'download_count': binary.download_count,
@@ -1038,7 +1047,7 @@ def download_rust_crate(request, package_name, version, package_id):
 
     # Return the .crate file
     response = HttpResponse(binary.rust_crate_file.read(), content_type='application/gzip')
-    crate_name = f"{package_name.replace('_', '-')}-sys-{version}.crate"
+    crate_name = f"{package_name.replace('_', '-')}-sys-{cargo_version_for(package_version)}.crate"
     response['Content-Disposition'] = f'attachment; filename="{crate_name}"'
     return response
 
@@ -1046,10 +1055,8 @@ def download_rust_crate(request, package_name, version, package_id):
 def download_rust_bundle(request, package_name, version, package_id):
     """
     Download a bundle containing the requested Rust crate and all its dependencies.
-    Returns a .zip file with all crate directories and path dependencies configured.
+    Returns a .zip file laid out as a Cargo workspace (see packages/crate_bundle.py).
     """
-    import re
-    
     package = get_object_or_404(Package, name=package_name)
     package_version = get_object_or_404(PackageVersion, package=package, version=version)
     binary = get_object_or_404(BinaryPackage, package_version=package_version, package_id=package_id)
@@ -1094,119 +1101,73 @@ def download_rust_bundle(request, package_name, version, package_id):
                 'package_id': dep_package_id
             })
 
-    # Create temp directory
-    temp_dir = tempfile.mkdtemp()
-    try:
-        crates_dir = os.path.join(temp_dir, 'crates')
-        os.makedirs(crates_dir)
-
-        # Download and extract main crate
-        main_crate_name = f"{package_name.replace('_', '-')}-sys"
-        main_crate_file = f"{main_crate_name}-{version}.crate"
-        main_crate_path = os.path.join(crates_dir, main_crate_file)
-
-        with open(main_crate_path, 'wb') as f:
-            binary.rust_crate_file.seek(0)
-            f.write(binary.rust_crate_file.read())
-
-        with tarfile.open(main_crate_path, 'r:gz') as tar:
-            tar.extractall(crates_dir)
-        os.remove(main_crate_path)
-
-        # Download and extract dependency crates
-        dep_crate_names = []
-        for dep in dependencies:
-            dep_pkg = Package.objects.filter(name=dep['name']).first()
-            if not dep_pkg:
-                continue
-            dep_ver = PackageVersion.objects.filter(package=dep_pkg, version=dep['version']).first()
-            if not dep_ver:
-                continue
-            dep_bin = BinaryPackage.objects.filter(
-                package_version=dep_ver, package_id=dep['package_id']
-            ).first()
-            if not dep_bin or not dep_bin.rust_crate_file:
-                continue
-
-            dep_crate_name = f"{dep['name'].replace('_', '-')}-sys"
-            dep_crate_file = f"{dep_crate_name}-{dep['version']}.crate"
-            dep_crate_path = os.path.join(crates_dir, dep_crate_file)
+    # Requested crate first, then the crates of its dependencies
+    main_crate_name = f"{package_name.replace('_', '-')}-sys"
+    crates = [(main_crate_name, read_crate_file(binary.rust_crate_file))]
+    for dep in dependencies:
+        dep_bin = BinaryPackage.objects.filter(
+            package_version__package__name=dep['name'],
+            package_version__version=dep['version'],
+            package_id=dep['package_id']
+        ).first()
+        if not dep_bin or not dep_bin.rust_crate_file:
+            continue
+        crates.append((f"{dep['name'].replace('_', '-')}-sys", read_crate_file(dep_bin.rust_crate_file)))
 
-            with open(dep_crate_path, 'wb') as f:
-                dep_bin.rust_crate_file.seek(0)
-                f.write(dep_bin.rust_crate_file.read())
+    dep_crate_names = [name for name, _ in crates[1:]]
+    readme = f"""# Rust Crate Bundle: {main_crate_name}
 
-            with tarfile.open(dep_crate_path, 'r:gz') as tar:
-                tar.extractall(crates_dir)
-            os.remove(dep_crate_path)
+This bundle is a Cargo workspace with {main_crate_name} and all its dependencies.
 
-            dep_crate_names.append(dep_crate_name)
+## Contents
+- Cargo.toml - Workspace listing every crate
+- Cargo.lock - Locked versions of the crates
+- crates/{main_crate_name}/ - Main crate
+"""
+    readme += ''.join(f"- crates/{dep}/ - Dependency\n" for dep in dep_crate_names)
+    readme += f"""
+Dependencies between the crates are path dependencies, so nothing is
+downloaded from a registry.
 
-        # Update Cargo.toml files to use path dependencies
-        for crate_dir_name in [main_crate_name] + dep_crate_names:
-            cargo_toml = os.path.join(crates_dir, crate_dir_name, 'Cargo.toml')
-            if not os.path.exists(cargo_toml):
-                continue
-                
-            with open(cargo_toml, 'r') as f:
-                content = f.read()
+## Usage
 
-            # Replace simple version dependencies with path dependencies
-            for dep_name in dep_crate_names:
-                pattern = f'{re.escape(dep_name)} = "([^"]+)"'
-                replacement = f'{dep_name} = {{ version = "\1", path = "../{dep_name}" }}'
-                content = re.sub(pattern, replacement, content)
+Build everything:
 
-            with open(cargo_toml, 'w') as f:
-                f.write(content)
+    cargo build --workspace
 
-        # Create README
-        with open(os.path.join(crates_dir, 'README.md'), 'w') as f:
-            f.write(f"""# Rust Crate Bundle: {main_crate_name}
+Run the link smoke tests of the crates that have one (tests/link_smoke.rs):
 
-This bundle contains {main_crate_name} and all its dependencies.
+    cargo test --workspace
 
-## Contents
-- {main_crate_name}/ - Main crate
-""")
-            for dep in dep_crate_names:
-                f.write(f"- {dep}/ - Dependency\n")
-            f.write(f"""
-## Usage
+To use the crates from your own project, add this bundle's crates as path
+dependencies in your Cargo.toml:
 
-1. Extract this bundle
-2. Copy the crates/ directory to your project
-3. In your Cargo.toml, add:
-   ```toml
-   [dependencies]
-   {main_crate_name} = {{ version = "{version}", path = "crates/{main_crate_name}" }}
-   ```
-4. Run: cargo build
+```toml
+[dependencies]
+{main_crate_name} = {{ version = "{version_requirement(cargo_version_for(package_version))}", path = "<bundle>/crates/{main_crate_name}" }}
+```
 
 Generated by ConanCrates
-""")
+"""
 
-        # Create zip
-        zip_path = os.path.join(temp_dir, f"{main_crate_name}-bundle.zip")
-        with zipfile.ZipFile(zip_path, 'w', zipfile.ZIP_DEFLATED) as zipf:
-            for root, dirs, files in os.walk(crates_dir):
-                for file in files:
-                    file_path = os.path.join(root, file)
-                    arcname = os.path.relpath(file_path, temp_dir)
-                    zipf.write(file_path, arcname)
+    bundle = build_bundle(crates, readme)
 
-        # Increment download count
-        binary.download_count += 1
-        binary.save(update_fields=['download_count'])
+    # Increment download count
+    binary.download_count += 1
+    binary.save(update_fields=['download_count'])
 
-        # Return zip
-        with open(zip_path, 'rb') as f:
-            response = HttpResponse(f.read(), content_type='application/zip')
-            response['Content-Disposition'] = f'attachment; filename="{main_crate_name}-bundle.zip"'
-            return response
+    response = HttpResponse(bundle, content_type='application/zip')
+    response['Content-Disposition'] = f'attachment; filename="{main_crate_name}-bundle.zip"'
+    return response
 
+
+def read_crate_file(crate_file):
+    """Read a stored .crate file."""
+    crate_file.open('rb')
+    try:
+        return crate_file.read()
     finally:
-        shutil.rmtree(temp_dir, ignore_errors=True)
+        crate_file.close()
 
 
 def get_package_info_api(request, package_name, version, package_id):
@@ -1231,14 +1192,16 @@ def get_package_info_api(request, package_name, version, package_id):
             if '/' not in ref:
                 continue
             dep_name, dep_version_with_hash = ref.split('/', 1)
-            dep_version = dep_version_with_hash.split('#')[0]
+            dep_version, _, dep_revision = dep_version_with_hash.partition('#')
             dep_package_id = node.get('package_id')
             
             if dep_package_id:
                 dependencies.append({
                     'name': dep_name,
                     'version': dep_version,
+                    'cargo_version': cargo_version(dep_version),
                     'package_id': dep_package_id,
+                    'recipe_revision': dep_revision.split('%')[0],
                     'rust_crate_url': f"/packages/{dep_name}/{dep_version}/binaries/{dep_package_id}/rust-crate/"
                 })
 
@@ -1246,11 +1209,17 @@ def get_package_info_api(request, package_name, version, package_id):
         'package': {
             'name': package_name,
             'version': version,
-            'package_id': package_id
+            'package_id': package_id,
+            'recipe_revision': package_version.recipe_revision
         },
         'rust_crate': {
             'available': bool(binary.rust_crate_file),
             'crate_name': f"{package_name.replace('_', '-')}-sys",
+            'cargo_version': cargo_version_for(package_version),
+            'sha256': binary.rust_crate_sha256,
+            'rust_target': rust_target_for_binary(binary),
+            # Targets of the crate the Cargo registry serves for this version (merged, or another binary's)
+            'registry_targets': registry_targets(package_version),
             'download_url': f"/packages/{package_name}/{version}/binaries/{package_id}/rust-crate/"
         },
         'dependencies': dependencies
@@ -1263,14 +1232,23 @@ def get_rust_crate_by_settings_api(request, package_name, version):
     """
     API endpoint to get Rust crate download URL by platform settings.
     Query params: os, arch, compiler, compiler_version, build_type
+    or target (Rust target triple, e.g. x86_64-unknown-linux-gnu),
+    and option (repeatable, e.g. option=shared=True)
     Returns the matching package_id and download URL.
     """
     # Get settings from query params
+    target = request.GET.get('target')
     os_name = request.GET.get('os')
     arch = request.GET.get('arch')
     compiler = request.GET.get('compiler')
     compiler_version = request.GET.get('compiler_version')
     build_type = request.GET.get('build_type')
+    options = dict(option.split('=', 1) for option in request.GET.getlist('option') if '=' in option)
+
+    if target and conan_settings_for_rust_target(target) is None:
+        return JsonResponse({
+            'error': f'Unknown Rust target: {target}'
+        }, status=400)
 
     package = get_object_or_404(Package, name=package_name)
     package_version = get_object_or_404(PackageVersion, package=package, version=version)
@@ -1278,6 +1256,10 @@ def get_rust_crate_by_settings_api(request, package_name, version):
     # Find matching binary
     binaries = BinaryPackage.objects.filter(package_version=package_version)
 
+    if target:
+        # Binaries uploaded before rust_target was recorded are matched by their settings
+        matching_ids = [binary.id for binary in binaries if rust_target_for_binary(binary) == target]
+        binaries = binaries.filter(id__in=matching_ids)
     if os_name:
         binaries = binaries.filter(os=os_name)
     if arch:
@@ -1288,6 +1270,12 @@ def get_rust_crate_by_settings_api(request, package_name, version):
         binaries = binaries.filter(compiler_version=compiler_version)
     if build_type:
         binaries = binaries.filter(build_type=build_type)
+    if options:
+        matching_ids = [
+            binary.id for binary in binaries
+            if all(str((binary.options or {}).get(name)) == value for name, value in options.items())
+        ]
+        binaries = binaries.filter(id__in=matching_ids)
 
     # Filter to only those with rust crates
     binaries = binaries.exclude(rust_crate_file='')
@@ -1296,11 +1284,13 @@ def get_rust_crate_by_settings_api(request, package_name, version):
         return JsonResponse({
             'error': 'No matching binary package with Rust crate found',
             'requested': {
+                'target': target,
                 'os': os_name,
                 'arch': arch,
                 'compiler': compiler,
                 'compiler_version': compiler_version,
-                'build_type': build_type
+                'build_type': build_type,
+                'options': options
             }
         }, status=404)
 
@@ -1327,6 +1317,7 @@ def get_rust_crate_by_settings_api(request, package_name, version):
                 dependencies.append({
                     'name': dep_name,
                     'version': dep_version,
+                    'cargo_version': cargo_version(dep_version),
                     'package_id': dep_package_id,
                     'rust_crate_url': f"/packages/{dep_name}/{dep_version}/binaries/{dep_package_id}/rust-crate/"
                 })
@@ -1342,10 +1333,15 @@ def get_rust_crate_by_settings_api(request, package_name, version):
             'arch': binary.arch,
             'compiler': binary.compiler,
             'compiler_version': binary.compiler_version,
-            'build_type': binary.build_type
+            'compiler_libcxx': binary.compiler_libcxx,
+            'compiler_runtime': binary.compiler_runtime,
+            'build_type': binary.build_type,
+            'rust_target': rust_target_for_binary(binary),
+            'options': binary.options or {}
         },
         'rust_crate': {
             'crate_name': f"{package_name.replace('_', '-')}-sys",
+            'cargo_version': cargo_version_for(package_version),
             'download_url': f"/packages/{package_name}/{version}/binaries/{binary.package_id}/rust-crate/"
         },
         'dependencies': dependencies
@@ -2,6 +2,8 @@ from django.shortcuts import render, get_object_or_404
 from django.db.models import Q
 from django.core.paginator import Paginator
 from packages.models import Package, PackageVersion, Topic
+from packages.cargo_versions import version_requirement, cargo_version_for
+from packages.crate_docs import served_crate
 
 
 def package_list(request):
@@ -109,12 +111,21 @@ def package_detail(request, package_name):
                 'bundle_packages': bundle_packages
             })
 
+    # Rustdoc of the selected version's crate (see packages/crate_docs.py)
+    rust_docs_crate = None
+    if selected_version and selected_version.rust_docs_path:
+        served = served_crate(selected_version)
+        rust_docs_crate = served[0] if served else None
+
     context = {
         'package': package,
         'versions': versions,
         'selected_version': selected_version,
+        'selected_cargo_version': cargo_version_for(selected_version) if selected_version else '',
+        'selected_cargo_requirement': version_requirement(cargo_version_for(selected_version)) if selected_version else '',
         'binaries': binaries,
         'binaries_with_deps': binaries_with_deps,
         'topics': package.get_topics_list(),
+        'rust_docs_crate': rust_docs_crate,
     }
     return render(request, 'packages/package_detail.html', context)
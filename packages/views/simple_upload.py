@@ -8,6 +8,12 @@ from django.views.decorators.csrf import csrf_exempt
 from django.views.decorators.http import require_http_methods
 from django.core.files.base import ContentFile
 from packages.models import Package, PackageVersion, BinaryPackage
+from packages.rust_targets import rust_target_for_settings
+from packages.cargo_versions import cargo_version, cargo_version_for
+from packages.cargo_registry import PUBLISHED_PACKAGE_PREFIX, PublishError, crate_name_for_package, validate_crate_archive
+from packages.crate_generator import schedule_crate_generation
+from packages.symbol_index import schedule_symbol_indexing
+from packages.crate_docs import schedule_docs_build
 import json
 import hashlib
 import tarfile
@@ -54,14 +60,20 @@ def parse_conanfile(recipe_content):
     if license_match:
         metadata['license'] = license_match.group(1)
 
-    # Extract dependencies (basic parsing - looks for requires = [...])
+    # Extract dependencies (basic parsing - looks for requires = [...] and self.requires("..."))
     # This is simplified and won't handle all cases
-    requires_match = re.search(r'requires\s*=\s*\[(.*?)\]', recipe_content, re.DOTALL)
+    requires_match = re.search(r'^\s*requires\s*=\s*[\[(]((?:\s*["\'][^"\']*["\']\s*,?)*)\s*[\])]', recipe_content, re.MULTILINE)
     if requires_match:
         requires_str = requires_match.group(1)
         # Extract quoted strings
         deps = re.findall(r'["\']([^"\']+)["\']', requires_str)
         metadata['dependencies'] = deps
+    requires_str_match = re.search(r'^\s*requires\s*=\s*["\']([^"\']+)["\']', recipe_content, re.MULTILINE)
+    if requires_str_match:
+        metadata['dependencies'] = [requires_str_match.group(1)]
+    for dep in re.findall(r'self\.requires\(\s*["\']([^"\']+)["\']', recipe_content):
+        if dep not in metadata['dependencies']:
+            metadata['dependencies'].append(dep)
 
     return metadata
 
@@ -75,14 +87,20 @@ def extract_conaninfo(binary_file):
     - arch: architecture
     - compiler: compiler name
     - compiler_version: compiler version
+    - compiler_libcxx: C++ standard library (empty for C libraries and MSVC)
+    - compiler_runtime: MSVC runtime (empty for other compilers)
     - build_type: build type (Release, Debug, etc.)
+    - options: dict of the package's options (e.g., {'shared': 'False'})
     """
     settings = {
         'os': 'Linux',
         'arch': 'x86_64',
         'compiler': 'gcc',
         'compiler_version': '11',
-        'build_type': 'Release'
+        'compiler_libcxx': '',
+        'compiler_runtime': '',
+        'build_type': 'Release',
+        'options': {}
     }
 
     try:
@@ -104,10 +122,18 @@ def extract_conaninfo(binary_file):
                         # os=Linux
                         # arch=x86_64
                         # ...
+                        # [options]
+                        # shared=False
 
+                        section = None
                         for line in content.split('\n'):
                             line = line.strip()
-                            if '=' in line:
+                            if line.startswith('[') and line.endswith(']'):
+                                section = line[1:-1]
+                            elif section == 'options' and '=' in line:
+                                key, value = line.split('=', 1)
+                                settings['options'][key.strip()] = value.strip()
+                            elif '=' in line:
                                 key, value = line.split('=', 1)
                                 key = key.strip()
                                 value = value.strip()
@@ -120,6 +146,10 @@ def extract_conaninfo(binary_file):
                                     settings['compiler'] = value
                                 elif key == 'compiler.version':
                                     settings['compiler_version'] = value
+                                elif key == 'compiler.libcxx':
+                                    settings['compiler_libcxx'] = value
+                                elif key == 'compiler.runtime':
+                                    settings['compiler_runtime'] = value
                                 elif key == 'build_type':
                                     settings['build_type'] = value
                         break
@@ -177,6 +207,25 @@ def upload_package(request):
                 'message': 'Missing required fields: package_name and version must be provided in POST data'
             }, status=400)
 
+        # Conan names can't have it: the prefix is the namespace of crates published with cargo
+        if package_name.startswith(PUBLISHED_PACKAGE_PREFIX):
+            return JsonResponse({
+                'status': 'error',
+                'message': f"Invalid package name: {package_name}"
+            }, status=400)
+
+        # Rust crates are served to cargo as uploaded: check the {name}-{cargo version}/ layout first
+        if 'rust_crate' in request.FILES:
+            rust_crate_file = request.FILES['rust_crate']
+            try:
+                validate_crate_archive(rust_crate_file.read(), crate_name_for_package(package_name), cargo_version(version))
+            except PublishError as e:
+                return JsonResponse({
+                    'status': 'error',
+                    'message': f"{e} (regenerate the crate with the current conancrates CLI)"
+                }, status=400)
+            rust_crate_file.seek(0)
+
         # Parse conanfile for description and license (still useful)
         metadata = parse_conanfile(recipe_content)
         description = metadata.get('description', '')
@@ -208,6 +257,7 @@ def upload_package(request):
             package=package,
             version=version,
             defaults={
+                'cargo_version': cargo_version(version),
                 'recipe_content': recipe_content,
                 'description': description,
                 'conan_version': conan_version,
@@ -220,6 +270,7 @@ def upload_package(request):
             package_version.recipe_content = recipe_content
             package_version.description = description
             package_version.conan_version = conan_version
+            package_version.cargo_version = cargo_version_for(package_version)
             package_version.save()
 
         # Get package_id from client if provided, otherwise generate from settings
@@ -255,7 +306,11 @@ def upload_package(request):
                 'arch': settings['arch'],
                 'compiler': settings['compiler'],
                 'compiler_version': settings['compiler_version'],
+                'compiler_libcxx': settings['compiler_libcxx'],
+                'compiler_runtime': settings['compiler_runtime'],
                 'build_type': settings['build_type'],
+                'rust_target': rust_target_for_settings(settings['os'], settings['arch'], settings['compiler']) or '',
+                'options': settings['options'],
                 'sha256': sha256,
                 'file_size': binary_file.size,
                 'dependency_graph': dependency_graph
@@ -272,45 +327,66 @@ def upload_package(request):
         binary.sha256 = sha256
         binary.file_size = binary_file.size
         binary.dependency_graph = dependency_graph  # Update graph even if binary exists
+        binary.options = settings['options']
 
         # Save Rust crate file if provided
         if 'rust_crate' in request.FILES:
             rust_crate_file = request.FILES['rust_crate']
             crate_name = package_name.replace('_', '-')
+
+            # Record checksum for the Cargo index (cksum field)
+            crate_sha256 = hashlib.sha256()
+            for chunk in rust_crate_file.chunks():
+                crate_sha256.update(chunk)
+            rust_crate_file.seek(0)
+
             binary.rust_crate_file.save(
-                f"{crate_name}-sys-{version}.crate",
+                f"{crate_name}-sys-{cargo_version_for(package_version)}.crate",
                 rust_crate_file,
                 save=False
             )
+            binary.rust_crate_sha256 = crate_sha256.hexdigest()
+            # Read from the new archive when the index needs it
+            binary.rust_crate_header_only = None
 
         binary.save()
 
+        # Uploaded with --no-rust: generate the crate on the server
+        schedule_crate_generation(binary)
+
+        # Index its symbols and headers for /symbols/
+        schedule_symbol_indexing(binary)
+
         # Create dependencies from metadata (if parsed from requires field)
         from packages.models import Dependency
         for dep_str in metadata.get('dependencies', []):
-            # Parse dependency string (e.g., "boost/1.81.0")
+            # Parse dependency string (e.g., "boost/1.81.0" or "zlib/[>=1.2 <2]@user/channel")
             if '/' in dep_str:
-                dep_name, dep_version = dep_str.split('/', 1)
+                dep_name, dep_requirement = dep_str.split('/', 1)
                 dep_package, _ = Package.objects.get_or_create(
                     name=dep_name,
                     defaults={'description': f'Dependency: {dep_name}', 'license': 'Unknown'}
                 )
-                dep_version_obj, _ = PackageVersion.objects.get_or_create(
-                    package=dep_package,
-                    version=dep_version
-                )
 
-                Dependency.objects.get_or_create(
+                # Keep the range as written: the Cargo index translates it into a requirement
+                Dependency.objects.update_or_create(
                     package_version=package_version,
-                    depends_on=dep_version_obj
+                    requires_package=dep_package,
+                    dependency_type='requires',
+                    defaults={'version_requirement': dep_requirement.split('#', 1)[0].split('@', 1)[0]}
                 )
 
+        # Uploaded with a crate: build its docs (generated crates get theirs once generated)
+        if 'rust_crate' in request.FILES:
+            schedule_docs_build(package_version)
+
         return JsonResponse({
             'status': 'success',
             'message': f'Package {package_name}/{version} uploaded successfully',
             'package': {
                 'name': package_name,
                 'version': version,
+                'cargo_version': package_version.cargo_version,
                 'package_id': package_id,
                 'sha256': sha256,
                 'size': binary_file.size,
@@ -0,0 +1,90 @@
+"""
+Symbol and header search: which package/version/binary provides a symbol or
+a header (see packages/symbol_index.py)
+"""
+from django.http import JsonResponse
+from django.shortcuts import render
+from django.urls import reverse
+from django.utils.http import urlencode
+from django.views.decorators.http import require_http_methods
+from packages.models import BinaryPackage
+from packages.symbol_index import search_symbols
+
+
+# Most matches returned by a search
+SEARCH_LIMIT = 200
+
+
+def run_search(request):
+    """
+    Run the search of a request's q, kind, os and arch parameters.
+
+    Returns:
+        Tuple of (kind, matches up to SEARCH_LIMIT, whether there were more),
+        kind is None without a query
+    """
+    query = request.GET.get('q', '').strip()
+    if not query:
+        return None, [], False
+    kind = request.GET.get('kind') if request.GET.get('kind') in ('symbol', 'header') else None
+    kind, matches = search_symbols(query, kind, request.GET.get('os', ''), request.GET.get('arch', ''))
+    matches = list(matches[:SEARCH_LIMIT + 1])
+    return kind, matches[:SEARCH_LIMIT], len(matches) > SEARCH_LIMIT
+
+
+def symbol_search(request):
+    """Search page: which package provides SSL_CTX_new or <zlib.h>?"""
+    kind, matches, truncated = run_search(request)
+
+    context = {
+        'search_query': request.GET.get('q', ''),
+        'kind': kind,
+        'matches': matches,
+        'truncated': truncated,
+        'current_kind': request.GET.get('kind', ''),
+        'current_os': request.GET.get('os', ''),
+        'current_arch': request.GET.get('arch', ''),
+        'all_os': BinaryPackage.objects.exclude(os='').values_list('os', flat=True).distinct().order_by('os'),
+        'all_arch': BinaryPackage.objects.exclude(arch='').values_list('arch', flat=True).distinct().order_by('arch'),
+    }
+    return render(request, 'packages/symbol_search.html', context)
+
+
+@require_http_methods(["GET"])
+def symbol_search_api(request):
+    """
+    API endpoint searching the symbols and headers of all binaries.
+
+    URL: /api/symbols?q={symbol or header}[&kind=symbol|header][&os=Linux][&arch=x86_64]
+
+    Returns JSON with the binaries providing it, at most SEARCH_LIMIT.
+    """
+    if not request.GET.get('q', '').strip():
+        return JsonResponse({'error': 'Missing search query (q)'}, status=400)
+    kind, matches, truncated = run_search(request)
+
+    results = []
+    for match in matches:
+        binary = match.binary
+        package_version = binary.package_version
+        package_url = reverse('packages:package_detail', args=[package_version.package.name])
+        results.append({
+            'package': package_version.package.name,
+            'version': package_version.version,
+            'package_id': binary.package_id,
+            'os': binary.os,
+            'arch': binary.arch,
+            'compiler': binary.compiler,
+            'compiler_version': binary.compiler_version,
+            'build_type': binary.build_type,
+            'name': match.name,
+            'library': match.library,
+            'url': f"{package_url}?{urlencode({'version': package_version.version})}",
+        })
+
+    return JsonResponse({
+        'query': request.GET['q'].strip(),
+        'kind': kind,
+        'results': results,
+        'truncated': truncated,
+    })
@@ -10,6 +10,9 @@ from django.contrib.auth.decorators import login_required
 from django.shortcuts import get_object_or_404
 from django.core.files.base import ContentFile
 from packages.models import Package, PackageVersion, BinaryPackage
+from packages.cargo_versions import cargo_version
+from packages.crate_generator import schedule_crate_generation
+from packages.symbol_index import schedule_symbol_indexing
 import json
 import hashlib
 
@@ -64,6 +67,7 @@ def upload_recipe(request, package_name, package_version):
             package=package,
             version=package_version,
             defaults={
+                'cargo_version': cargo_version(package_version),
                 'uploaded_by': request.user if request.user.is_authenticated else None
             }
         )
@@ -125,6 +129,12 @@ def upload_package(request, package_name, package_version, package_id):
             binary.file_size = uploaded_file.size
             binary.save()
 
+            # Conan clients don't send crates: generate it on the server
+            schedule_crate_generation(binary)
+
+            # Index its symbols and headers for /symbols/
+            schedule_symbol_indexing(binary)
+
             return JsonResponse({
                 "status": "ok",
                 "message": f"Binary {package_name}/{package_version}:{package_id} uploaded successfully",